@@ -1,23 +1,75 @@
-use crate::Ironbar;
 use crate::config::{BarConfig, BarPosition, MarginConfig, ModuleConfig};
-use crate::modules::{BarModuleFactory, ModuleInfo, ModuleLocation};
+use crate::modules::{BarModuleFactory, ModuleInfo, ModuleLocation, ModuleRef};
 use crate::popup::Popup;
+use crate::{Ironbar, rc_mut};
+#[cfg(feature = "ipc")]
+use color_eyre::Report;
 use color_eyre::Result;
 use glib::Propagation;
 use gtk::gdk::Monitor;
 use gtk::prelude::*;
 use gtk::{Application, ApplicationWindow, IconTheme, Orientation, Window, WindowType};
 use gtk_layer_shell::LayerShell;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::time::Duration;
-use tracing::{debug, info};
+use tracing::{debug, error, info};
 
 #[derive(Debug, Clone)]
 enum Inner {
-    New { config: Option<BarConfig> },
-    Loaded { popup: Rc<Popup> },
+    New {
+        config: Option<BarConfig>,
+    },
+    Loaded {
+        popup: Rc<Popup>,
+        monitor: Monitor,
+        icon_theme: IconTheme,
+        hotspot_window: Option<Window>,
+        /// The bar config, with the modules removed.
+        settings: Rc<BarConfig>,
+        modules: Rc<RefCell<LoadedModules>>,
+    },
+}
+
+/// A module which has been loaded onto the bar,
+/// alongside the config used to create it.
+#[derive(Debug, Clone)]
+struct LoadedModule {
+    config: ModuleConfig,
+    module: ModuleRef,
+}
+
+/// The modules currently loaded into each section of the bar.
+#[derive(Debug, Default)]
+struct LoadedModules {
+    start: Vec<LoadedModule>,
+    center: Vec<LoadedModule>,
+    end: Vec<LoadedModule>,
+}
+
+impl LoadedModule {
+    /// Removes the module from `container` and stops its tasks.
+    fn unload(&self, container: &gtk::Box, popup: &Popup) {
+        container.remove(&self.module.widget);
+        popup.unregister_content(self.module.id);
+        self.module.tasks.abort();
+    }
+}
+
+impl LoadedModules {
+    fn iter(&self) -> impl Iterator<Item = &LoadedModule> {
+        self.start.iter().chain(&self.center).chain(&self.end)
+    }
+
+    fn section_mut(&mut self, location: ModuleLocation) -> &mut Vec<LoadedModule> {
+        match location {
+            ModuleLocation::Left => &mut self.start,
+            ModuleLocation::Center => &mut self.center,
+            ModuleLocation::Right => &mut self.end,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -86,6 +138,27 @@ impl Bar {
 
         window.add(&content);
 
+        #[cfg(feature = "ipc")]
+        {
+            use crate::ipc::events::{self, Event};
+
+            let bar = name.clone();
+            window.connect_show(move |_| {
+                events::emit(Event::BarVisibility {
+                    bar: bar.clone(),
+                    visible: true,
+                });
+            });
+
+            let bar = name.clone();
+            window.connect_hide(move |_| {
+                events::emit(Event::BarVisibility {
+                    bar: bar.clone(),
+                    visible: false,
+                });
+            });
+        }
+
         window.connect_destroy_event(|_, _| {
             info!("Shutting down");
             gtk::main_quit();
@@ -115,7 +188,7 @@ impl Bar {
             return Ok(self);
         };
 
-        let Some(config) = config.take() else {
+        let Some(mut config) = config.take() else {
             return Ok(self);
         };
 
@@ -137,7 +210,7 @@ impl Bar {
             monitor,
         );
 
-        if let Some(autohide) = config.autohide {
+        let hotspot_window = config.autohide.map(|autohide| {
             let hotspot_window = Window::new(WindowType::Toplevel);
             Self::setup_autohide(&self.window, &hotspot_window, autohide);
             self.setup_layer_shell(
@@ -152,18 +225,176 @@ impl Bar {
             if start_hidden {
                 hotspot_window.show();
             }
+
+            hotspot_window
+        });
+
+        let icon_theme = IconTheme::new();
+        if let Some(ref theme) = config.icon_theme {
+            icon_theme.set_custom_theme(Some(theme));
         }
 
-        let load_result = self.load_modules(config, monitor, self.monitor_size)?;
+        let app = &self.window.application().expect("to exist");
+
+        // popup ignores module location so can bodge this for now
+        let popup = Popup::new(
+            self.ironbar.clone(),
+            &self.module_info(app, monitor, &icon_theme, ModuleLocation::Left),
+            self.monitor_size,
+            config.popup_gap,
+        );
+        let popup = Rc::new(popup);
+
+        let mut modules = LoadedModules::default();
+
+        for (location, section) in [
+            (ModuleLocation::Left, config.start.take()),
+            (ModuleLocation::Center, config.center.take()),
+            (ModuleLocation::Right, config.end.take()),
+        ] {
+            if let Some(section) = section {
+                self.attach_container(location);
+
+                let info = self.module_info(app, monitor, &icon_theme, location);
+                *modules.section_mut(location) = add_modules(
+                    self.container(location),
+                    section,
+                    &info,
+                    &self.ironbar,
+                    &popup,
+                )?;
+            }
+        }
 
         self.show(!start_hidden);
 
         self.inner = Inner::Loaded {
-            popup: load_result.popup,
+            popup,
+            monitor: monitor.clone(),
+            icon_theme,
+            hotspot_window,
+            settings: Rc::new(config),
+            modules: rc_mut!(modules),
         };
         Ok(self)
     }
 
+    /// Checks whether the bar can be updated in place to match `config`.
+    ///
+    /// This is only possible if none of the bar-level options have changed,
+    /// as only the modules can be swapped out on a running bar.
+    pub fn can_reload(&self, config: &BarConfig) -> bool {
+        match &self.inner {
+            Inner::New { .. } => false,
+            Inner::Loaded { settings, .. } => settings.settings_eq(config),
+        }
+    }
+
+    /// Updates the bar's modules to match `config`.
+    ///
+    /// Modules whose config is unchanged are kept running.
+    /// Any new or changed modules are created,
+    /// and any which are no longer present are removed.
+    ///
+    /// Check [`Bar::can_reload`] before calling this.
+    pub fn reload(&self, mut config: BarConfig) {
+        let Inner::Loaded {
+            popup,
+            monitor,
+            icon_theme,
+            modules,
+            ..
+        } = &self.inner
+        else {
+            return;
+        };
+
+        info!("Reloading bar '{}' on '{}'", self.name, self.monitor_name);
+
+        let app = &self.window.application().expect("to exist");
+        let mut modules = modules.borrow_mut();
+
+        for (location, section) in [
+            (ModuleLocation::Left, config.start.take()),
+            (ModuleLocation::Center, config.center.take()),
+            (ModuleLocation::Right, config.end.take()),
+        ] {
+            if section.is_some() {
+                self.attach_container(location);
+            }
+
+            let info = self.module_info(app, monitor, icon_theme, location);
+            self.reload_section(
+                modules.section_mut(location),
+                section.unwrap_or_default(),
+                &info,
+                popup,
+            );
+        }
+    }
+
+    /// Reconciles the modules loaded into a section against a new list of module configs.
+    ///
+    /// Loaded modules with an identical config are re-used and moved into position.
+    fn reload_section(
+        &self,
+        loaded: &mut Vec<LoadedModule>,
+        configs: Vec<ModuleConfig>,
+        info: &ModuleInfo,
+        popup: &Rc<Popup>,
+    ) {
+        let container = self.container(info.location);
+        let module_factory = BarModuleFactory::new(self.ironbar.clone(), popup.clone()).into();
+
+        let mut previous = std::mem::take(loaded);
+
+        for config in configs {
+            let module = if let Some(index) = previous.iter().position(|m| m.config == config) {
+                previous.remove(index)
+            } else {
+                match config.clone().create(&module_factory, container, info) {
+                    Ok(module) => LoadedModule { config, module },
+                    Err(err) => {
+                        error!("{err:?}");
+                        continue;
+                    }
+                }
+            };
+
+            container.reorder_child(&module.module.widget, loaded.len() as i32);
+            loaded.push(module);
+        }
+
+        for module in previous {
+            module.unload(container, popup);
+        }
+    }
+
+    /// Closes the bar, alongside its popup and auto-hide windows.
+    pub fn close(&self) {
+        info!("Closing bar '{}' on '{}'", self.name, self.monitor_name);
+
+        if let Inner::Loaded {
+            popup,
+            hotspot_window,
+            modules,
+            ..
+        } = &self.inner
+        {
+            for module in modules.borrow().iter() {
+                module.module.tasks.abort();
+            }
+
+            popup.window.close();
+
+            if let Some(hotspot_window) = hotspot_window {
+                hotspot_window.close();
+            }
+        }
+
+        self.window.close();
+    }
+
     /// Sets up GTK layer shell for a provided application window.
     fn setup_layer_shell(
         &self,
@@ -250,67 +481,48 @@ impl Bar {
         }
     }
 
-    /// Loads the configured modules onto a bar.
-    fn load_modules(
-        &self,
-        config: BarConfig,
-        monitor: &Monitor,
-        output_size: (i32, i32),
-    ) -> Result<BarLoadResult> {
-        let icon_theme = IconTheme::new();
-        if let Some(ref theme) = config.icon_theme {
-            icon_theme.set_custom_theme(Some(theme));
-        }
-
-        let app = &self.window.application().expect("to exist");
-
-        macro_rules! info {
-            ($location:expr) => {
-                ModuleInfo {
-                    app,
-                    bar_position: config.position,
-                    monitor,
-                    output_name: &self.monitor_name,
-                    location: $location,
-                    icon_theme: &icon_theme,
-                    icon_overrides: self.icon_overrides.clone(),
-                }
-            };
+    fn module_info<'a>(
+        &'a self,
+        app: &'a Application,
+        monitor: &'a Monitor,
+        icon_theme: &'a IconTheme,
+        location: ModuleLocation,
+    ) -> ModuleInfo<'a> {
+        ModuleInfo {
+            app,
+            bar_position: self.position,
+            monitor,
+            output_name: &self.monitor_name,
+            location,
+            icon_theme,
+            icon_overrides: self.icon_overrides.clone(),
         }
+    }
 
-        // popup ignores module location so can bodge this for now
-        let popup = Popup::new(
-            self.ironbar.clone(),
-            &info!(ModuleLocation::Left),
-            output_size,
-            config.popup_gap,
-        );
-        let popup = Rc::new(popup);
-
-        if let Some(modules) = config.start {
-            self.content.add(&self.start);
-
-            let info = info!(ModuleLocation::Left);
-            add_modules(&self.start, modules, &info, &self.ironbar, &popup)?;
+    /// Gets the container for the section at `location`.
+    fn container(&self, location: ModuleLocation) -> &gtk::Box {
+        match location {
+            ModuleLocation::Left => &self.start,
+            ModuleLocation::Center => &self.center,
+            ModuleLocation::Right => &self.end,
         }
+    }
 
-        if let Some(modules) = config.center {
-            self.content.set_center_widget(Some(&self.center));
-
-            let info = info!(ModuleLocation::Center);
-            add_modules(&self.center, modules, &info, &self.ironbar, &popup)?;
+    /// Adds the container for the section at `location` to the bar,
+    /// if it has not been added already.
+    fn attach_container(&self, location: ModuleLocation) {
+        let container = self.container(location);
+        if container.parent().is_some() {
+            return;
         }
 
-        if let Some(modules) = config.end {
-            self.content.pack_end(&self.end, false, true, 0);
-
-            let info = info!(ModuleLocation::Right);
-            add_modules(&self.end, modules, &info, &self.ironbar, &popup)?;
+        match location {
+            ModuleLocation::Left => self.content.add(container),
+            ModuleLocation::Center => self.content.set_center_widget(Some(container)),
+            ModuleLocation::Right => self.content.pack_end(container, false, true, 0),
         }
 
-        let result = BarLoadResult { popup };
-
-        Ok(result)
+        container.show();
     }
 
     fn show(&self, include_window: bool) {
@@ -337,12 +549,164 @@ impl Bar {
         &self.monitor_name
     }
 
+    /// Gets all modules on this bar with the given name.
+    /// Modules without a configured name use their type name.
+    #[cfg(feature = "ipc")]
+    pub fn modules_by_name(&self, name: &str) -> Vec<ModuleRef> {
+        match &self.inner {
+            Inner::New { .. } => vec![],
+            Inner::Loaded { modules, .. } => modules
+                .borrow()
+                .iter()
+                .filter(|loaded| loaded.module.name == name)
+                .map(|loaded| loaded.module.clone())
+                .collect(),
+        }
+    }
+
+    /// Creates a module from `config` and adds it to the section at `location`.
+    ///
+    /// The module is inserted at `index`, or at the end of the section if not provided.
+    #[cfg(feature = "ipc")]
+    pub fn add_module(
+        &self,
+        config: ModuleConfig,
+        location: ModuleLocation,
+        index: Option<usize>,
+    ) -> Result<()> {
+        let Inner::Loaded {
+            popup,
+            monitor,
+            icon_theme,
+            modules,
+            ..
+        } = &self.inner
+        else {
+            return Err(Report::msg("Bar is not loaded"));
+        };
+
+        self.attach_container(location);
+
+        let app = &self.window.application().expect("to exist");
+        let info = self.module_info(app, monitor, icon_theme, location);
+        let container = self.container(location);
+
+        let module_factory = BarModuleFactory::new(self.ironbar.clone(), popup.clone()).into();
+        let module = config.clone().create(&module_factory, container, &info)?;
+
+        let mut modules = modules.borrow_mut();
+        let section = modules.section_mut(location);
+        let index = index.map_or(section.len(), |index| index.min(section.len()));
+
+        container.reorder_child(&module.widget, index as i32);
+        section.insert(index, LoadedModule { config, module });
+
+        Ok(())
+    }
+
+    /// Removes all modules with the given name from the bar.
+    #[cfg(feature = "ipc")]
+    pub fn remove_module(&self, name: &str) -> Result<()> {
+        let Inner::Loaded { popup, modules, .. } = &self.inner else {
+            return Err(Report::msg("Bar is not loaded"));
+        };
+
+        let mut modules = modules.borrow_mut();
+        let mut found = false;
+
+        for location in [
+            ModuleLocation::Left,
+            ModuleLocation::Center,
+            ModuleLocation::Right,
+        ] {
+            let container = self.container(location);
+
+            modules.section_mut(location).retain(|loaded| {
+                if loaded.module.name != name {
+                    return true;
+                }
+
+                loaded.unload(container, popup);
+                found = true;
+                false
+            });
+        }
+
+        if found {
+            Ok(())
+        } else {
+            Err(Report::msg("Invalid module name"))
+        }
+    }
+
+    /// Moves the first module with the given name into the section at `location`.
+    ///
+    /// The module is placed at `index`, or at the end of the section if not provided.
+    /// Moving between sections rebuilds the module,
+    /// so that its popup and orientation match the new section.
+    #[cfg(feature = "ipc")]
+    pub fn move_module(
+        &self,
+        name: &str,
+        location: ModuleLocation,
+        index: Option<usize>,
+    ) -> Result<()> {
+        let Inner::Loaded { popup, modules, .. } = &self.inner else {
+            return Err(Report::msg("Bar is not loaded"));
+        };
+
+        let found = {
+            let mut modules = modules.borrow_mut();
+
+            [
+                ModuleLocation::Left,
+                ModuleLocation::Center,
+                ModuleLocation::Right,
+            ]
+            .into_iter()
+            .find_map(|from| {
+                let section = modules.section_mut(from);
+                let position = section
+                    .iter()
+                    .position(|loaded| loaded.module.name == name)?;
+
+                Some((from, position, section[position].config.clone()))
+            })
+        };
+
+        let Some((from, position, config)) = found else {
+            return Err(Report::msg("Invalid module name"));
+        };
+
+        if from == location {
+            let mut modules = modules.borrow_mut();
+            let section = modules.section_mut(location);
+
+            let module = section.remove(position);
+            let index = index.map_or(section.len(), |index| index.min(section.len()));
+
+            self.container(location)
+                .reorder_child(&module.module.widget, index as i32);
+            section.insert(index, module);
+
+            return Ok(());
+        }
+
+        // the new module is created first so the old one is kept if this fails
+        self.add_module(config, location, index)?;
+
+        let module = modules.borrow_mut().section_mut(from).remove(position);
+        module.unload(self.container(from), popup);
+
+        Ok(())
+    }
+
     pub fn popup(&self) -> Rc<Popup> {
         match &self.inner {
             Inner::New { .. } => {
                 panic!("Attempted to get popup of uninitialized bar. This is a serious bug!")
             }
-            Inner::Loaded { popup } => popup.clone(),
+            Inner::Loaded { popup, .. } => popup.clone(),
         }
     }
 
@@ -376,11 +740,6 @@ fn create_container(name: &str, orientation: Orientation) -> gtk::Box {
     container
 }
 
-#[derive(Debug)]
-struct BarLoadResult {
-    popup: Rc<Popup>,
-}
-
 /// Adds modules into a provided GTK box,
 /// which should be one of its left, center or right containers.
 fn add_modules(
@@ -389,14 +748,16 @@ fn add_modules(
     info: &ModuleInfo,
     ironbar: &Rc<Ironbar>,
     popup: &Rc<Popup>,
-) -> Result<()> {
+) -> Result<Vec<LoadedModule>> {
     let module_factory = BarModuleFactory::new(ironbar.clone(), popup.clone()).into();
 
-    for config in modules {
-        config.create(&module_factory, content, info)?;
-    }
-
-    Ok(())
+    modules
+        .into_iter()
+        .map(|config| {
+            let module = config.clone().create(&module_factory, content, info)?;
+            Ok(LoadedModule { config, module })
+        })
+        .collect()
 }
 
 pub fn create_bar(
@@ -1,7 +1,8 @@
 use crate::error::ExitCode;
-use crate::ipc::{Command, Response};
-use clap::{Parser, ValueEnum};
+use crate::ipc::{Command as IpcCommand, Response};
+use clap::{Parser, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::process::exit;
 
 #[derive(Parser, Debug, Serialize, Deserialize)]
@@ -31,6 +32,20 @@ pub struct Args {
     sway_bar_id: Option<String>,
 }
 
+#[derive(Subcommand, Debug, Serialize, Deserialize)]
+pub enum Command {
+    /// Validate a config file without starting Ironbar.
+    /// Exits with a non-zero code if any errors are found.
+    Check {
+        /// The path to the config file.
+        /// Defaults to the file Ironbar would load on startup.
+        path: Option<PathBuf>,
+    },
+
+    #[command(flatten)]
+    Ipc(IpcCommand),
+}
+
 #[derive(Debug, Serialize, Deserialize, Default, ValueEnum, Clone, Copy)]
 pub enum Format {
     #[default]
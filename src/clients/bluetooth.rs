@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{register_fallible_client, spawn};
+use color_eyre::{Report, Result};
+use futures_lite::StreamExt;
+use futures_signals::signal::{Mutable, MutableSignalCloned};
+use tokio::select;
+use tracing::error;
+use zbus::fdo::{ManagedObjects, ObjectManagerProxy, PropertiesChanged};
+use zbus::message::Type as MessageType;
+use zbus::names::OwnedInterfaceName;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::{Connection, MatchRule, Message, MessageStream, proxy};
+
+const DBUS_BUS: &str = "org.bluez";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+const ADAPTER_INTERFACE: &str = "org.bluez.Adapter1";
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+const BATTERY_INTERFACE: &str = "org.bluez.Battery1";
+
+#[derive(Debug)]
+pub struct Client {
+    client_state: Mutable<ClientState>,
+    dbus_connection: Connection,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClientState {
+    /// The first Bluetooth adapter, if any are present.
+    pub adapter: Option<Adapter>,
+    /// Devices paired with the adapter,
+    /// with connected devices first.
+    pub devices: Vec<Device>,
+}
+
+impl ClientState {
+    pub fn connected_devices(&self) -> impl Iterator<Item = &Device> {
+        self.devices.iter().filter(|device| device.connected)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Adapter {
+    pub path: String,
+    pub name: String,
+    pub powered: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Device {
+    pub path: String,
+    /// The user-facing device name.
+    pub name: String,
+    pub address: String,
+    /// Freedesktop icon name for the device type, such as `audio-headset`.
+    pub icon: Option<String>,
+    pub connected: bool,
+    /// Battery percentage, for devices which report it.
+    pub battery: Option<u8>,
+}
+
+#[proxy(
+    default_service = "org.bluez",
+    interface = "org.bluez.Adapter1",
+    assume_defaults = false
+)]
+trait AdapterDbus {
+    #[zbus(property)]
+    fn set_powered(&self, powered: bool) -> zbus::Result<()>;
+}
+
+#[proxy(
+    default_service = "org.bluez",
+    interface = "org.bluez.Device1",
+    assume_defaults = false
+)]
+trait DeviceDbus {
+    fn connect(&self) -> zbus::Result<()>;
+
+    fn disconnect(&self) -> zbus::Result<()>;
+}
+
+impl Client {
+    async fn new() -> Result<Self> {
+        let client_state = Mutable::new(ClientState::default());
+        let dbus_connection = Connection::system().await?;
+
+        Ok(Self {
+            client_state,
+            dbus_connection,
+        })
+    }
+
+    async fn run(&self) -> Result<()> {
+        let object_manager = ObjectManagerProxy::builder(&self.dbus_connection)
+            .destination(DBUS_BUS)?
+            .path("/")?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+
+        // subscribe before reading to avoid missing changes in between.
+        let mut added = object_manager.receive_interfaces_added().await?;
+        let mut removed = object_manager.receive_interfaces_removed().await?;
+
+        let mut adapter_changes = self.receive_properties_changed(ADAPTER_INTERFACE).await?;
+        let mut device_changes = self.receive_properties_changed(DEVICE_INTERFACE).await?;
+        let mut battery_changes = self.receive_properties_changed(BATTERY_INTERFACE).await?;
+
+        let mut objects = object_manager.get_managed_objects().await?;
+        self.client_state.set(read_state(&objects));
+
+        loop {
+            let res = select! {
+                Some(signal) = added.next() => signal.args().map(|args| {
+                    let path = OwnedObjectPath::from(args.object_path().clone());
+                    let interfaces = objects.entry(path).or_default();
+
+                    for (interface, properties) in args.interfaces_and_properties() {
+                        interfaces.insert(
+                            OwnedInterfaceName::from(interface.clone()),
+                            to_owned_properties(properties),
+                        );
+                    }
+                }),
+                Some(signal) = removed.next() => signal.args().map(|args| {
+                    let path = OwnedObjectPath::from(args.object_path().clone());
+
+                    if let Some(interfaces) = objects.get_mut(&path) {
+                        for interface in args.interfaces().iter() {
+                            interfaces.remove(interface.as_str());
+                        }
+
+                        if interfaces.is_empty() {
+                            objects.remove(&path);
+                        }
+                    }
+                }),
+                Some(message) = adapter_changes.next() => {
+                    apply_properties_changed(&mut objects, message?)
+                }
+                Some(message) = device_changes.next() => {
+                    apply_properties_changed(&mut objects, message?)
+                }
+                Some(message) = battery_changes.next() => {
+                    apply_properties_changed(&mut objects, message?)
+                }
+                else => break,
+            };
+
+            if let Err(err) = res {
+                error!("Failed to read bluetooth signal: {err:?}");
+                continue;
+            }
+
+            let state = read_state(&objects);
+            if self.client_state.get_cloned() != state {
+                self.client_state.set(state);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates a stream of `PropertiesChanged` signals
+    /// for `interface` on any BlueZ object.
+    async fn receive_properties_changed(&self, interface: &'static str) -> Result<MessageStream> {
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .sender(DBUS_BUS)?
+            .interface(PROPERTIES_INTERFACE)?
+            .member("PropertiesChanged")?
+            .arg(0, interface)?
+            .build();
+
+        let stream = MessageStream::for_match_rule(rule, &self.dbus_connection, None).await?;
+        Ok(stream)
+    }
+
+    pub fn subscribe(&self) -> MutableSignalCloned<ClientState> {
+        self.client_state.signal_cloned()
+    }
+
+    /// Gets the current adapter and device state.
+    pub fn state(&self) -> ClientState {
+        self.client_state.get_cloned()
+    }
+
+    /// Turns the adapter on or off.
+    pub async fn set_powered(&self, powered: bool) -> Result<()> {
+        let adapter = self
+            .state()
+            .adapter
+            .map(|adapter| adapter.path)
+            .ok_or_else(|| Report::msg("No Bluetooth adapter found"))?;
+
+        let proxy = AdapterDbusProxy::builder(&self.dbus_connection)
+            .path(adapter)?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+
+        proxy.set_powered(powered).await?;
+        Ok(())
+    }
+
+    /// Connects to the paired device at `path`.
+    pub async fn connect(&self, path: &str) -> Result<()> {
+        self.device_proxy(path).await?.connect().await?;
+        Ok(())
+    }
+
+    /// Disconnects from the device at `path`.
+    pub async fn disconnect(&self, path: &str) -> Result<()> {
+        self.device_proxy(path).await?.disconnect().await?;
+        Ok(())
+    }
+
+    async fn device_proxy(&self, path: &str) -> Result<DeviceDbusProxy<'static>> {
+        let proxy = DeviceDbusProxy::builder(&self.dbus_connection)
+            .path(path.to_string())?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+
+        Ok(proxy)
+    }
+}
+
+type Properties = HashMap<String, OwnedValue>;
+
+fn to_owned_properties(properties: &HashMap<&str, Value>) -> Properties {
+    properties
+        .iter()
+        .filter_map(|(key, value)| {
+            value
+                .try_to_owned()
+                .ok()
+                .map(|value| ((*key).to_string(), value))
+        })
+        .collect()
+}
+
+/// Updates the cached properties of the object which sent a `PropertiesChanged` signal.
+fn apply_properties_changed(objects: &mut ManagedObjects, message: Message) -> zbus::Result<()> {
+    let Some(path) = message
+        .header()
+        .path()
+        .map(|path| OwnedObjectPath::from(path.clone()))
+    else {
+        return Ok(());
+    };
+
+    let Some(signal) = PropertiesChanged::from_message(message) else {
+        return Ok(());
+    };
+
+    let args = signal.args()?;
+
+    // changes to objects which have not been added yet are caught by `InterfacesAdded`
+    let Some(properties) = objects
+        .get_mut(&path)
+        .and_then(|interfaces| interfaces.get_mut(args.interface_name().as_str()))
+    else {
+        return Ok(());
+    };
+
+    properties.extend(to_owned_properties(args.changed_properties()));
+
+    for property in args.invalidated_properties().iter() {
+        properties.remove(*property);
+    }
+
+    Ok(())
+}
+
+fn get_str(properties: &Properties, key: &str) -> Option<String> {
+    properties
+        .get(key)
+        .and_then(|value| value.downcast_ref::<&str>().ok())
+        .map(ToString::to_string)
+}
+
+fn get_bool(properties: &Properties, key: &str) -> bool {
+    properties
+        .get(key)
+        .and_then(|value| value.downcast_ref::<bool>().ok())
+        .unwrap_or_default()
+}
+
+/// Builds the client state from BlueZ's managed objects.
+fn read_state(objects: &ManagedObjects) -> ClientState {
+    let interface = |path: &OwnedObjectPath, name: &str| {
+        objects.get(path).and_then(|interfaces| {
+            interfaces
+                .iter()
+                .find(|(interface, _)| interface.as_str() == name)
+                .map(|(_, properties)| properties)
+        })
+    };
+
+    let mut adapters = objects
+        .iter()
+        .filter_map(|(path, _)| interface(path, ADAPTER_INTERFACE).map(|props| (path, props)))
+        .collect::<Vec<_>>();
+
+    adapters.sort_by_key(|(path, _)| path.as_str());
+
+    let Some((adapter_path, adapter)) = adapters.first() else {
+        return ClientState::default();
+    };
+
+    let adapter = Adapter {
+        path: adapter_path.to_string(),
+        name: get_str(adapter, "Alias").unwrap_or_else(|| adapter_path.to_string()),
+        powered: get_bool(adapter, "Powered"),
+    };
+
+    let mut devices = objects
+        .keys()
+        .filter_map(|path| {
+            let device = interface(path, DEVICE_INTERFACE)?;
+
+            let is_adapter_device = device
+                .get("Adapter")
+                .and_then(|value| value.downcast_ref::<ObjectPath>().ok())
+                .is_some_and(|path| path.as_str() == adapter.path);
+
+            if !is_adapter_device || !get_bool(device, "Paired") {
+                return None;
+            }
+
+            let address = get_str(device, "Address").unwrap_or_default();
+
+            Some(Device {
+                path: path.to_string(),
+                name: get_str(device, "Alias").unwrap_or_else(|| address.clone()),
+                address,
+                icon: get_str(device, "Icon"),
+                connected: get_bool(device, "Connected"),
+                battery: interface(path, BATTERY_INTERFACE)
+                    .and_then(|battery| battery.get("Percentage"))
+                    .and_then(|value| value.downcast_ref::<u8>().ok()),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    devices.sort_by(|a, b| {
+        b.connected
+            .cmp(&a.connected)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    ClientState {
+        adapter: Some(adapter),
+        devices,
+    }
+}
+
+/// Registers the `bluetooth` ironvar namespace,
+/// which exposes the adapter state and connected devices.
+#[cfg(feature = "ipc")]
+pub fn register_namespace(client: &Arc<Client>) {
+    use crate::ironvar::ClientNamespace;
+    use futures_signals::signal::SignalExt;
+
+    let namespace = ClientNamespace::register("bluetooth");
+    let mut stream = client.subscribe().to_stream();
+
+    spawn(async move {
+        while let Some(state) = stream.next().await {
+            let powered = state
+                .adapter
+                .as_ref()
+                .is_some_and(|adapter| adapter.powered);
+
+            let connected = state
+                .connected_devices()
+                .map(|device| device.name.as_str())
+                .collect::<Vec<_>>();
+
+            namespace.set("powered", powered);
+            namespace.set("connected_count", connected.len());
+            namespace.set("connected", connected.join(","));
+        }
+    });
+}
+
+pub async fn create_client() -> Result<Arc<Client>> {
+    let client = Arc::new(Client::new().await?);
+    {
+        let client = client.clone();
+        spawn(async move {
+            if let Err(error) = client.run().await {
+                error!("{}", error);
+            };
+        });
+    }
+    Ok(client)
+}
+
+register_fallible_client!(Client, bluetooth);
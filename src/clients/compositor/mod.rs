@@ -207,6 +207,44 @@ pub trait WorkspaceClient: Debug + Send + Sync {
 #[cfg(feature = "workspaces")]
 register_fallible_client!(dyn WorkspaceClient, workspaces);
 
+/// Registers the `workspace` ironvar namespace,
+/// which exposes the currently focused workspace.
+#[cfg(all(feature = "workspaces", feature = "ipc"))]
+pub fn register_workspace_namespace(client: &Arc<dyn WorkspaceClient>) {
+    use crate::ironvar::ClientNamespace;
+    use tokio::sync::broadcast::error::RecvError;
+
+    let namespace = ClientNamespace::register("workspace");
+    let mut rx = client.subscribe();
+
+    crate::spawn(async move {
+        let mut focused = None;
+
+        let set_focused = |workspace: &Workspace| {
+            namespace.set("id", workspace.id);
+            namespace.set("name", &workspace.name);
+            namespace.set("monitor", &workspace.monitor);
+            Some(workspace.id)
+        };
+
+        loop {
+            match rx.recv().await {
+                Ok(WorkspaceUpdate::Init(workspaces)) => {
+                    if let Some(workspace) = workspaces.iter().find(|w| w.visibility.is_focused()) {
+                        focused = set_focused(workspace);
+                    }
+                }
+                Ok(WorkspaceUpdate::Focus { new, .. }) => focused = set_focused(&new),
+                Ok(WorkspaceUpdate::Rename { id, name }) if focused == Some(id) => {
+                    namespace.set("name", name);
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
 #[cfg(feature = "keyboard")]
 pub trait KeyboardLayoutClient: Debug + Send + Sync {
     /// Switches to the next layout.
@@ -218,3 +256,24 @@ pub trait KeyboardLayoutClient: Debug + Send + Sync {
 
 #[cfg(feature = "keyboard")]
 register_fallible_client!(dyn KeyboardLayoutClient, keyboard_layout);
+
+/// Registers the `keyboard` ironvar namespace,
+/// which exposes the active keyboard layout.
+#[cfg(all(feature = "keyboard", feature = "ipc"))]
+pub fn register_keyboard_namespace(client: &Arc<dyn KeyboardLayoutClient>) {
+    use crate::ironvar::ClientNamespace;
+    use tokio::sync::broadcast::error::RecvError;
+
+    let namespace = ClientNamespace::register("keyboard");
+    let mut rx = client.subscribe();
+
+    crate::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(KeyboardLayoutUpdate(layout)) => namespace.set("layout", layout),
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
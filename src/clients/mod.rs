@@ -4,7 +4,11 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::rc::Rc;
 use std::sync::Arc;
+#[cfg(feature = "ipc")]
+use tracing::{debug, error};
 
+#[cfg(feature = "bluetooth")]
+pub mod bluetooth;
 #[cfg(feature = "clipboard")]
 pub mod clipboard;
 #[cfg(any(feature = "keyboard", feature = "workspaces", feature = "hyprland"))]
@@ -17,6 +21,8 @@ pub mod lua;
 pub mod music;
 #[cfg(feature = "network_manager")]
 pub mod networkmanager;
+#[cfg(feature = "power_profile")]
+pub mod power_profiles;
 #[cfg(feature = "sway")]
 pub mod sway;
 #[cfg(feature = "notifications")]
@@ -42,6 +48,8 @@ pub struct Clients {
     sway: Option<Arc<sway::Client>>,
     #[cfg(feature = "hyprland")]
     hyprland: Option<Arc<compositor::hyprland::Client>>,
+    #[cfg(feature = "bluetooth")]
+    bluetooth: Option<Arc<bluetooth::Client>>,
     #[cfg(feature = "clipboard")]
     clipboard: Option<Arc<clipboard::Client>>,
     #[cfg(feature = "keyboard")]
@@ -54,6 +62,8 @@ pub struct Clients {
     music: HashMap<music::ClientType, Arc<dyn music::MusicClient>>,
     #[cfg(feature = "network_manager")]
     network_manager: Option<Arc<networkmanager::Client>>,
+    #[cfg(feature = "power_profile")]
+    power_profiles: Option<Arc<power_profiles::Client>>,
     #[cfg(feature = "notifications")]
     notifications: Option<Arc<swaync::Client>>,
     #[cfg(feature = "sys_info")]
@@ -61,7 +71,7 @@ pub struct Clients {
     #[cfg(feature = "tray")]
     tray: Option<Arc<tray::Client>>,
     #[cfg(feature = "upower")]
-    upower: Option<Arc<zbus::fdo::PropertiesProxy<'static>>>,
+    upower: Option<Arc<upower::Client>>,
     #[cfg(feature = "volume")]
     volume: Option<Arc<volume::Client>>,
 }
@@ -79,6 +89,21 @@ impl Clients {
             .clone()
     }
 
+    #[cfg(feature = "bluetooth")]
+    pub fn bluetooth(&mut self) -> ClientResult<bluetooth::Client> {
+        if let Some(client) = &self.bluetooth {
+            Ok(client.clone())
+        } else {
+            let client = await_sync(async move { bluetooth::create_client().await })?;
+            self.bluetooth = Some(client.clone());
+
+            #[cfg(feature = "ipc")]
+            bluetooth::register_namespace(&client);
+
+            Ok(client)
+        }
+    }
+
     #[cfg(feature = "clipboard")]
     pub fn clipboard(&mut self) -> Arc<clipboard::Client> {
         let wayland = self.wayland();
@@ -95,6 +120,9 @@ impl Clients {
         } else {
             let client = compositor::Compositor::create_workspace_client(self)?;
             self.workspaces.replace(client.clone());
+
+            #[cfg(feature = "ipc")]
+            compositor::register_workspace_namespace(&client);
             client
         };
 
@@ -108,6 +136,9 @@ impl Clients {
         } else {
             let client = compositor::Compositor::create_keyboard_layout_client(self)?;
             self.keyboard_layout.replace(client.clone());
+
+            #[cfg(feature = "ipc")]
+            compositor::register_keyboard_namespace(&client);
             client
         };
 
@@ -157,12 +188,28 @@ impl Clients {
         }
     }
 
+    /// Gets the music client for `client_type`, creating it if it does not exist.
+    ///
+    /// The `music` ironvar namespace is registered for the first client created,
+    /// and keeps following that client if other music modules create their own.
+    /// With several music modules, the namespace reflects whichever is loaded first.
     #[cfg(feature = "music")]
     pub fn music(&mut self, client_type: music::ClientType) -> Arc<dyn music::MusicClient> {
-        self.music
+        #[cfg(feature = "ipc")]
+        let first = self.music.is_empty();
+
+        let client = self
+            .music
             .entry(client_type.clone())
             .or_insert_with(|| music::create_client(client_type))
-            .clone()
+            .clone();
+
+        #[cfg(feature = "ipc")]
+        if first {
+            music::register_namespace(&client);
+        }
+
+        client
     }
 
     #[cfg(feature = "network_manager")]
@@ -172,10 +219,30 @@ impl Clients {
         } else {
             let client = await_sync(async move { networkmanager::create_client().await })?;
             self.network_manager = Some(client.clone());
+
+            #[cfg(feature = "ipc")]
+            networkmanager::register_namespace(&client);
+
             Ok(client)
         }
     }
 
+    #[cfg(feature = "power_profile")]
+    pub fn power_profiles(&mut self) -> ClientResult<power_profiles::Client> {
+        let client = if let Some(client) = &self.power_profiles {
+            client.clone()
+        } else {
+            let client = await_sync(async { power_profiles::Client::new().await })?;
+            self.power_profiles.replace(client.clone());
+
+            #[cfg(feature = "ipc")]
+            power_profiles::register_namespace(&client);
+            client
+        };
+
+        Ok(client)
+    }
+
     #[cfg(feature = "notifications")]
     pub fn notifications(&mut self) -> ClientResult<swaync::Client> {
         let client = if let Some(client) = &self.notifications {
@@ -219,12 +286,15 @@ impl Clients {
     }
 
     #[cfg(feature = "upower")]
-    pub fn upower(&mut self) -> ClientResult<zbus::fdo::PropertiesProxy<'static>> {
+    pub fn upower(&mut self) -> ClientResult<upower::Client> {
         let client = if let Some(client) = &self.upower {
             client.clone()
         } else {
-            let client = await_sync(async { upower::create_display_proxy().await })?;
+            let client = await_sync(async { upower::Client::new().await })?;
             self.upower.replace(client.clone());
+
+            #[cfg(feature = "ipc")]
+            upower::register_namespace(&client);
             client
         };
 
@@ -234,9 +304,67 @@ impl Clients {
     #[cfg(feature = "volume")]
     pub fn volume(&mut self) -> Arc<volume::Client> {
         self.volume
-            .get_or_insert_with(volume::create_client)
+            .get_or_insert_with(|| {
+                let client = volume::create_client();
+
+                #[cfg(feature = "ipc")]
+                volume::register_namespace(&client);
+
+                client
+            })
             .clone()
     }
+
+    /// Starts the client behind the ironvar namespace with `name`,
+    /// registering the namespace.
+    ///
+    /// Unknown names, and those for features which are not enabled, are ignored.
+    #[cfg(feature = "ipc")]
+    pub fn start_namespace(&mut self, name: &str) {
+        let result: Result<()> = match name {
+            #[cfg(feature = "sys_info")]
+            "sysinfo" => {
+                self.sys_info();
+                Ok(())
+            }
+            #[cfg(feature = "volume")]
+            "volume" => {
+                self.volume();
+                Ok(())
+            }
+            #[cfg(feature = "upower")]
+            "upower" => self.upower().map(|_| ()),
+            // the namespace is registered by the first music client,
+            // so only fall back to an MPRIS client if no module has created one.
+            #[cfg(feature = "music+mpris")]
+            "music" => {
+                if self.music.is_empty() {
+                    self.music(music::ClientType::Mpris(
+                        music::mpris::PlayerSelection::default(),
+                    ));
+                }
+                Ok(())
+            }
+            #[cfg(feature = "bluetooth")]
+            "bluetooth" => self.bluetooth().map(|_| ()),
+            #[cfg(feature = "network_manager")]
+            "network" => self.network_manager().map(|_| ()),
+            #[cfg(feature = "power_profile")]
+            "power_profile" => self.power_profiles().map(|_| ()),
+            #[cfg(feature = "workspaces")]
+            "workspace" => self.workspaces().map(|_| ()),
+            #[cfg(feature = "keyboard")]
+            "keyboard" => self.keyboard_layout().map(|_| ()),
+            _ => {
+                debug!("No client provides the '{name}' namespace");
+                Ok(())
+            }
+        };
+
+        if let Err(err) = result {
+            error!("Failed to start client for '{name}' namespace: {err:?}");
+        }
+    }
 }
 
 /// Types implementing this trait
@@ -1,6 +1,8 @@
-use color_eyre::Result;
+use color_eyre::{Report, Result};
+use serde::Deserialize;
 use std::fmt::Debug;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::broadcast;
@@ -9,6 +11,8 @@ use tokio::sync::broadcast;
 pub mod mpd;
 #[cfg(feature = "music+mpris")]
 pub mod mpris;
+#[cfg(feature = "music+script")]
+pub mod script;
 
 pub const TICK_INTERVAL_MS: u64 = 200;
 
@@ -20,6 +24,28 @@ pub enum PlayerUpdate {
     /// Triggered at regular intervals while a track is playing.
     /// Used to keep track of the progress through the current track.
     ProgressTick(ProgressTick),
+    /// Triggered when the tracks in the queue change.
+    /// Only sent by clients which support queues.
+    Queue(Vec<QueueItem>),
+    /// Triggered when the stored playlists change.
+    /// Only sent by clients which support stored playlists.
+    Playlists(Vec<String>),
+    /// Triggered when a player appears or disappears,
+    /// or the player being controlled changes.
+    Players {
+        players: Vec<PlayerInfo>,
+        /// The ID of the player being controlled.
+        current: Option<String>,
+    },
+}
+
+/// A player which can be picked between by the client.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlayerInfo {
+    /// Uniquely identifies the player, such as by its D-Bus name.
+    pub id: String,
+    /// The user-facing player name.
+    pub name: String,
 }
 
 #[derive(Clone, Debug)]
@@ -32,9 +58,27 @@ pub struct Track {
     pub genre: Option<String>,
     pub track: Option<u64>,
     pub cover_path: Option<String>,
+    /// Path to the track on the local filesystem, if known.
+    pub file_path: Option<PathBuf>,
+    /// Lyrics embedded in the track's tags, if any.
+    /// These may be plain text or in LRC format.
+    pub lyrics: Option<String>,
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+/// A track in the queue.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueueItem {
+    /// Position of the track in the queue, starting at 0.
+    pub position: u32,
+    /// ID of the track in the queue.
+    /// Unlike the position, this does not change as the queue is reordered.
+    pub id: u64,
+    pub title: String,
+    pub artist: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PlayerState {
     #[default]
     Stopped,
@@ -42,12 +86,48 @@ pub enum PlayerState {
     Paused,
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoopStatus {
+    /// Playback stops at the end of the playlist.
+    #[default]
+    None,
+    /// The current track repeats.
+    Track,
+    /// The playlist repeats from the start once finished.
+    Playlist,
+}
+
+impl FromStr for LoopStatus {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "none" => Ok(Self::None),
+            "track" => Ok(Self::Track),
+            "playlist" => Ok(Self::Playlist),
+            _ => Err(Report::msg(format!("Invalid loop status: '{s}'"))),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Status {
     pub state: PlayerState,
     pub volume_percent: Option<u8>,
+    /// Position of the current track in the queue,
+    /// or `0` if the player does not have a queue.
     pub playlist_position: u32,
-    pub playlist_length: u32,
+    /// Whether the player can skip to the next track.
+    pub can_go_next: bool,
+    /// Whether the player can go back to the previous track.
+    pub can_go_previous: bool,
+    /// Whether shuffle is enabled,
+    /// or `None` if the player does not support it.
+    pub shuffle: Option<bool>,
+    /// The loop/repeat mode,
+    /// or `None` if the player does not support it.
+    pub loop_status: Option<LoopStatus>,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -65,6 +145,49 @@ pub trait MusicClient: Debug + Send + Sync {
     fn set_volume_percent(&self, vol: u8) -> Result<()>;
     fn seek(&self, duration: Duration) -> Result<()>;
 
+    fn set_shuffle(&self, shuffle: bool) -> Result<()>;
+    fn set_loop_status(&self, loop_status: LoopStatus) -> Result<()>;
+
+    /// Plays the track with queue ID `id`.
+    fn play_at(&self, _id: u64) -> Result<()> {
+        Err(Report::msg("The player does not support queues"))
+    }
+
+    /// Removes the track with queue ID `id` from the queue.
+    fn remove_from_queue(&self, _id: u64) -> Result<()> {
+        Err(Report::msg("The player does not support queues"))
+    }
+
+    /// Moves the track with queue ID `id` to play after the current track.
+    fn move_to_next(&self, _id: u64) -> Result<()> {
+        Err(Report::msg("The player does not support queues"))
+    }
+
+    /// Adds the tracks in the stored playlist called `name` to the queue.
+    fn load_playlist(&self, _name: &str) -> Result<()> {
+        Err(Report::msg("The player does not support stored playlists"))
+    }
+
+    /// Requests that lyrics embedded in track tags are included in updates.
+    /// Clients which need extra requests to read the tags only do so once this is called.
+    fn enable_lyrics(&self) {}
+
+    /// Gets the available players, in order of priority.
+    /// Clients which only ever control a single player return an empty list.
+    fn players(&self) -> Vec<PlayerInfo> {
+        vec![]
+    }
+
+    /// Gets the ID of the player currently being controlled, if any.
+    fn current_player(&self) -> Option<String> {
+        None
+    }
+
+    /// Switches to controlling the player with the ID or name `player`.
+    fn set_player(&self, _player: &str) -> Result<()> {
+        Ok(())
+    }
+
     fn subscribe_change(&self) -> broadcast::Receiver<PlayerUpdate>;
 }
 
@@ -73,7 +196,70 @@ pub enum ClientType {
     #[cfg(feature = "music+mpd")]
     Mpd { host: String, music_dir: PathBuf },
     #[cfg(feature = "music+mpris")]
-    Mpris,
+    Mpris(mpris::PlayerSelection),
+    #[cfg(feature = "music+script")]
+    Script(script::Commands),
+}
+
+/// Registers the `music` ironvar namespace,
+/// which exposes the current track and player state of `client`.
+#[cfg(feature = "ipc")]
+pub fn register_namespace(client: &Arc<dyn MusicClient>) {
+    use crate::ironvar::ClientNamespace;
+    use tokio::sync::broadcast::error::RecvError;
+
+    let namespace = ClientNamespace::register("music");
+    let mut rx = client.subscribe_change();
+
+    crate::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(PlayerUpdate::Update(track, status)) => {
+                    let track = *track;
+                    let fields = [
+                        ("title", track.as_ref().and_then(|t| t.title.clone())),
+                        ("artist", track.as_ref().and_then(|t| t.artist.clone())),
+                        ("album", track.as_ref().and_then(|t| t.album.clone())),
+                    ];
+
+                    for (key, value) in fields {
+                        match value {
+                            Some(value) => namespace.set(key, value),
+                            None => namespace.remove(key),
+                        }
+                    }
+
+                    let state = match status.state {
+                        PlayerState::Playing => "playing",
+                        PlayerState::Paused => "paused",
+                        PlayerState::Stopped => "stopped",
+                    };
+                    namespace.set("state", state);
+
+                    match status.volume_percent {
+                        Some(volume) => namespace.set("volume", volume),
+                        None => namespace.remove("volume"),
+                    }
+                }
+                Ok(PlayerUpdate::Players { players, current }) => {
+                    match players
+                        .into_iter()
+                        .find(|player| Some(&player.id) == current.as_ref())
+                    {
+                        Some(player) => namespace.set("player", player.name),
+                        None => namespace.remove("player"),
+                    }
+                }
+                Ok(
+                    PlayerUpdate::ProgressTick(_)
+                    | PlayerUpdate::Queue(_)
+                    | PlayerUpdate::Playlists(_),
+                )
+                | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
 }
 
 pub fn create_client(client_type: ClientType) -> Arc<dyn MusicClient> {
@@ -81,6 +267,8 @@ pub fn create_client(client_type: ClientType) -> Arc<dyn MusicClient> {
         #[cfg(feature = "music+mpd")]
         ClientType::Mpd { host, music_dir } => Arc::new(mpd::Client::new(host, music_dir)),
         #[cfg(feature = "music+mpris")]
-        ClientType::Mpris => Arc::new(mpris::Client::new()),
+        ClientType::Mpris(selection) => Arc::new(mpris::Client::new(selection)),
+        #[cfg(feature = "music+script")]
+        ClientType::Script(commands) => Arc::new(script::Client::new(commands)),
     }
 }
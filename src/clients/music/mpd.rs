@@ -1,20 +1,23 @@
 use super::{
-    MusicClient, PlayerState, PlayerUpdate, ProgressTick, Status, TICK_INTERVAL_MS, Track,
+    LoopStatus, MusicClient, PlayerState, PlayerUpdate, ProgressTick, QueueItem, Status,
+    TICK_INTERVAL_MS, Track,
 };
-use crate::{Ironbar, await_sync, send, spawn};
+use crate::{Ironbar, await_sync, lock, send, spawn};
 use color_eyre::Report;
 use color_eyre::Result;
 use mpd_client::client::{ConnectionEvent, Subsystem};
-use mpd_client::commands::{self, SeekMode};
-use mpd_client::responses::{PlayState, Song};
+use mpd_client::commands::{self, SeekMode, SingleMode, SongId};
+use mpd_client::protocol::Command as RawCommand;
+use mpd_client::responses::{PlayState, Song, SongInQueue};
 use mpd_client::tag::Tag;
 use mpd_utils::{PersistentClient, mpd_client};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::sync::broadcast;
 use tokio::time::sleep;
-use tracing::debug;
+use tracing::{debug, error};
 
 macro_rules! command {
     ($self:ident, $command:expr) => {
@@ -26,15 +29,58 @@ macro_rules! command {
 pub struct Client {
     client: Arc<PersistentClient>,
     music_dir: PathBuf,
+    lyrics: Arc<LyricsCache>,
+    lists: Arc<Lists>,
     tx: broadcast::Sender<PlayerUpdate>,
     _rx: broadcast::Receiver<PlayerUpdate>,
 }
 
+/// The last queue and stored playlists,
+/// which are replayed to each new subscriber.
+#[derive(Debug, Default)]
+struct Lists {
+    queue: Mutex<Option<Vec<QueueItem>>>,
+    playlists: Mutex<Option<Vec<String>>>,
+}
+
+/// Lyrics embedded in the tags of the current song.
+///
+/// Reading these takes an extra request,
+/// so they are only read once a module asks for them,
+/// and again only when the song changes.
+#[derive(Debug, Default)]
+struct LyricsCache {
+    enabled: AtomicBool,
+    /// The URL of the last song and its lyrics.
+    song: Mutex<Option<(String, Option<String>)>>,
+}
+
+impl LyricsCache {
+    async fn get(&self, client: &PersistentClient, url: &str) -> Option<String> {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        if let Some((cached_url, lyrics)) = &*lock!(self.song) {
+            if cached_url == url {
+                return lyrics.clone();
+            }
+        }
+
+        let lyrics = Client::get_lyrics(client, url).await;
+        *lock!(self.song) = Some((url.to_string(), lyrics.clone()));
+
+        lyrics
+    }
+}
+
 impl Client {
     pub fn new(host: String, music_dir: PathBuf) -> Self {
         let client = Arc::new(PersistentClient::new(host, Duration::from_secs(5)));
         let mut client_rx = client.subscribe();
 
+        let lyrics = Arc::new(LyricsCache::default());
+        let lists = Arc::new(Lists::default());
         let (tx, rx) = broadcast::channel(32);
 
         let _guard = Ironbar::runtime().enter();
@@ -44,21 +90,38 @@ impl Client {
             let tx = tx.clone();
             let client = client.clone();
             let music_dir = music_dir.clone();
+            let lyrics = lyrics.clone();
+            let lists = lists.clone();
 
             spawn(async move {
-                Self::send_update(&client, &tx, &music_dir)
+                Self::send_update(&client, &tx, &music_dir, &lyrics)
                     .await
                     .expect("Failed to send update");
+                Self::send_queue(&client, &tx, &lists).await;
+                Self::send_playlists(&client, &tx, &lists).await;
 
                 while let Ok(change) = client_rx.recv().await {
                     debug!("Received state change: {change:?}");
-                    if let ConnectionEvent::SubsystemChange(
-                        Subsystem::Player | Subsystem::Queue | Subsystem::Mixer,
-                    ) = *change
-                    {
-                        Self::send_update(&client, &tx, &music_dir)
-                            .await
-                            .expect("Failed to send update");
+                    if let ConnectionEvent::SubsystemChange(subsystem) = &*change {
+                        match subsystem {
+                            Subsystem::Player
+                            | Subsystem::Queue
+                            | Subsystem::Mixer
+                            | Subsystem::Options => {
+                                Self::send_update(&client, &tx, &music_dir, &lyrics)
+                                    .await
+                                    .expect("Failed to send update");
+                            }
+                            _ => {}
+                        }
+
+                        match subsystem {
+                            Subsystem::Queue => Self::send_queue(&client, &tx, &lists).await,
+                            Subsystem::StoredPlaylist => {
+                                Self::send_playlists(&client, &tx, &lists).await;
+                            }
+                            _ => {}
+                        }
                     }
                 }
             });
@@ -78,6 +141,8 @@ impl Client {
 
         Self {
             client,
+            lyrics,
+            lists,
             tx,
             music_dir,
             _rx: rx,
@@ -88,12 +153,21 @@ impl Client {
         client: &PersistentClient,
         tx: &broadcast::Sender<PlayerUpdate>,
         music_dir: &Path,
+        lyrics: &LyricsCache,
     ) -> Result<(), broadcast::error::SendError<PlayerUpdate>> {
         let current_song = client.command(commands::CurrentSong).await;
         let status = client.command(commands::Status).await;
 
         if let (Ok(current_song), Ok(status)) = (current_song, status) {
-            let track = current_song.map(|s| convert_song(&s.song, music_dir));
+            let track = match current_song {
+                Some(current_song) => {
+                    let mut track = convert_song(&current_song.song, music_dir);
+                    track.lyrics = lyrics.get(client, &current_song.song.url).await;
+                    Some(track)
+                }
+                None => None,
+            };
+
             let status = Status::from(status);
 
             let update = PlayerUpdate::Update(Box::new(track), status);
@@ -103,6 +177,65 @@ impl Client {
         Ok(())
     }
 
+    async fn send_queue(
+        client: &PersistentClient,
+        tx: &broadcast::Sender<PlayerUpdate>,
+        lists: &Lists,
+    ) {
+        match client.command(commands::Queue).await {
+            Ok(queue) => {
+                let queue = queue.iter().map(convert_queue_item).collect::<Vec<_>>();
+                lock!(lists.queue).replace(queue.clone());
+                send!(tx, PlayerUpdate::Queue(queue));
+            }
+            Err(err) => error!("Failed to get queue: {err:?}"),
+        }
+    }
+
+    async fn send_playlists(
+        client: &PersistentClient,
+        tx: &broadcast::Sender<PlayerUpdate>,
+        lists: &Lists,
+    ) {
+        match client.command(commands::GetPlaylists).await {
+            Ok(playlists) => {
+                let playlists = playlists
+                    .into_iter()
+                    .map(|playlist| playlist.name)
+                    .collect::<Vec<_>>();
+                lock!(lists.playlists).replace(playlists.clone());
+                send!(tx, PlayerUpdate::Playlists(playlists));
+            }
+            Err(err) => error!("Failed to get stored playlists: {err:?}"),
+        }
+    }
+
+    /// Reads lyrics embedded in the tags of the song at `url`.
+    async fn get_lyrics(client: &PersistentClient, url: &str) -> Option<String> {
+        let frame = client
+            .with_client(|client| async move {
+                client
+                    .raw_command(RawCommand::new("readcomments").argument(url))
+                    .await
+            })
+            .await
+            .ok()?;
+
+        let lyrics = frame
+            .fields()
+            .filter(|(key, _)| {
+                key.eq_ignore_ascii_case("lyrics") || key.eq_ignore_ascii_case("unsyncedlyrics")
+            })
+            .map(|(_, value)| value)
+            .collect::<Vec<_>>();
+
+        if lyrics.is_empty() {
+            None
+        } else {
+            Some(lyrics.join("\n"))
+        }
+    }
+
     async fn send_tick_update(client: &PersistentClient, tx: &broadcast::Sender<PlayerUpdate>) {
         let status = client.command(commands::Status).await;
 
@@ -120,6 +253,10 @@ impl Client {
 }
 
 impl MusicClient for Client {
+    fn enable_lyrics(&self) {
+        self.lyrics.enabled.store(true, Ordering::Relaxed);
+    }
+
     fn play(&self) -> Result<()> {
         command!(self, commands::SetPause(false))
     }
@@ -144,13 +281,74 @@ impl MusicClient for Client {
         command!(self, commands::Seek(SeekMode::Absolute(duration)))
     }
 
+    fn set_shuffle(&self, shuffle: bool) -> Result<()> {
+        command!(self, commands::SetRandom(shuffle))
+    }
+
+    fn set_loop_status(&self, loop_status: LoopStatus) -> Result<()> {
+        // MPD has no single loop mode;
+        // repeating a track is repeat combined with single mode.
+        let (repeat, single) = match loop_status {
+            LoopStatus::None => (false, SingleMode::Disabled),
+            LoopStatus::Track => (true, SingleMode::Enabled),
+            LoopStatus::Playlist => (true, SingleMode::Disabled),
+        };
+
+        command!(self, commands::SetRepeat(repeat))?;
+        command!(self, commands::SetSingle(single))
+    }
+
+    fn play_at(&self, id: u64) -> Result<()> {
+        command!(self, commands::Play::song(SongId(id)))
+    }
+
+    fn remove_from_queue(&self, id: u64) -> Result<()> {
+        command!(self, commands::Delete::id(SongId(id)))
+    }
+
+    fn move_to_next(&self, id: u64) -> Result<()> {
+        command!(self, commands::Move::id(SongId(id)).after_current(0))
+    }
+
+    fn load_playlist(&self, name: &str) -> Result<()> {
+        command!(self, commands::LoadPlaylist::name(name))
+    }
+
     fn subscribe_change(&self) -> broadcast::Receiver<PlayerUpdate> {
-        let rx = self.tx.subscribe();
+        let mut shared_rx = self.tx.subscribe();
         await_sync(async move {
-            Self::send_update(&self.client, &self.tx, &self.music_dir)
+            Self::send_update(&self.client, &self.tx, &self.music_dir, &self.lyrics)
                 .await
                 .expect("to be able to send update");
         });
+
+        // the queue and playlists only change on their own events,
+        // so are replayed from the cache to the new subscriber alone
+        // rather than fetched and broadcast to every subscriber again.
+        let (tx, rx) = broadcast::channel(32);
+
+        if let Some(queue) = lock!(self.lists.queue).clone() {
+            send!(tx, PlayerUpdate::Queue(queue));
+        }
+
+        if let Some(playlists) = lock!(self.lists.playlists).clone() {
+            send!(tx, PlayerUpdate::Playlists(playlists));
+        }
+
+        spawn(async move {
+            loop {
+                match shared_rx.recv().await {
+                    Ok(update) => {
+                        if tx.send(update).is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+
         rx
     }
 }
@@ -178,6 +376,26 @@ fn convert_song(song: &Song, music_dir: &Path) -> Track {
         disc: Some(disc),
         track: Some(track),
         cover_path,
+        file_path: Some(music_dir.join(song.file_path())),
+        lyrics: None,
+    }
+}
+
+fn convert_queue_item(item: &SongInQueue) -> QueueItem {
+    let song = &item.song;
+
+    let title = song.title().map(ToString::to_string).unwrap_or_else(|| {
+        song.file_path()
+            .file_stem()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| song.url.clone())
+    });
+
+    QueueItem {
+        position: item.position.0 as u32,
+        id: item.id.0,
+        title,
+        artist: Some(song.artists().join(", ")).filter(|artist| !artist.is_empty()),
     }
 }
 
@@ -195,7 +413,19 @@ impl From<mpd_client::responses::Status> for Status {
             state: PlayerState::from(status.state),
             volume_percent: Some(status.volume),
             playlist_position: status.current_song.map_or(0, |(pos, _)| pos.0 as u32),
-            playlist_length: status.playlist_length as u32,
+            // with repeat enabled, the queue wraps around at either end
+            can_go_next: status
+                .current_song
+                .is_some_and(|(pos, _)| status.repeat || pos.0 + 1 < status.playlist_length),
+            can_go_previous: status
+                .current_song
+                .is_some_and(|(pos, _)| status.repeat || pos.0 > 0),
+            shuffle: Some(status.random),
+            loop_status: Some(match (status.repeat, status.single) {
+                (true, SingleMode::Enabled) => LoopStatus::Track,
+                (true, _) => LoopStatus::Playlist,
+                (false, _) => LoopStatus::None,
+            }),
         }
     }
 }
@@ -1,37 +1,157 @@
-use super::{MusicClient, PlayerState, PlayerUpdate, Status, TICK_INTERVAL_MS, Track};
+use super::{
+    LoopStatus, MusicClient, PlayerInfo, PlayerState, PlayerUpdate, Status, TICK_INTERVAL_MS, Track,
+};
 use crate::clients::music::ProgressTick;
 use crate::{arc_mut, lock, send, spawn_blocking};
-use color_eyre::Result;
+use color_eyre::{Report, Result};
 use mpris::{DBusError, Event, Metadata, PlaybackStatus, Player, PlayerFinder};
 use std::cmp;
-use std::collections::HashSet;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::sleep;
 use std::time::Duration;
 use tokio::sync::broadcast;
 use tracing::{debug, error, trace};
 
+/// Player bus names mapped to their details.
+type Players = Arc<Mutex<HashMap<String, PlayerEntry>>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PlayerEntry {
+    identity: String,
+    /// The player's priority, where lower is preferred.
+    rank: usize,
+}
+
 #[derive(Debug)]
 pub struct Client {
     current_player: Arc<Mutex<Option<String>>>,
+    players: Players,
+    /// Whether the current player was chosen by the user,
+    /// in which case it is not switched away from automatically.
+    pinned: Arc<AtomicBool>,
     tx: broadcast::Sender<PlayerUpdate>,
     _rx: broadcast::Receiver<PlayerUpdate>,
 }
 
+/// Controls which MPRIS players the client picks between.
+///
+/// Players are matched by their identity (such as `Spotify`)
+/// or the last part of their bus name (such as `spotify` or `firefox`),
+/// ignoring case.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PlayerSelection {
+    /// Players to prefer, highest priority first.
+    /// Unlisted players have the lowest priority.
+    pub priority: Vec<String>,
+    /// If not empty, only these players are used.
+    pub allowlist: Vec<String>,
+    /// Players which are never used.
+    pub denylist: Vec<String>,
+}
+
+impl PlayerSelection {
+    fn matches(pattern: &str, identity: &str, bus_name: &str) -> bool {
+        let name = bus_name
+            .strip_prefix("org.mpris.MediaPlayer2.")
+            .unwrap_or(bus_name);
+
+        // multi-instance players append an instance ID, such as `firefox.instance_1_23`
+        let base_name = name.split('.').next().unwrap_or(name);
+
+        [identity, name, base_name]
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(pattern))
+    }
+
+    fn allows(&self, identity: &str, bus_name: &str) -> bool {
+        let matches = |pattern: &String| Self::matches(pattern, identity, bus_name);
+
+        (self.allowlist.is_empty() || self.allowlist.iter().any(matches))
+            && !self.denylist.iter().any(matches)
+    }
+
+    /// Gets the priority of the player, where lower is preferred.
+    fn rank(&self, identity: &str, bus_name: &str) -> usize {
+        self.priority
+            .iter()
+            .position(|pattern| Self::matches(pattern, identity, bus_name))
+            .unwrap_or(self.priority.len())
+    }
+
+    fn rank_player(&self, player: &Player) -> usize {
+        self.rank(player.identity(), player.bus_name())
+    }
+}
+
 const NO_ACTIVE_PLAYER: &str = "com.github.altdesktop.playerctld.NoActivePlayer";
 const NO_REPLY: &str = "org.freedesktop.DBus.Error.NoReply";
 const NO_SERVICE: &str = "org.freedesktop.DBus.Error.ServiceUnknown";
 const NO_METHOD: &str = "org.freedesktop.DBus.Error.UnknownMethod";
 
+fn is_playing(player: &Player) -> bool {
+    matches!(player.get_playback_status(), Ok(PlaybackStatus::Playing))
+}
+
+/// Finds the player with the bus name `bus_name`.
+///
+/// Several players can share an identity,
+/// so they are looked up by bus name rather than using [`PlayerFinder::find_by_name`].
+fn find_player(player_finder: &PlayerFinder, bus_name: &str) -> Option<Player> {
+    player_finder
+        .iter_players()
+        .ok()?
+        .flatten()
+        .find(|player| player.bus_name() == bus_name)
+}
+
+/// Gets `players` ordered by priority, then identity.
+fn sorted_players(players: &HashMap<String, PlayerEntry>) -> Vec<PlayerInfo> {
+    let mut players = players.iter().collect::<Vec<_>>();
+    players.sort_by(|(a_bus_name, a), (b_bus_name, b)| {
+        a.rank
+            .cmp(&b.rank)
+            .then_with(|| a.identity.cmp(&b.identity))
+            .then_with(|| a_bus_name.cmp(b_bus_name))
+    });
+
+    players
+        .into_iter()
+        .map(|(bus_name, player)| PlayerInfo {
+            id: bus_name.clone(),
+            name: player.identity.clone(),
+        })
+        .collect()
+}
+
+fn send_players(
+    players: &HashMap<String, PlayerEntry>,
+    current: Option<&String>,
+    tx: &broadcast::Sender<PlayerUpdate>,
+) {
+    send!(
+        tx,
+        PlayerUpdate::Players {
+            players: sorted_players(players),
+            current: current.cloned(),
+        }
+    );
+}
+
 impl Client {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(selection: PlayerSelection) -> Self {
         let (tx, rx) = broadcast::channel(32);
 
         let current_player = arc_mut!(None);
+        let players = arc_mut!(HashMap::new());
+        let pinned = Arc::new(AtomicBool::new(false));
+        let selection = Arc::new(selection);
 
         {
-            let players_list = arc_mut!(HashSet::new());
+            let players_list = players.clone();
             let current_player = current_player.clone();
+            let pinned = pinned.clone();
             let tx = tx.clone();
 
             spawn_blocking(move || {
@@ -55,36 +175,68 @@ impl Client {
                         }
                     });
 
+                    let players = players
+                        .into_iter()
+                        .filter(|player| selection.allows(player.identity(), player.bus_name()))
+                        .collect::<Vec<_>>();
+
                     // Acquire the lock of current_player before players to avoid deadlock.
                     // There are places where we lock on current_player and players, but we always lock on current_player first.
                     // This is because we almost never need to lock on players without locking on current_player.
                     {
                         let mut current_player_lock = lock!(current_player);
-
                         let mut players_list_val = lock!(players_list);
-                        for player in players {
-                            let identity = player.identity();
 
-                            if current_player_lock.is_none() {
-                                debug!("Setting active player to '{identity}'");
-                                current_player_lock.replace(identity.to_string());
+                        let mut changed = false;
 
-                                if let Err(err) = Self::send_update(&player, &tx) {
-                                    error!("{err:?}");
-                                }
-                            }
-                            if !players_list_val.contains(identity) {
-                                debug!("Adding MPRIS player '{identity}'");
-                                players_list_val.insert(identity.to_string());
+                        for player in &players {
+                            let bus_name = player.bus_name();
+
+                            if !players_list_val.contains_key(bus_name) {
+                                debug!("Adding MPRIS player '{bus_name}'");
+                                players_list_val.insert(
+                                    bus_name.to_string(),
+                                    PlayerEntry {
+                                        identity: player.identity().to_string(),
+                                        rank: selection.rank_player(player),
+                                    },
+                                );
 
                                 Self::listen_player_events(
-                                    identity.to_string(),
+                                    bus_name.to_string(),
                                     players_list.clone(),
                                     current_player.clone(),
+                                    pinned.clone(),
+                                    selection.clone(),
                                     tx.clone(),
                                 );
+
+                                changed = true;
+                            }
+                        }
+
+                        if current_player_lock.is_none() {
+                            // prefer playing players, then by priority
+                            let best = players.iter().min_by_key(|player| {
+                                (!is_playing(player), selection.rank_player(player))
+                            });
+
+                            if let Some(player) = best {
+                                let bus_name = player.bus_name();
+
+                                debug!("Setting active player to '{bus_name}'");
+                                current_player_lock.replace(bus_name.to_string());
+                                changed = true;
+
+                                if let Err(err) = Self::send_update(player, &tx) {
+                                    error!("{err:?}");
+                                }
                             }
                         }
+
+                        if changed {
+                            send_players(&players_list_val, current_player_lock.as_ref(), &tx);
+                        }
                     }
                     // wait 1 second before re-checking players
                     sleep(Duration::from_secs(1));
@@ -108,41 +260,51 @@ impl Client {
 
         Self {
             current_player,
+            players,
+            pinned,
             tx,
             _rx: rx,
         }
     }
 
     fn listen_player_events(
-        player_id: String,
-        players: Arc<Mutex<HashSet<String>>>,
+        bus_name: String,
+        players: Players,
         current_player: Arc<Mutex<Option<String>>>,
+        pinned: Arc<AtomicBool>,
+        selection: Arc<PlayerSelection>,
         tx: broadcast::Sender<PlayerUpdate>,
     ) {
         spawn_blocking(move || {
             let player_finder = PlayerFinder::new()?;
 
-            if let Ok(player) = player_finder.find_by_name(&player_id) {
-                let identity = player.identity();
+            if let Some(player) = find_player(&player_finder, &bus_name) {
+                let bus_name = bus_name.as_str();
                 let handle_shutdown = |current_player_lock_option: Option<
                     std::sync::MutexGuard<'_, Option<String>>,
                 >| {
-                    debug!("Player '{identity}' shutting down");
+                    debug!("Player '{bus_name}' shutting down");
                     // Lock of player before players (see new() to make sure order is consistent)
-                    if let Some(mut guard) = current_player_lock_option {
-                        guard.take();
-                    } else {
-                        lock!(current_player).take();
+                    let mut current_player_lock =
+                        current_player_lock_option.unwrap_or_else(|| lock!(current_player));
+
+                    // the next best player is picked up when next polling
+                    if current_player_lock.as_deref() == Some(bus_name) {
+                        current_player_lock.take();
+                        pinned.store(false, Ordering::Relaxed);
                     }
+
                     let mut players_locked = lock!(players);
-                    players_locked.remove(identity);
+                    players_locked.remove(bus_name);
                     if players_locked.is_empty() {
                         send!(tx, PlayerUpdate::Update(Box::new(None), Status::default()));
                     }
+
+                    send_players(&players_locked, current_player_lock.as_ref(), &tx);
                 };
 
                 for event in player.events()? {
-                    trace!("Received player event from '{identity}': {event:?}");
+                    trace!("Received player event from '{bus_name}': {event:?}");
                     match event {
                         Ok(Event::PlayerShutDown) => {
                             handle_shutdown(None);
@@ -159,12 +321,38 @@ impl Client {
                             break;
                         }
                         Ok(_) => {
-                            let mut current_player_lock = lock!(current_player);
                             if matches!(event, Ok(Event::Playing)) {
-                                current_player_lock.replace(identity.to_string());
+                                // deciding whether to switch makes blocking D-Bus calls,
+                                // so must not hold the lock while it runs
+                                let current = lock!(current_player).clone();
+
+                                if current.as_deref() != Some(bus_name)
+                                    && Self::should_switch_to(
+                                        &player,
+                                        current.as_deref(),
+                                        &pinned,
+                                        &selection,
+                                    )
+                                {
+                                    let mut current_player_lock = lock!(current_player);
+
+                                    // another player may have become active in the meantime
+                                    if *current_player_lock == current {
+                                        debug!("Setting active player to '{bus_name}'");
+                                        current_player_lock.replace(bus_name.to_string());
+                                        send_players(
+                                            &lock!(players),
+                                            current_player_lock.as_ref(),
+                                            &tx,
+                                        );
+                                    }
+                                }
                             }
-                            if let Some(current_identity) = current_player_lock.as_ref() {
-                                if current_identity == identity {
+
+                            let current_player_lock = lock!(current_player);
+
+                            if let Some(current_bus_name) = current_player_lock.as_ref() {
+                                if current_bus_name == bus_name {
                                     if let Err(err) = Self::send_update(&player, &tx) {
                                         if let Some(DBusError::TransportError(transport_error)) =
                                             err.downcast_ref::<DBusError>()
@@ -188,25 +376,51 @@ impl Client {
         });
     }
 
+    /// Checks whether `player` starting playback should make it the current player.
+    ///
+    /// This is the case unless the user has picked a player,
+    /// or the current player is still playing and has a higher priority.
+    fn should_switch_to(
+        player: &Player,
+        current: Option<&str>,
+        pinned: &AtomicBool,
+        selection: &PlayerSelection,
+    ) -> bool {
+        if pinned.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        let Some(current) =
+            current.and_then(|bus_name| find_player(&PlayerFinder::new().ok()?, bus_name))
+        else {
+            return true;
+        };
+
+        !is_playing(&current) || selection.rank_player(player) <= selection.rank_player(&current)
+    }
+
     fn send_update(player: &Player, tx: &broadcast::Sender<PlayerUpdate>) -> Result<()> {
-        debug!("Sending update using '{}'", player.identity());
+        debug!("Sending update using '{}'", player.bus_name());
 
         let metadata = player.get_metadata()?;
         let playback_status = player
             .get_playback_status()
             .unwrap_or(PlaybackStatus::Stopped);
 
-        let track_list = player.get_track_list();
-
         let volume_percent = player.get_volume().map(|vol| (vol * 100.0) as u8).ok();
 
         let status = Status {
-            // MRPIS doesn't seem to provide playlist info reliably,
-            // so we can just assume next/prev will work by bodging the numbers
-            playlist_position: 1,
-            playlist_length: track_list.map(|list| list.len() as u32).unwrap_or(u32::MAX),
+            playlist_position: 0,
+            can_go_next: player.can_go_next().unwrap_or_default(),
+            can_go_previous: player.can_go_previous().unwrap_or_default(),
             state: PlayerState::from(playback_status),
             volume_percent,
+            shuffle: player.checked_get_shuffle().ok().flatten(),
+            loop_status: player
+                .checked_get_loop_status()
+                .ok()
+                .flatten()
+                .map(LoopStatus::from),
         };
 
         let track = Track::from(metadata);
@@ -218,12 +432,12 @@ impl Client {
     }
 
     fn get_player(&self) -> Option<Player> {
-        let player_name = lock!(self.current_player);
-        let player_name = player_name.as_ref();
+        let bus_name = lock!(self.current_player);
+        let bus_name = bus_name.as_ref();
 
-        player_name.and_then(|player_name| {
+        bus_name.and_then(|bus_name| {
             let player_finder = PlayerFinder::new().expect("Failed to connect to D-Bus");
-            player_finder.find_by_name(player_name).ok()
+            find_player(&player_finder, bus_name)
         })
     }
 
@@ -234,7 +448,7 @@ impl Client {
     ) {
         if let Some(player) = lock!(current_player)
             .as_ref()
-            .and_then(|name| player_finder.find_by_name(name).ok())
+            .and_then(|bus_name| find_player(player_finder, bus_name))
         {
             if let Ok(metadata) = player.get_metadata() {
                 let update = PlayerUpdate::ProgressTick(ProgressTick {
@@ -304,6 +518,66 @@ impl MusicClient for Client {
         Ok(())
     }
 
+    fn set_shuffle(&self, shuffle: bool) -> Result<()> {
+        if let Some(player) = Self::get_player(self) {
+            player.set_shuffle(shuffle)?;
+        } else {
+            error!("Could not find player");
+        }
+        Ok(())
+    }
+
+    fn set_loop_status(&self, loop_status: LoopStatus) -> Result<()> {
+        if let Some(player) = Self::get_player(self) {
+            player.set_loop_status(loop_status.into())?;
+        } else {
+            error!("Could not find player");
+        }
+        Ok(())
+    }
+
+    fn players(&self) -> Vec<PlayerInfo> {
+        sorted_players(&lock!(self.players))
+    }
+
+    fn current_player(&self) -> Option<String> {
+        lock!(self.current_player).clone()
+    }
+
+    fn set_player(&self, player: &str) -> Result<()> {
+        let mut current_player = lock!(self.current_player);
+        let players = lock!(self.players);
+
+        // prefer an exact bus name, falling back to the first player with a matching identity
+        let bus_name = if players.contains_key(player) {
+            Some(player.to_string())
+        } else {
+            sorted_players(&players)
+                .into_iter()
+                .find(|info| info.name.eq_ignore_ascii_case(player))
+                .map(|info| info.id)
+        };
+
+        let Some(bus_name) = bus_name else {
+            return Err(Report::msg(format!("Player '{player}' not found")));
+        };
+
+        debug!("Setting active player to '{bus_name}'");
+        current_player.replace(bus_name);
+        self.pinned.store(true, Ordering::Relaxed);
+
+        send_players(&players, current_player.as_ref(), &self.tx);
+
+        drop(players);
+        drop(current_player);
+
+        if let Some(player) = self.get_player() {
+            Self::send_update(&player, &self.tx)?;
+        }
+
+        Ok(())
+    }
+
     fn subscribe_change(&self) -> broadcast::Receiver<PlayerUpdate> {
         debug!("Creating new subscription");
         let rx = self.tx.subscribe();
@@ -315,13 +589,21 @@ impl MusicClient for Client {
         } else {
             let status = Status {
                 playlist_position: 0,
-                playlist_length: 0,
+                can_go_next: false,
+                can_go_previous: false,
                 state: PlayerState::Stopped,
                 volume_percent: None,
+                shuffle: None,
+                loop_status: None,
             };
             send!(self.tx, PlayerUpdate::Update(Box::new(None), status));
         }
 
+        {
+            let current_player = lock!(self.current_player);
+            send_players(&lock!(self.players), current_player.as_ref(), &self.tx);
+        }
+
         rx
     }
 }
@@ -330,6 +612,7 @@ impl From<Metadata> for Track {
     fn from(value: Metadata) -> Self {
         const KEY_DATE: &str = "xesam:contentCreated";
         const KEY_GENRE: &str = "xesam:genre";
+        const KEY_LYRICS: &str = "xesam:asText";
 
         Self {
             title: value
@@ -355,6 +638,16 @@ impl From<Metadata> for Track {
                 .and_then(|arr| arr.first().map(|val| (*val).to_string())),
             track: value.track_number().map(|track| track as u64),
             cover_path: value.art_url().map(ToString::to_string),
+            file_path: value
+                .url()
+                .filter(|url| url.starts_with("file://"))
+                .and_then(|url| glib::filename_from_uri(url).ok())
+                .map(|(path, _)| path),
+            lyrics: value
+                .get(KEY_LYRICS)
+                .and_then(mpris::MetadataValue::as_str)
+                .map(ToString::to_string)
+                .and_then(replace_empty_none),
         }
     }
 }
@@ -369,6 +662,26 @@ impl From<PlaybackStatus> for PlayerState {
     }
 }
 
+impl From<mpris::LoopStatus> for LoopStatus {
+    fn from(value: mpris::LoopStatus) -> Self {
+        match value {
+            mpris::LoopStatus::None => Self::None,
+            mpris::LoopStatus::Track => Self::Track,
+            mpris::LoopStatus::Playlist => Self::Playlist,
+        }
+    }
+}
+
+impl From<LoopStatus> for mpris::LoopStatus {
+    fn from(value: LoopStatus) -> Self {
+        match value {
+            LoopStatus::None => Self::None,
+            LoopStatus::Track => Self::Track,
+            LoopStatus::Playlist => Self::Playlist,
+        }
+    }
+}
+
 fn replace_empty_none(string: String) -> Option<String> {
     if string.is_empty() {
         None
@@ -376,3 +689,92 @@ fn replace_empty_none(string: String) -> Option<String> {
         Some(string)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPOTIFY: (&str, &str) = ("Spotify", "org.mpris.MediaPlayer2.spotify");
+    const FIREFOX: (&str, &str) = (
+        "Mozilla Firefox",
+        "org.mpris.MediaPlayer2.firefox.instance_1_23",
+    );
+
+    #[test]
+    fn test_matches() {
+        let (identity, bus_name) = FIREFOX;
+
+        assert!(PlayerSelection::matches(
+            "mozilla firefox",
+            identity,
+            bus_name
+        ));
+        assert!(PlayerSelection::matches("firefox", identity, bus_name));
+        assert!(PlayerSelection::matches(
+            "firefox.instance_1_23",
+            identity,
+            bus_name
+        ));
+        assert!(!PlayerSelection::matches("fire", identity, bus_name));
+    }
+
+    #[test]
+    fn test_allows() {
+        let selection = PlayerSelection {
+            allowlist: vec!["spotify".to_string(), "firefox".to_string()],
+            denylist: vec!["firefox".to_string()],
+            ..PlayerSelection::default()
+        };
+
+        assert!(selection.allows(SPOTIFY.0, SPOTIFY.1));
+        assert!(!selection.allows(FIREFOX.0, FIREFOX.1));
+        assert!(!selection.allows("VLC media player", "org.mpris.MediaPlayer2.vlc"));
+
+        assert!(PlayerSelection::default().allows(FIREFOX.0, FIREFOX.1));
+    }
+
+    #[test]
+    fn test_rank() {
+        let selection = PlayerSelection {
+            priority: vec!["spotify".to_string(), "firefox".to_string()],
+            ..PlayerSelection::default()
+        };
+
+        assert_eq!(selection.rank(SPOTIFY.0, SPOTIFY.1), 0);
+        assert_eq!(selection.rank(FIREFOX.0, FIREFOX.1), 1);
+        assert_eq!(
+            selection.rank("VLC media player", "org.mpris.MediaPlayer2.vlc"),
+            2
+        );
+    }
+
+    #[test]
+    fn test_sorted_players() {
+        let entry = |identity: &str, rank| PlayerEntry {
+            identity: identity.to_string(),
+            rank,
+        };
+
+        let players = HashMap::from([
+            (
+                "org.mpris.MediaPlayer2.vlc".to_string(),
+                entry("VLC media player", 2),
+            ),
+            (
+                "org.mpris.MediaPlayer2.spotify".to_string(),
+                entry("Spotify", 0),
+            ),
+            (
+                "org.mpris.MediaPlayer2.celluloid".to_string(),
+                entry("Celluloid", 2),
+            ),
+        ]);
+
+        let names = sorted_players(&players)
+            .into_iter()
+            .map(|player| player.name)
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["Spotify", "Celluloid", "VLC media player"]);
+    }
+}
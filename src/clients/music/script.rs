@@ -0,0 +1,363 @@
+use super::{
+    LoopStatus, MusicClient, PlayerState, PlayerUpdate, ProgressTick, Status, TICK_INTERVAL_MS,
+    Track,
+};
+use crate::script::{OutputStream, Script, ScriptMode};
+use crate::{arc_mut, await_sync, lock, send, spawn};
+use color_eyre::{Report, Result};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, watch};
+use tokio::time::sleep;
+use tracing::{debug, error};
+
+/// Shell commands used to watch and control a scripted player.
+///
+/// Any argument is passed to the command as `$0`.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Commands {
+    /// Long-running command which prints the player's state
+    /// as a JSON object each time it changes, one per line.
+    ///
+    /// The command is restarted if it exits.
+    pub watch: String,
+
+    /// Command to resume playback.
+    pub play: Option<String>,
+    /// Command to pause playback.
+    pub pause: Option<String>,
+    /// Command to skip to the next track.
+    pub next: Option<String>,
+    /// Command to go back to the previous track.
+    pub previous: Option<String>,
+    /// Command to seek to a position, in seconds.
+    pub seek: Option<String>,
+    /// Command to set the volume, as a percentage.
+    pub volume: Option<String>,
+    /// Command to set shuffle, to either `true` or `false`.
+    pub shuffle: Option<String>,
+    /// Command to set the loop status,
+    /// to one of `none`, `track` or `playlist`.
+    #[serde(rename = "loop")]
+    pub loop_status: Option<String>,
+}
+
+/// A single line of output from the watch command.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PlayerInfo {
+    state: PlayerState,
+    title: Option<String>,
+    album: Option<String>,
+    artist: Option<String>,
+    date: Option<String>,
+    disc: Option<u64>,
+    genre: Option<String>,
+    track: Option<u64>,
+    /// Path or URL to the album art.
+    cover: Option<String>,
+    file: Option<PathBuf>,
+    lyrics: Option<String>,
+    volume: Option<u8>,
+    shuffle: Option<bool>,
+    #[serde(rename = "loop")]
+    loop_status: Option<LoopStatus>,
+    /// Time into the track, in seconds.
+    position: Option<f64>,
+    /// Length of the track, in seconds.
+    duration: Option<f64>,
+}
+
+impl PlayerInfo {
+    fn track(&self) -> Option<Track> {
+        if self.title.is_none() && self.artist.is_none() && self.album.is_none() {
+            return None;
+        }
+
+        Some(Track {
+            title: self.title.clone(),
+            album: self.album.clone(),
+            artist: self.artist.clone(),
+            date: self.date.clone(),
+            disc: self.disc,
+            genre: self.genre.clone(),
+            track: self.track,
+            cover_path: self.cover.clone(),
+            file_path: self.file.clone(),
+            lyrics: self.lyrics.clone(),
+        })
+    }
+
+    fn status(&self, commands: &Commands) -> Status {
+        Status {
+            state: self.state,
+            volume_percent: self.volume,
+            playlist_position: 0,
+            can_go_next: commands.next.is_some(),
+            can_go_previous: commands.previous.is_some(),
+            shuffle: self.shuffle.filter(|_| commands.shuffle.is_some()),
+            loop_status: self.loop_status.filter(|_| commands.loop_status.is_some()),
+        }
+    }
+}
+
+/// The last state received from the watch command.
+#[derive(Debug)]
+struct State {
+    info: PlayerInfo,
+    received: Instant,
+}
+
+impl State {
+    /// Gets the progress through the current track,
+    /// counting on from the last reported position while playing.
+    fn progress(&self) -> ProgressTick {
+        let duration = self
+            .info
+            .duration
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok());
+
+        let elapsed = self
+            .info
+            .position
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            .map(|position| match self.info.state {
+                PlayerState::Playing => position + self.received.elapsed(),
+                _ => position,
+            })
+            .map(|elapsed| duration.map_or(elapsed, |duration| elapsed.min(duration)));
+
+        ProgressTick { duration, elapsed }
+    }
+}
+
+#[derive(Debug)]
+pub struct Client {
+    commands: Commands,
+    state: Arc<Mutex<Option<State>>>,
+    tx: broadcast::Sender<PlayerUpdate>,
+    _rx: broadcast::Receiver<PlayerUpdate>,
+}
+
+impl Client {
+    pub fn new(commands: Commands) -> Self {
+        let (tx, rx) = broadcast::channel(32);
+        let state = arc_mut!(None);
+        let (playing_tx, mut playing_rx) = watch::channel(false);
+
+        if commands.watch.is_empty() {
+            error!("No watch command configured for scripted player");
+        } else {
+            let script = Script {
+                mode: ScriptMode::Watch,
+                cmd: commands.watch.clone(),
+                ..Script::default()
+            };
+
+            let tx = tx.clone();
+            let state = state.clone();
+            let commands = commands.clone();
+
+            spawn(async move {
+                script
+                    .run(None, |output, _| match output {
+                        OutputStream::Stdout(line) => {
+                            Self::handle_line(&line, &commands, &state, &playing_tx, &tx);
+                        }
+                        OutputStream::Stderr(line) => error!("{line}"),
+                    })
+                    .await;
+            });
+        }
+
+        {
+            let tx = tx.clone();
+            let state = state.clone();
+
+            // the position is only counted on while playing,
+            // so there is nothing to tick otherwise
+            spawn(async move {
+                while playing_rx.wait_for(|playing| *playing).await.is_ok() {
+                    let tick = lock!(state).as_ref().map(State::progress);
+                    if let Some(tick) = tick {
+                        send!(tx, PlayerUpdate::ProgressTick(tick));
+                    }
+
+                    sleep(Duration::from_millis(TICK_INTERVAL_MS)).await;
+                }
+            });
+        }
+
+        Self {
+            commands,
+            state,
+            tx,
+            _rx: rx,
+        }
+    }
+
+    fn handle_line(
+        line: &str,
+        commands: &Commands,
+        state: &Mutex<Option<State>>,
+        playing: &watch::Sender<bool>,
+        tx: &broadcast::Sender<PlayerUpdate>,
+    ) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+
+        match serde_json::from_str::<PlayerInfo>(line) {
+            Ok(info) => {
+                debug!("Received player state: {info:?}");
+
+                let update = PlayerUpdate::Update(Box::new(info.track()), info.status(commands));
+                playing.send_replace(matches!(info.state, PlayerState::Playing));
+
+                let new_state = State {
+                    info,
+                    received: Instant::now(),
+                };
+
+                // send the reported position straight away, as ticks only run while playing
+                let tick = new_state.progress();
+                lock!(state).replace(new_state);
+
+                send!(tx, update);
+                send!(tx, PlayerUpdate::ProgressTick(tick));
+            }
+            Err(err) => error!("Invalid player state '{line}': {err}"),
+        }
+    }
+
+    /// Runs the command called `name`, passing `arg` if set.
+    fn run_command(&self, name: &str, cmd: Option<&String>, arg: Option<String>) -> Result<()> {
+        let cmd = cmd.ok_or_else(|| Report::msg(format!("No {name} command configured")))?;
+
+        let script = Script {
+            cmd: cmd.clone(),
+            ..Script::default()
+        };
+
+        let args = arg.map(|arg| vec![arg]);
+
+        match await_sync(script.get_output(args.as_deref()))? {
+            (OutputStream::Stdout(_), true) => Ok(()),
+            (OutputStream::Stderr(err) | OutputStream::Stdout(err), _) => Err(Report::msg(err)),
+        }
+    }
+}
+
+impl MusicClient for Client {
+    fn play(&self) -> Result<()> {
+        self.run_command("play", self.commands.play.as_ref(), None)
+    }
+
+    fn pause(&self) -> Result<()> {
+        self.run_command("pause", self.commands.pause.as_ref(), None)
+    }
+
+    fn next(&self) -> Result<()> {
+        self.run_command("next", self.commands.next.as_ref(), None)
+    }
+
+    fn prev(&self) -> Result<()> {
+        self.run_command("previous", self.commands.previous.as_ref(), None)
+    }
+
+    fn set_volume_percent(&self, vol: u8) -> Result<()> {
+        self.run_command(
+            "volume",
+            self.commands.volume.as_ref(),
+            Some(vol.to_string()),
+        )
+    }
+
+    fn seek(&self, duration: Duration) -> Result<()> {
+        self.run_command(
+            "seek",
+            self.commands.seek.as_ref(),
+            Some(duration.as_secs_f64().to_string()),
+        )
+    }
+
+    fn set_shuffle(&self, shuffle: bool) -> Result<()> {
+        self.run_command(
+            "shuffle",
+            self.commands.shuffle.as_ref(),
+            Some(shuffle.to_string()),
+        )
+    }
+
+    fn set_loop_status(&self, loop_status: LoopStatus) -> Result<()> {
+        let loop_status = match loop_status {
+            LoopStatus::None => "none",
+            LoopStatus::Track => "track",
+            LoopStatus::Playlist => "playlist",
+        };
+
+        self.run_command(
+            "loop",
+            self.commands.loop_status.as_ref(),
+            Some(loop_status.to_string()),
+        )
+    }
+
+    fn subscribe_change(&self) -> broadcast::Receiver<PlayerUpdate> {
+        debug!("Creating new subscription");
+        let rx = self.tx.subscribe();
+
+        let update = match lock!(self.state).as_ref() {
+            Some(state) => PlayerUpdate::Update(
+                Box::new(state.info.track()),
+                state.info.status(&self.commands),
+            ),
+            None => PlayerUpdate::Update(Box::new(None), Status::default()),
+        };
+
+        send!(self.tx, update);
+
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_info() {
+        let info: PlayerInfo = serde_json::from_str(
+            r#"{"state": "playing", "title": "Title", "artist": "Artist", "volume": 50, "loop": "track", "position": 12.5}"#,
+        )
+        .expect("to parse");
+
+        assert!(matches!(info.state, PlayerState::Playing));
+        assert_eq!(info.volume, Some(50));
+        assert_eq!(info.loop_status, Some(LoopStatus::Track));
+        assert_eq!(info.position, Some(12.5));
+        assert!(info.track().is_some());
+    }
+
+    #[test]
+    fn test_parse_stopped() {
+        let info: PlayerInfo = serde_json::from_str("{}").expect("to parse");
+
+        assert!(matches!(info.state, PlayerState::Stopped));
+        assert!(info.track().is_none());
+    }
+
+    #[test]
+    fn test_status_without_commands() {
+        let info: PlayerInfo = serde_json::from_str(r#"{"shuffle": true}"#).expect("to parse");
+        let status = info.status(&Commands::default());
+
+        assert_eq!(status.shuffle, None);
+        assert!(!status.can_go_next);
+        assert!(!status.can_go_previous);
+    }
+}
@@ -1,28 +1,43 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use crate::{register_fallible_client, spawn};
+use crate::{register_fallible_client, send, spawn};
 use color_eyre::Result;
 use futures_signals::signal::{Mutable, MutableSignalCloned};
+use tokio::sync::broadcast;
 use tracing::error;
 use zbus::export::ordered_stream::OrderedStreamExt;
-use zbus::fdo::PropertiesProxy;
+use zbus::fdo::PropertiesChanged;
+use zbus::message::Type as MessageType;
+use zbus::proxy::CacheProperties;
 use zbus::{
-    Connection,
+    Connection, MatchRule, MessageStream,
     names::InterfaceName,
     proxy,
-    zvariant::{ObjectPath, Str},
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Str, Value},
 };
 
 const DBUS_BUS: &str = "org.freedesktop.NetworkManager";
 const DBUS_PATH: &str = "/org/freedesktop/NetworkManager";
 const DBUS_INTERFACE: &str = "org.freedesktop.NetworkManager";
+const WIRELESS_INTERFACE: &str = "org.freedesktop.NetworkManager.Device.Wireless";
+const SETTINGS_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings";
+const ACCESS_POINT_INTERFACE: &str = "org.freedesktop.NetworkManager.AccessPoint";
+
+/// `NM_DEVICE_TYPE_WIFI`
+const DEVICE_TYPE_WIFI: u32 = 2;
+/// `NM_802_11_AP_FLAGS_PRIVACY`
+const AP_FLAGS_PRIVACY: u32 = 0x1;
 
 #[derive(Debug)]
 pub struct Client {
     client_state: Mutable<ClientState>,
+    connection_details: Mutable<ConnectionDetails>,
     interface_name: InterfaceName<'static>,
     dbus_connection: Connection,
-    props_proxy: PropertiesProxy<'static>,
+
+    networks_tx: broadcast::Sender<()>,
+    _networks_rx: broadcast::Receiver<()>,
 }
 
 #[derive(Clone, Debug)]
@@ -36,6 +51,81 @@ pub enum ClientState {
     Unknown,
 }
 
+/// Details of the active connections.
+///
+/// Values which do not apply to the current connections are `None`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConnectionDetails {
+    /// Name of the primary connection's network interface, such as `wlan0`.
+    pub interface: Option<String>,
+    /// First IPv4 address of the primary connection.
+    pub ipv4_address: Option<String>,
+    /// First IPv6 address of the primary connection.
+    pub ipv6_address: Option<String>,
+    /// SSID of the connected Wi-Fi network.
+    pub ssid: Option<String>,
+    /// Signal strength of the connected Wi-Fi network, as a percentage.
+    pub strength: Option<u8>,
+    /// Name of the active VPN connection.
+    pub vpn_name: Option<String>,
+}
+
+/// The networks which can be connected to,
+/// alongside the global radio switches.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Networks {
+    /// Wi-Fi access points currently in range.
+    pub access_points: Vec<AccessPoint>,
+    /// Connections saved in NetworkManager's settings.
+    pub connections: Vec<SavedConnection>,
+    pub wireless_enabled: bool,
+    pub networking_enabled: bool,
+}
+
+impl Networks {
+    /// Gets the saved Wi-Fi connection for `ssid`,
+    /// preferring one which is currently active.
+    pub fn wifi_connection(&self, ssid: &str) -> Option<&SavedConnection> {
+        let mut connections = self.connections.iter().filter(
+            |connection| matches!(&connection.kind, ConnectionKind::Wifi { ssid: s } if s == ssid),
+        );
+
+        connections
+            .clone()
+            .find(|connection| connection.active_path.is_some())
+            .or_else(|| connections.next())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessPoint {
+    pub path: String,
+    /// Path of the Wi-Fi device which can see the access point.
+    pub device: String,
+    pub ssid: String,
+    /// Signal strength, as a percentage.
+    pub strength: u8,
+    /// Whether the network requires authentication.
+    pub secured: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SavedConnection {
+    pub path: String,
+    /// The user-facing connection name.
+    pub id: String,
+    pub kind: ConnectionKind,
+    /// Path of the active connection, while this connection is active.
+    pub active_path: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionKind {
+    Wifi { ssid: String },
+    Vpn,
+    Other,
+}
+
 #[proxy(
     default_service = "org.freedesktop.NetworkManager",
     interface = "org.freedesktop.NetworkManager",
@@ -59,30 +149,173 @@ trait NetworkManagerDbus {
 
     #[zbus(property)]
     fn wireless_enabled(&self) -> Result<bool>;
+
+    #[zbus(property)]
+    fn set_wireless_enabled(&self, enabled: bool) -> zbus::Result<()>;
+
+    fn activate_connection(
+        &self,
+        connection: &ObjectPath<'_>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    fn add_and_activate_connection(
+        &self,
+        connection: HashMap<&str, HashMap<&str, Value<'_>>>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> zbus::Result<(OwnedObjectPath, OwnedObjectPath)>;
+
+    fn deactivate_connection(&self, active_connection: &ObjectPath<'_>) -> zbus::Result<()>;
+
+    fn enable(&self, enable: bool) -> zbus::Result<()>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.Connection.Active",
+    assume_defaults = false
+)]
+trait ActiveConnectionDbus {
+    #[zbus(property)]
+    fn id(&self) -> Result<String>;
+
+    #[zbus(property, name = "Type")]
+    fn type_(&self) -> Result<String>;
+
+    #[zbus(property)]
+    fn devices(&self) -> Result<Vec<OwnedObjectPath>>;
+
+    #[zbus(property)]
+    fn specific_object(&self) -> Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn ip4_config(&self) -> Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn ip6_config(&self) -> Result<OwnedObjectPath>;
+
+    #[zbus(property, name = "Connection")]
+    fn settings_connection(&self) -> Result<OwnedObjectPath>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.Device",
+    assume_defaults = false
+)]
+trait DeviceDbus {
+    #[zbus(property, name = "Interface")]
+    fn interface_name(&self) -> Result<String>;
+
+    #[zbus(property)]
+    fn device_type(&self) -> Result<u32>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.Device.Wireless",
+    assume_defaults = false
+)]
+trait WirelessDeviceDbus {
+    fn get_all_access_points(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    fn request_scan(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.AccessPoint",
+    assume_defaults = false
+)]
+trait AccessPointDbus {
+    #[zbus(property)]
+    fn ssid(&self) -> Result<Vec<u8>>;
+
+    #[zbus(property)]
+    fn strength(&self) -> Result<u8>;
+
+    #[zbus(property)]
+    fn flags(&self) -> Result<u32>;
+
+    #[zbus(property)]
+    fn wpa_flags(&self) -> Result<u32>;
+
+    #[zbus(property)]
+    fn rsn_flags(&self) -> Result<u32>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.Settings",
+    default_path = "/org/freedesktop/NetworkManager/Settings"
+)]
+trait SettingsDbus {
+    fn list_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.Settings.Connection",
+    assume_defaults = false
+)]
+trait SettingsConnectionDbus {
+    fn get_settings(&self) -> zbus::Result<HashMap<String, HashMap<String, OwnedValue>>>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.IP4Config",
+    assume_defaults = false
+)]
+trait Ip4ConfigDbus {
+    #[zbus(property)]
+    fn address_data(&self) -> Result<Vec<HashMap<String, OwnedValue>>>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.IP6Config",
+    assume_defaults = false
+)]
+trait Ip6ConfigDbus {
+    #[zbus(property)]
+    fn address_data(&self) -> Result<Vec<HashMap<String, OwnedValue>>>;
 }
 
 impl Client {
     async fn new() -> Result<Self> {
         let client_state = Mutable::new(ClientState::Unknown);
+        let connection_details = Mutable::new(ConnectionDetails::default());
         let dbus_connection = Connection::system().await?;
         let interface_name = InterfaceName::from_static_str(DBUS_INTERFACE)?;
-        let props_proxy = PropertiesProxy::builder(&dbus_connection)
-            .destination(DBUS_BUS)?
-            .path(DBUS_PATH)?
-            .build()
-            .await?;
+        let (networks_tx, networks_rx) = broadcast::channel(8);
 
         Ok(Self {
             client_state,
+            connection_details,
             interface_name,
             dbus_connection,
-            props_proxy,
+            networks_tx,
+            _networks_rx: networks_rx,
         })
     }
 
     async fn run(&self) -> Result<()> {
         let proxy = NetworkManagerDbusProxy::new(&self.dbus_connection).await?;
 
+        // subscribe before reading to avoid missing changes in between.
+        // this covers NetworkManager itself as well as the objects details are read from.
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .sender(DBUS_BUS)?
+            .interface("org.freedesktop.DBus.Properties")?
+            .member("PropertiesChanged")?
+            .build();
+
+        let mut stream = MessageStream::for_match_rule(rule, &self.dbus_connection, None).await?;
+
         let mut primary_connection = proxy.primary_connection().await?;
         let mut primary_connection_type = proxy.primary_connection_type().await?;
         let mut wireless_enabled = proxy.wireless_enabled().await?;
@@ -93,10 +326,57 @@ impl Client {
             wireless_enabled,
         ));
 
-        let mut stream = self.props_proxy.receive_properties_changed().await?;
-        while let Some(change) = stream.next().await {
+        let mut watched_paths = self.update_details(&proxy, &primary_connection).await;
+
+        while let Some(message) = stream.next().await {
+            let message = message?;
+            let path = message.header().path().map(ToString::to_string);
+
+            let Some(change) = PropertiesChanged::from_message(message) else {
+                continue;
+            };
+
             let args = change.args()?;
-            if args.interface_name != self.interface_name {
+
+            if path.as_deref() != Some(DBUS_PATH) || args.interface_name != self.interface_name {
+                // access points appearing or disappearing, or saved connections changing.
+                // other wireless properties such as the bitrate change often and can be ignored.
+                let networks_changed = match args.interface_name.as_str() {
+                    WIRELESS_INTERFACE => ["AccessPoints", "ActiveAccessPoint", "LastScan"]
+                        .iter()
+                        .any(|key| args.changed_properties.contains_key(*key)),
+                    SETTINGS_INTERFACE => true,
+                    _ => false,
+                };
+
+                if networks_changed {
+                    send!(self.networks_tx, ());
+                }
+
+                // a change to one of the objects the details were read from
+                if path.is_some_and(|path| watched_paths.contains(&path)) {
+                    if args.interface_name.as_str() == ACCESS_POINT_INTERFACE {
+                        // the strength of the active access point changes often,
+                        // so is updated on its own rather than re-reading every detail.
+                        // other properties such as when it was last seen are ignored.
+                        if args.changed_properties.contains_key("Ssid") {
+                            watched_paths = self.update_details(&proxy, &primary_connection).await;
+                        } else if let Some(strength) = args
+                            .changed_properties
+                            .get("Strength")
+                            .and_then(|value| value.downcast_ref::<u8>().ok())
+                        {
+                            let mut details = self.connection_details.get_cloned();
+                            if details.strength != Some(strength) {
+                                details.strength = Some(strength);
+                                self.connection_details.set(details);
+                            }
+                        }
+                    } else {
+                        watched_paths = self.update_details(&proxy, &primary_connection).await;
+                    }
+                }
+
                 continue;
             }
 
@@ -123,14 +403,416 @@ impl Client {
                     wireless_enabled,
                 ));
             }
+
+            if relevant_prop_changed || changed_props.contains_key("ActiveConnections") {
+                watched_paths = self.update_details(&proxy, &primary_connection).await;
+            }
+
+            if ["ActiveConnections", "WirelessEnabled", "NetworkingEnabled"]
+                .iter()
+                .any(|key| changed_props.contains_key(*key))
+            {
+                send!(self.networks_tx, ());
+            }
         }
 
         Ok(())
     }
 
+    /// Re-reads the connection details, publishing them if they changed.
+    ///
+    /// Returns the paths of the objects the details were read from,
+    /// so that they can be re-read when any of those objects change.
+    async fn update_details(
+        &self,
+        proxy: &NetworkManagerDbusProxy<'_>,
+        primary_connection: &str,
+    ) -> HashSet<String> {
+        let mut watched_paths = HashSet::new();
+
+        match self
+            .read_details(proxy, primary_connection, &mut watched_paths)
+            .await
+        {
+            Ok(details) => {
+                if self.connection_details.get_cloned() != details {
+                    self.connection_details.set(details);
+                }
+            }
+            // objects can be removed while being read,
+            // in which case another update will follow.
+            Err(err) => error!("Failed to read connection details: {err:?}"),
+        }
+
+        watched_paths
+    }
+
+    async fn read_details(
+        &self,
+        proxy: &NetworkManagerDbusProxy<'_>,
+        primary_connection: &str,
+        watched_paths: &mut HashSet<String>,
+    ) -> Result<ConnectionDetails> {
+        let mut details = ConnectionDetails::default();
+
+        for path in proxy.active_connections().await? {
+            let path = OwnedObjectPath::from(path);
+            watched_paths.insert(path.to_string());
+
+            let active = ActiveConnectionDbusProxy::builder(&self.dbus_connection)
+                .path(path.clone())?
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?;
+
+            match active.type_().await?.as_str() {
+                "vpn" | "wireguard" if details.vpn_name.is_none() => {
+                    details.vpn_name = Some(active.id().await?);
+                }
+                "802-11-wireless" if details.ssid.is_none() => {
+                    let access_point = active.specific_object().await?;
+
+                    if let Some(access_point) = object_path(access_point, watched_paths) {
+                        let access_point = AccessPointDbusProxy::builder(&self.dbus_connection)
+                            .path(access_point)?
+                            .cache_properties(CacheProperties::No)
+                            .build()
+                            .await?;
+
+                        details.ssid =
+                            Some(String::from_utf8_lossy(&access_point.ssid().await?).into_owned());
+                        details.strength = Some(access_point.strength().await?);
+                    }
+                }
+                _ => {}
+            }
+
+            if path.as_str() != primary_connection {
+                continue;
+            }
+
+            if let Some(device) = active.devices().await?.into_iter().next() {
+                let device = DeviceDbusProxy::builder(&self.dbus_connection)
+                    .path(device)?
+                    .cache_properties(CacheProperties::No)
+                    .build()
+                    .await?;
+
+                details.interface = Some(device.interface_name().await?);
+            }
+
+            if let Some(config) = object_path(active.ip4_config().await?, watched_paths) {
+                let config = Ip4ConfigDbusProxy::builder(&self.dbus_connection)
+                    .path(config)?
+                    .cache_properties(CacheProperties::No)
+                    .build()
+                    .await?;
+
+                details.ipv4_address = first_address(&config.address_data().await?);
+            }
+
+            if let Some(config) = object_path(active.ip6_config().await?, watched_paths) {
+                let config = Ip6ConfigDbusProxy::builder(&self.dbus_connection)
+                    .path(config)?
+                    .cache_properties(CacheProperties::No)
+                    .build()
+                    .await?;
+
+                details.ipv6_address = first_address(&config.address_data().await?);
+            }
+        }
+
+        Ok(details)
+    }
+
     pub fn subscribe(&self) -> MutableSignalCloned<ClientState> {
         self.client_state.signal_cloned()
     }
+
+    pub fn subscribe_details(&self) -> MutableSignalCloned<ConnectionDetails> {
+        self.connection_details.signal_cloned()
+    }
+
+    /// Creates a receiver which is notified
+    /// each time the result of [`Client::networks`] may have changed.
+    pub fn subscribe_networks(&self) -> broadcast::Receiver<()> {
+        self.networks_tx.subscribe()
+    }
+
+    /// Reads the available access points and saved connections.
+    pub async fn networks(&self) -> Result<Networks> {
+        let proxy = NetworkManagerDbusProxy::new(&self.dbus_connection).await?;
+
+        // settings connection path -> active connection path
+        let mut active_connections = HashMap::new();
+        for path in proxy.active_connections().await? {
+            let path = OwnedObjectPath::from(path);
+
+            let active = ActiveConnectionDbusProxy::builder(&self.dbus_connection)
+                .path(path.clone())?
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?;
+
+            active_connections.insert(
+                active.settings_connection().await?.to_string(),
+                path.to_string(),
+            );
+        }
+
+        let settings = SettingsDbusProxy::new(&self.dbus_connection).await?;
+
+        let mut connections = vec![];
+        for path in settings.list_connections().await? {
+            let connection = SettingsConnectionDbusProxy::builder(&self.dbus_connection)
+                .path(path.clone())?
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?;
+
+            let path = path.to_string();
+            let active_path = active_connections.get(&path).cloned();
+
+            if let Some(connection) =
+                saved_connection(path, active_path, connection.get_settings().await?)
+            {
+                connections.push(connection);
+            }
+        }
+
+        let mut access_points = vec![];
+        for (device_path, wireless) in self.wifi_devices(&proxy).await? {
+            for path in wireless.get_all_access_points().await? {
+                let access_point = AccessPointDbusProxy::builder(&self.dbus_connection)
+                    .path(path.clone())?
+                    .cache_properties(CacheProperties::No)
+                    .build()
+                    .await?;
+
+                let secured = access_point.flags().await? & AP_FLAGS_PRIVACY != 0
+                    || access_point.wpa_flags().await? != 0
+                    || access_point.rsn_flags().await? != 0;
+
+                access_points.push(AccessPoint {
+                    path: path.to_string(),
+                    device: device_path.to_string(),
+                    ssid: String::from_utf8_lossy(&access_point.ssid().await?).into_owned(),
+                    strength: access_point.strength().await?,
+                    secured,
+                });
+            }
+        }
+
+        Ok(Networks {
+            access_points,
+            connections,
+            wireless_enabled: proxy.wireless_enabled().await?,
+            networking_enabled: proxy.networking_enabled().await?,
+        })
+    }
+
+    /// Asks all Wi-Fi devices to scan for access points.
+    /// Results arrive over the next few seconds.
+    pub async fn request_scan(&self) -> Result<()> {
+        let proxy = NetworkManagerDbusProxy::new(&self.dbus_connection).await?;
+
+        for (_, wireless) in self.wifi_devices(&proxy).await? {
+            wireless.request_scan(HashMap::new()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the path and wireless proxy of each Wi-Fi device.
+    async fn wifi_devices(
+        &self,
+        proxy: &NetworkManagerDbusProxy<'_>,
+    ) -> Result<Vec<(OwnedObjectPath, WirelessDeviceDbusProxy<'static>)>> {
+        let mut devices = vec![];
+
+        for path in proxy.devices().await? {
+            let path = OwnedObjectPath::from(path);
+
+            let device = DeviceDbusProxy::builder(&self.dbus_connection)
+                .path(path.clone())?
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?;
+
+            if device.device_type().await? != DEVICE_TYPE_WIFI {
+                continue;
+            }
+
+            let wireless = WirelessDeviceDbusProxy::builder(&self.dbus_connection)
+                .path(path.clone())?
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?;
+
+            devices.push((path, wireless));
+        }
+
+        Ok(devices)
+    }
+
+    /// Activates the saved connection at `path`.
+    ///
+    /// A suitable device is picked automatically.
+    pub async fn activate_connection(&self, path: &str) -> Result<()> {
+        let proxy = NetworkManagerDbusProxy::new(&self.dbus_connection).await?;
+
+        proxy
+            .activate_connection(&ObjectPath::try_from(path)?, &no_object(), &no_object())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Creates and activates a new connection to an access point,
+    /// using NetworkManager's defaults for the network.
+    ///
+    /// Secured networks require a secret agent, such as the desktop's keyring prompt,
+    /// to supply the password.
+    pub async fn connect_access_point(&self, access_point: &AccessPoint) -> Result<()> {
+        let proxy = NetworkManagerDbusProxy::new(&self.dbus_connection).await?;
+
+        proxy
+            .add_and_activate_connection(
+                HashMap::new(),
+                &ObjectPath::try_from(access_point.device.as_str())?,
+                &ObjectPath::try_from(access_point.path.as_str())?,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Deactivates the active connection at `path`.
+    pub async fn deactivate_connection(&self, path: &str) -> Result<()> {
+        let proxy = NetworkManagerDbusProxy::new(&self.dbus_connection).await?;
+
+        proxy
+            .deactivate_connection(&ObjectPath::try_from(path)?)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_wireless_enabled(&self, enabled: bool) -> Result<()> {
+        let proxy = NetworkManagerDbusProxy::new(&self.dbus_connection).await?;
+        proxy.set_wireless_enabled(enabled).await?;
+        Ok(())
+    }
+
+    pub async fn set_networking_enabled(&self, enabled: bool) -> Result<()> {
+        let proxy = NetworkManagerDbusProxy::new(&self.dbus_connection).await?;
+        proxy.enable(enabled).await?;
+        Ok(())
+    }
+}
+
+/// NetworkManager uses `/` in place of an object path to indicate no object.
+fn no_object() -> ObjectPath<'static> {
+    ObjectPath::from_static_str_unchecked("/")
+}
+
+/// Creates a saved connection from its settings,
+/// or `None` for connections which should not be shown, such as loopback.
+fn saved_connection(
+    path: String,
+    active_path: Option<String>,
+    mut settings: HashMap<String, HashMap<String, OwnedValue>>,
+) -> Option<SavedConnection> {
+    let get_str = |settings: &HashMap<String, HashMap<String, OwnedValue>>, key: &str| {
+        settings
+            .get("connection")
+            .and_then(|connection| connection.get(key))
+            .and_then(|value| value.downcast_ref::<&str>().ok())
+            .map(ToString::to_string)
+    };
+
+    let id = get_str(&settings, "id")?;
+
+    let kind = match get_str(&settings, "type")?.as_str() {
+        "802-11-wireless" => {
+            let ssid = settings
+                .get_mut("802-11-wireless")
+                .and_then(|wireless| wireless.remove("ssid"))
+                .and_then(|ssid| Vec::<u8>::try_from(Value::from(ssid)).ok())?;
+
+            ConnectionKind::Wifi {
+                ssid: String::from_utf8_lossy(&ssid).into_owned(),
+            }
+        }
+        "vpn" | "wireguard" => ConnectionKind::Vpn,
+        "loopback" => return None,
+        _ => ConnectionKind::Other,
+    };
+
+    Some(SavedConnection {
+        path,
+        id,
+        kind,
+        active_path,
+    })
+}
+
+/// Gets `path` if it points to an object, adding it to the watched paths.
+/// NetworkManager uses `/` to indicate no object.
+fn object_path(
+    path: OwnedObjectPath,
+    watched_paths: &mut HashSet<String>,
+) -> Option<OwnedObjectPath> {
+    if path.as_str() == "/" {
+        None
+    } else {
+        watched_paths.insert(path.to_string());
+        Some(path)
+    }
+}
+
+/// Gets the first address from an IP config's `AddressData` property.
+fn first_address(address_data: &[HashMap<String, OwnedValue>]) -> Option<String> {
+    address_data.iter().find_map(|address| {
+        address
+            .get("address")
+            .and_then(|address| address.downcast_ref::<&str>().ok())
+            .map(ToString::to_string)
+    })
+}
+
+impl ClientState {
+    /// Gets the state name, as exposed over the ironvar namespace.
+    #[cfg(feature = "ipc")]
+    const fn name(&self) -> &'static str {
+        match self {
+            Self::WiredConnected => "wired_connected",
+            Self::WifiConnected => "wifi_connected",
+            Self::CellularConnected => "cellular_connected",
+            Self::VpnConnected => "vpn_connected",
+            Self::WifiDisconnected => "wifi_disconnected",
+            Self::Offline => "offline",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// Registers the `network` ironvar namespace,
+/// which exposes the overall connection state.
+#[cfg(feature = "ipc")]
+pub fn register_namespace(client: &Arc<Client>) {
+    use crate::ironvar::ClientNamespace;
+    use futures_lite::StreamExt;
+    use futures_signals::signal::SignalExt;
+
+    let namespace = ClientNamespace::register("network");
+    let mut stream = client.subscribe().to_stream();
+
+    spawn(async move {
+        while let Some(state) = stream.next().await {
+            namespace.set("state", state.name());
+        }
+    });
 }
 
 pub async fn create_client() -> Result<Arc<Client>> {
@@ -0,0 +1,27 @@
+use std::collections::HashMap;
+use zbus::proxy;
+use zbus::zvariant::OwnedValue;
+
+/// The `power-profiles-daemon` interface.
+///
+/// Newer versions of the daemon provide this as `org.freedesktop.UPower.PowerProfiles`,
+/// while older versions use `net.hadess.PowerProfiles`.
+/// Both have the same members.
+#[proxy(
+    interface = "org.freedesktop.UPower.PowerProfiles",
+    default_service = "org.freedesktop.UPower.PowerProfiles",
+    default_path = "/org/freedesktop/UPower/PowerProfiles"
+)]
+pub trait PowerProfiles {
+    #[zbus(property)]
+    fn active_profile(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn set_active_profile(&self, profile: &str) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn profiles(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+
+    #[zbus(property)]
+    fn performance_degraded(&self) -> zbus::Result<String>;
+}
@@ -0,0 +1,186 @@
+mod dbus;
+
+use crate::{lock, register_fallible_client, send, spawn};
+use color_eyre::Result;
+use dbus::PowerProfilesProxy;
+use futures_lite::StreamExt;
+use std::sync::{Arc, Mutex};
+use tokio::select;
+use tokio::sync::broadcast;
+use tracing::debug;
+use zbus::Connection;
+
+/// The service name, object path and interface used by `power-profiles-daemon`.
+type Service = (&'static str, &'static str, &'static str);
+
+const SERVICE: Service = (
+    "org.freedesktop.UPower.PowerProfiles",
+    "/org/freedesktop/UPower/PowerProfiles",
+    "org.freedesktop.UPower.PowerProfiles",
+);
+
+/// Used by `power-profiles-daemon` before version 0.20.
+const LEGACY_SERVICE: Service = (
+    "net.hadess.PowerProfiles",
+    "/net/hadess/PowerProfiles",
+    "net.hadess.PowerProfiles",
+);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct State {
+    /// The name of the active profile, such as `balanced`.
+    pub active: String,
+    /// The names of all available profiles,
+    /// in the order provided by the daemon.
+    pub profiles: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Client {
+    proxy: PowerProfilesProxy<'static>,
+    state: Arc<Mutex<State>>,
+
+    tx: broadcast::Sender<State>,
+    _rx: broadcast::Receiver<State>,
+}
+
+impl Client {
+    pub async fn new() -> Result<Arc<Self>> {
+        let connection = Box::pin(Connection::system()).await?;
+
+        let proxy = match create_proxy(&connection, SERVICE).await {
+            Ok(proxy) => proxy,
+            Err(err) => {
+                debug!("Failed to connect to power profiles service, trying legacy name: {err}");
+                create_proxy(&connection, LEGACY_SERVICE).await?
+            }
+        };
+
+        let state = State {
+            active: proxy.active_profile().await?,
+            profiles: read_profiles(&proxy).await,
+        };
+
+        let (tx, rx) = broadcast::channel(8);
+
+        let client = Arc::new(Self {
+            proxy,
+            state: Arc::new(Mutex::new(state)),
+            tx,
+            _rx: rx,
+        });
+
+        {
+            let proxy = client.proxy.clone();
+            let state = client.state.clone();
+            let tx = client.tx.clone();
+
+            spawn(async move {
+                let mut active_stream = proxy.receive_active_profile_changed().await;
+                let mut profiles_stream = proxy.receive_profiles_changed().await;
+
+                loop {
+                    select! {
+                        Some(change) = active_stream.next() => {
+                            let Ok(active) = change.get().await else {
+                                continue;
+                            };
+
+                            lock!(state).active = active;
+                        }
+                        Some(_) = profiles_stream.next() => {
+                            lock!(state).profiles = read_profiles(&proxy).await;
+                        }
+                        else => break,
+                    }
+
+                    let state = lock!(state).clone();
+                    send!(tx, state);
+                }
+            });
+        }
+
+        Ok(client)
+    }
+
+    /// Gets the current active and available profiles.
+    pub fn state(&self) -> State {
+        lock!(self.state).clone()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<State> {
+        self.tx.subscribe()
+    }
+
+    /// Sets the active profile.
+    pub async fn set_profile(&self, profile: &str) -> Result<()> {
+        self.proxy.set_active_profile(profile).await?;
+        Ok(())
+    }
+}
+
+/// Creates a proxy for the daemon using `service`,
+/// checking the service is available by reading the active profile.
+async fn create_proxy(
+    connection: &Connection,
+    (destination, path, interface): Service,
+) -> Result<PowerProfilesProxy<'static>> {
+    let proxy = PowerProfilesProxy::builder(connection)
+        .destination(destination)?
+        .path(path)?
+        .interface(interface)?
+        .build()
+        .await?;
+
+    proxy.active_profile().await?;
+
+    Ok(proxy)
+}
+
+async fn read_profiles(proxy: &PowerProfilesProxy<'_>) -> Vec<String> {
+    match proxy.profiles().await {
+        Ok(profiles) => profiles
+            .iter()
+            .filter_map(|profile| {
+                profile
+                    .get("Profile")
+                    .and_then(|name| name.downcast_ref::<&str>().ok())
+                    .map(ToString::to_string)
+            })
+            .collect(),
+        Err(err) => {
+            debug!("Failed to read power profiles: {err}");
+            vec![]
+        }
+    }
+}
+
+/// Registers the `power_profile` ironvar namespace,
+/// which exposes the active profile.
+#[cfg(feature = "ipc")]
+pub fn register_namespace(client: &Arc<Client>) {
+    use crate::ironvar::ClientNamespace;
+    use tokio::sync::broadcast::error::RecvError;
+
+    let namespace = ClientNamespace::register("power_profile");
+    let mut rx = client.subscribe();
+
+    let state = client.state();
+    namespace.set("active", &state.active);
+    namespace.set("profiles", state.profiles.join(","));
+
+    spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(state) => {
+                    namespace.set("active", &state.active);
+                    namespace.set("profiles", state.profiles.join(","));
+                }
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+register_fallible_client!(Client, power_profiles);
@@ -149,6 +149,8 @@ pub struct Client {
     components: Mutex<Components>,
     networks: Mutex<Networks>,
     load_average: Mutex<LoadAvg>,
+    #[cfg(feature = "ipc")]
+    namespace_values: crate::ironvar::NamespaceValues,
 }
 
 impl Client {
@@ -167,31 +169,46 @@ impl Client {
             components: Mutex::new(components),
             networks: Mutex::new(networks),
             load_average: Mutex::new(load_average),
+            #[cfg(feature = "ipc")]
+            namespace_values: crate::ironvar::NamespaceValues::default(),
         }
     }
 
     pub fn refresh_cpu(&self) {
         lock!(self.system).refresh_cpu_all();
+        self.refreshed();
     }
 
     pub fn refresh_memory(&self) {
         lock!(self.system).refresh_memory();
+        self.refreshed();
     }
 
     pub fn refresh_network(&self) {
         lock!(self.networks).refresh(true);
+        self.refreshed();
     }
 
     pub fn refresh_temps(&self) {
         lock!(self.components).refresh(true);
+        self.refreshed();
     }
 
     pub fn refresh_disks(&self) {
         lock!(self.disks).refresh(true);
+        self.refreshed();
     }
 
     pub fn refresh_load_average(&self) {
         *lock!(self.load_average) = System::load_average();
+        self.refreshed();
+    }
+
+    /// Pushes any values which changed in the last refresh
+    /// to ironvar subscribers.
+    fn refreshed(&self) {
+        #[cfg(feature = "ipc")]
+        self.namespace_values.update("sysinfo", self);
     }
 
     pub fn cpu_frequency(&self) -> ValueSet {
@@ -2,12 +2,14 @@
 /// <https://github.com/pop-os/upower-dbus/blob/main/LICENSE>
 // Copyright 2021 System76 <info@system76.com>
 // SPDX-License-Identifier: MPL-2.0
+use serde::Deserialize;
 use zbus::proxy;
 use zbus::zvariant::OwnedValue;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, OwnedValue)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, OwnedValue)]
 #[repr(u32)]
 pub enum BatteryState {
+    #[default]
     Unknown = 0,
     Charging = 1,
     Discharging = 2,
@@ -17,9 +19,12 @@ pub enum BatteryState {
     PendingDischarge = 6,
 }
 
-#[derive(Debug, Copy, Clone, OwnedValue)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, OwnedValue, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[repr(u32)]
 pub enum BatteryType {
+    #[default]
     Unknown = 0,
     LinePower = 1,
     Battery = 2,
@@ -29,6 +34,26 @@ pub enum BatteryType {
     Keyboard = 6,
     Pda = 7,
     Phone = 8,
+    MediaPlayer = 9,
+    Tablet = 10,
+    Computer = 11,
+    GamingInput = 12,
+    Pen = 13,
+    Touchpad = 14,
+    Modem = 15,
+    Network = 16,
+    Headset = 17,
+    Speakers = 18,
+    Headphones = 19,
+    Video = 20,
+    OtherAudio = 21,
+    RemoteControl = 22,
+    Printer = 23,
+    Scanner = 24,
+    Camera = 25,
+    Wearable = 26,
+    Toy = 27,
+    BluetoothGeneric = 28,
 }
 
 #[derive(Debug, Copy, Clone, OwnedValue)]
@@ -111,6 +136,12 @@ pub trait Device {
     #[zbus(property)]
     fn temperature(&self) -> zbus::Result<f64>;
 
+    #[zbus(property)]
+    fn time_to_empty(&self) -> zbus::Result<i64>;
+
+    #[zbus(property)]
+    fn time_to_full(&self) -> zbus::Result<i64>;
+
     #[zbus(property, name = "Type")]
     fn type_(&self) -> zbus::Result<BatteryType>;
 
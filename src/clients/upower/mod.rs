@@ -1,33 +1,551 @@
 mod dbus;
 
-use crate::clients::ClientResult;
-use crate::register_fallible_client;
-use dbus::UPowerProxy;
-use std::sync::Arc;
+use crate::{lock, register_fallible_client, send, spawn};
+use color_eyre::Result;
+use dbus::{DeviceProxy, UPowerProxy};
+use futures_lite::StreamExt;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{debug, error};
+use zbus::Connection;
 use zbus::fdo::PropertiesProxy;
+use zbus::names::InterfaceName;
 use zbus::proxy::CacheProperties;
 
-pub use dbus::BatteryState;
+pub use dbus::{BatteryState, BatteryType};
 
-pub async fn create_display_proxy() -> ClientResult<PropertiesProxy<'static>> {
-    let dbus = Box::pin(zbus::Connection::system()).await?;
+const DBUS_BUS: &str = "org.freedesktop.UPower";
+const DEVICE_INTERFACE: &str = "org.freedesktop.UPower.Device";
 
-    let device_proxy = UPowerProxy::new(&dbus).await?;
+/// The state of a single UPower device.
+///
+/// Properties which the UPower implementation does not provide
+/// are left at their default values.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Device {
+    /// The D-Bus object path, which uniquely identifies the device.
+    pub path: String,
+    pub percentage: f64,
+    pub icon_name: String,
+    pub state: BatteryState,
+    pub time_to_full: i64,
+    pub time_to_empty: i64,
+    pub model: String,
+    pub device_type: BatteryType,
+}
+
+impl Device {
+    /// Reads the current properties of the device on `proxy`.
+    async fn read(path: String, proxy: &DeviceProxy<'_>) -> Self {
+        Self {
+            percentage: or_default(proxy.percentage().await, &path, "Percentage"),
+            icon_name: or_default(proxy.icon_name().await, &path, "IconName"),
+            state: or_default(proxy.state().await, &path, "State"),
+            time_to_full: or_default(proxy.time_to_full().await, &path, "TimeToFull"),
+            time_to_empty: or_default(proxy.time_to_empty().await, &path, "TimeToEmpty"),
+            model: or_default(proxy.model().await, &path, "Model"),
+            device_type: or_default(proxy.type_().await, &path, "Type"),
+            path,
+        }
+    }
+}
+
+/// Falls back to the default value for a property which could not be read,
+/// as not all UPower implementations provide every property.
+fn or_default<T: Default>(result: zbus::Result<T>, path: &str, property: &str) -> T {
+    result.unwrap_or_else(|err| {
+        debug!("Failed to read property '{property}' for upower device '{path}': {err}");
+        T::default()
+    })
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A device was added, or its properties changed.
+    Update(Device),
+    /// The device with this path was removed.
+    Remove(String),
+}
+
+#[derive(Debug)]
+pub struct Client {
+    connection: Connection,
+    display_path: String,
+
+    devices: Arc<Mutex<HashMap<String, Device>>>,
+    tasks: Mutex<HashMap<String, JoinHandle<()>>>,
+
+    tx: broadcast::Sender<Event>,
+    _rx: broadcast::Receiver<Event>,
+}
+
+impl Client {
+    /// Creates a new client connected to UPower on the system bus.
+    pub async fn new() -> Result<Arc<Self>> {
+        let connection = Box::pin(Connection::system()).await?;
+        Self::with_connection(connection).await
+    }
+
+    /// Creates a new client connected to UPower on `connection`.
+    ///
+    /// The display device, and all devices currently known to UPower, are watched immediately.
+    /// Devices added or removed later are tracked automatically.
+    pub async fn with_connection(connection: Connection) -> Result<Arc<Self>> {
+        let upower = UPowerProxy::new(&connection).await?;
+
+        // subscribe before enumerating to avoid missing devices in between
+        let mut added = upower.receive_device_added().await?;
+        let mut removed = upower.receive_device_removed().await?;
+
+        let display_path = upower
+            .get_display_device()
+            .await?
+            .inner()
+            .path()
+            .to_string();
+        let paths = upower.enumerate_devices().await?;
+
+        let (tx, rx) = broadcast::channel(32);
+
+        let client = Arc::new(Self {
+            connection,
+            display_path: display_path.clone(),
+            devices: Arc::default(),
+            tasks: Mutex::default(),
+            tx,
+            _rx: rx,
+        });
+
+        client.watch_device(display_path);
+        for path in paths {
+            client.watch_device(path.to_string());
+        }
+
+        {
+            let client = client.clone();
+
+            spawn(async move {
+                loop {
+                    select! {
+                        Some(signal) = added.next() => {
+                            if let Ok(args) = signal.args() {
+                                client.watch_device(args.device().to_string());
+                            }
+                        }
+                        Some(signal) = removed.next() => {
+                            if let Ok(args) = signal.args() {
+                                client.remove_device(&args.device().to_string());
+                            }
+                        }
+                        else => break,
+                    }
+                }
+            });
+        }
+
+        Ok(client)
+    }
+
+    /// Gets the path of the display device,
+    /// which combines the state of all system batteries.
+    pub fn display_path(&self) -> &str {
+        &self.display_path
+    }
 
-    let display_device = device_proxy.get_display_device().await?;
+    /// Gets the current state of the display device,
+    /// if it has been read yet.
+    pub fn display_device(&self) -> Option<Device> {
+        lock!(self.devices).get(&self.display_path).cloned()
+    }
 
-    let path = display_device.inner().path();
+    /// Gets the current state of all devices,
+    /// excluding the display device, ordered by path.
+    pub fn devices(&self) -> Vec<Device> {
+        let mut devices = lock!(self.devices)
+            .values()
+            .filter(|device| device.path != self.display_path)
+            .cloned()
+            .collect::<Vec<_>>();
 
-    let proxy = PropertiesProxy::builder(&dbus)
-        .destination("org.freedesktop.UPower")
-        .expect("failed to set proxy destination address")
-        .path(path)
-        .expect("failed to set proxy path")
+        devices.sort_by(|a, b| a.path.cmp(&b.path));
+        devices
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.tx.subscribe()
+    }
+
+    /// Starts watching the device at `path` for changes,
+    /// replacing any existing watcher for the same device.
+    fn watch_device(&self, path: String) {
+        let connection = self.connection.clone();
+        let devices = self.devices.clone();
+        let tx = self.tx.clone();
+
+        let task = {
+            let path = path.clone();
+            spawn(async move {
+                if let Err(err) = watch_device(connection, path.clone(), devices, tx).await {
+                    error!("Failed to watch upower device '{path}': {err:?}");
+                }
+            })
+        };
+
+        if let Some(task) = lock!(self.tasks).insert(path, task) {
+            task.abort();
+        }
+    }
+
+    fn remove_device(&self, path: &str) {
+        if let Some(task) = lock!(self.tasks).remove(path) {
+            task.abort();
+        }
+
+        if lock!(self.devices).remove(path).is_some() {
+            send!(self.tx, Event::Remove(path.to_string()));
+        }
+    }
+}
+
+/// Reads the properties of the device at `path`,
+/// then re-reads them each time they change.
+async fn watch_device(
+    connection: Connection,
+    path: String,
+    devices: Arc<Mutex<HashMap<String, Device>>>,
+    tx: broadcast::Sender<Event>,
+) -> Result<()> {
+    let proxy = DeviceProxy::builder(&connection)
+        .destination(DBUS_BUS)?
+        .path(path.clone())?
         .cache_properties(CacheProperties::No)
         .build()
         .await?;
 
-    Ok(Arc::new(proxy))
+    let properties_proxy = PropertiesProxy::builder(&connection)
+        .destination(DBUS_BUS)?
+        .path(path.clone())?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    let interface = InterfaceName::from_static_str(DEVICE_INTERFACE)?;
+    let mut stream = properties_proxy.receive_properties_changed().await?;
+
+    loop {
+        let device = Device::read(path.clone(), &proxy).await;
+
+        let previous = lock!(devices).insert(path.clone(), device.clone());
+        if previous.as_ref() != Some(&device) {
+            send!(tx, Event::Update(device));
+        }
+
+        // wait for the next change to the device interface
+        loop {
+            let Some(signal) = stream.next().await else {
+                return Ok(());
+            };
+
+            if signal.args()?.interface_name == interface {
+                break;
+            }
+        }
+    }
+}
+
+impl BatteryState {
+    /// Gets the state name, as exposed over the ironvar namespace.
+    #[cfg(feature = "ipc")]
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Charging => "charging",
+            Self::Discharging => "discharging",
+            Self::Empty => "empty",
+            Self::FullyCharged => "fully_charged",
+            Self::PendingCharge => "pending_charge",
+            Self::PendingDischarge => "pending_discharge",
+        }
+    }
+}
+
+/// Registers the `upower` ironvar namespace,
+/// which exposes the state of the display device.
+#[cfg(feature = "ipc")]
+pub fn register_namespace(client: &Arc<Client>) {
+    use crate::ironvar::ClientNamespace;
+    use tokio::sync::broadcast::error::RecvError;
+
+    fn set_device(namespace: &ClientNamespace, device: &Device) {
+        namespace.set("percent", device.percentage.round());
+        namespace.set("state", device.state.name());
+        namespace.set("time_to_empty", device.time_to_empty);
+        namespace.set("time_to_full", device.time_to_full);
+    }
+
+    let namespace = ClientNamespace::register("upower");
+    let display_path = client.display_path().to_string();
+
+    let mut rx = client.subscribe();
+
+    if let Some(device) = client.display_device() {
+        set_device(&namespace, &device);
+    }
+
+    spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(Event::Update(device)) if device.path == display_path => {
+                    set_device(&namespace, &device);
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
 }
 
-register_fallible_client!(PropertiesProxy<'static>, upower);
+register_fallible_client!(Client, upower);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::net::UnixStream;
+    use tokio::time::{sleep, timeout};
+    use zbus::object_server::SignalEmitter;
+    use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+    use zbus::{Guid, connection, interface};
+
+    const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+    const DISPLAY_PATH: &str = "/org/freedesktop/UPower/devices/DisplayDevice";
+    const BATTERY_PATH: &str = "/org/freedesktop/UPower/devices/battery_BAT0";
+    const MOUSE_PATH: &str = "/org/freedesktop/UPower/devices/mouse_0";
+
+    struct MockUPower {
+        devices: Vec<&'static str>,
+    }
+
+    #[interface(name = "org.freedesktop.UPower")]
+    impl MockUPower {
+        fn enumerate_devices(&self) -> Vec<OwnedObjectPath> {
+            self.devices
+                .iter()
+                .map(|&path| OwnedObjectPath::try_from(path).expect("valid path"))
+                .collect()
+        }
+
+        fn get_display_device(&self) -> OwnedObjectPath {
+            OwnedObjectPath::try_from(DISPLAY_PATH).expect("valid path")
+        }
+
+        #[zbus(signal)]
+        async fn device_added(
+            emitter: &SignalEmitter<'_>,
+            device: ObjectPath<'_>,
+        ) -> zbus::Result<()>;
+
+        #[zbus(signal)]
+        async fn device_removed(
+            emitter: &SignalEmitter<'_>,
+            device: ObjectPath<'_>,
+        ) -> zbus::Result<()>;
+    }
+
+    /// A minimal device, which deliberately omits most properties.
+    struct MockDevice {
+        percentage: f64,
+        device_type: u32,
+    }
+
+    #[interface(name = "org.freedesktop.UPower.Device")]
+    impl MockDevice {
+        #[zbus(property)]
+        fn percentage(&self) -> f64 {
+            self.percentage
+        }
+
+        #[zbus(property)]
+        fn state(&self) -> u32 {
+            BatteryState::Discharging as u32
+        }
+
+        #[zbus(property, name = "Type")]
+        fn type_(&self) -> u32 {
+            self.device_type
+        }
+    }
+
+    /// Serves a mock UPower service over a private peer-to-peer bus,
+    /// returning the server and client connections.
+    async fn mock_upower() -> (Connection, Connection) {
+        let (server_stream, client_stream) = UnixStream::pair().expect("to create socket pair");
+
+        let server = connection::Builder::unix_stream(server_stream)
+            .server(Guid::generate())
+            .expect("valid guid")
+            .p2p()
+            .serve_at(
+                UPOWER_PATH,
+                MockUPower {
+                    devices: vec![BATTERY_PATH],
+                },
+            )
+            .expect("to serve upower")
+            .serve_at(
+                DISPLAY_PATH,
+                MockDevice {
+                    percentage: 80.0,
+                    device_type: BatteryType::Battery as u32,
+                },
+            )
+            .expect("to serve display device")
+            .serve_at(
+                BATTERY_PATH,
+                MockDevice {
+                    percentage: 80.0,
+                    device_type: BatteryType::Battery as u32,
+                },
+            )
+            .expect("to serve battery")
+            .build();
+
+        let client = connection::Builder::unix_stream(client_stream)
+            .p2p()
+            .build();
+
+        tokio::try_join!(server, client).expect("to connect")
+    }
+
+    async fn wait_for(client: &Client, condition: impl Fn(&Client) -> bool) {
+        timeout(Duration::from_secs(5), async {
+            while !condition(client) {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("condition to be met before timeout");
+    }
+
+    #[tokio::test]
+    async fn test_reads_devices() {
+        let (_server, connection) = mock_upower().await;
+        let client = Client::with_connection(connection)
+            .await
+            .expect("to create client");
+
+        wait_for(&client, |client| {
+            client.display_device().is_some() && !client.devices().is_empty()
+        })
+        .await;
+
+        let display = client.display_device().expect("display device");
+        assert_eq!(display.path, DISPLAY_PATH);
+        assert_eq!(display.percentage, 80.0);
+
+        let devices = client.devices();
+        assert_eq!(devices.len(), 1);
+
+        let battery = &devices[0];
+        assert_eq!(battery.path, BATTERY_PATH);
+        assert_eq!(battery.state, BatteryState::Discharging);
+        assert_eq!(battery.device_type, BatteryType::Battery);
+
+        // missing properties fall back to defaults
+        assert_eq!(battery.model, "");
+        assert_eq!(battery.time_to_empty, 0);
+    }
+
+    #[tokio::test]
+    async fn test_property_changes() {
+        let (server, connection) = mock_upower().await;
+        let client = Client::with_connection(connection)
+            .await
+            .expect("to create client");
+
+        wait_for(&client, |client| {
+            !client.devices().is_empty() && client.display_device().is_some()
+        })
+        .await;
+
+        let mut rx = client.subscribe();
+
+        let device = server
+            .object_server()
+            .interface::<_, MockDevice>(BATTERY_PATH)
+            .await
+            .expect("battery interface");
+
+        device.get_mut().await.percentage = 15.0;
+        device
+            .get()
+            .await
+            .percentage_changed(device.signal_emitter())
+            .await
+            .expect("to emit change");
+
+        let device = timeout(Duration::from_secs(5), async {
+            loop {
+                match rx.recv().await.expect("event") {
+                    Event::Update(device) if device.path == BATTERY_PATH => break device,
+                    _ => {}
+                }
+            }
+        })
+        .await
+        .expect("event before timeout");
+
+        assert_eq!(device.percentage, 15.0);
+    }
+
+    #[tokio::test]
+    async fn test_added_and_removed() {
+        let (server, connection) = mock_upower().await;
+        let client = Client::with_connection(connection)
+            .await
+            .expect("to create client");
+
+        wait_for(&client, |client| client.devices().len() == 1).await;
+
+        server
+            .object_server()
+            .at(
+                MOUSE_PATH,
+                MockDevice {
+                    percentage: 50.0,
+                    device_type: BatteryType::Mouse as u32,
+                },
+            )
+            .await
+            .expect("to serve mouse");
+
+        let upower = server
+            .object_server()
+            .interface::<_, MockUPower>(UPOWER_PATH)
+            .await
+            .expect("upower interface");
+
+        let mouse_path = ObjectPath::try_from(MOUSE_PATH).expect("valid path");
+
+        MockUPower::device_added(upower.signal_emitter(), mouse_path.clone())
+            .await
+            .expect("to emit device added");
+
+        wait_for(&client, |client| client.devices().len() == 2).await;
+        assert!(
+            client
+                .devices()
+                .iter()
+                .any(|device| device.device_type == BatteryType::Mouse)
+        );
+
+        MockUPower::device_removed(upower.signal_emitter(), mouse_path)
+            .await
+            .expect("to emit device removed");
+
+        wait_for(&client, |client| client.devices().len() == 1).await;
+    }
+}
@@ -0,0 +1,163 @@
+use super::{ArcMutVec, Client, ConnectionState, Event};
+use crate::{lock, send};
+use libpulse_binding::callbacks::ListResult;
+use libpulse_binding::context::Context;
+use libpulse_binding::context::introspect::{CardInfo, CardProfileInfo};
+use libpulse_binding::context::subscribe::Operation;
+use libpulse_binding::proplist::properties;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::{debug, error, instrument, trace};
+
+/// A sound card, which groups sinks and sources.
+/// Its profile determines which of these are available,
+/// for example analog stereo output or HDMI output.
+#[derive(Debug, Clone)]
+pub struct Card {
+    pub index: u32,
+    pub name: String,
+    pub description: String,
+    pub profiles: Vec<Profile>,
+    pub active_profile: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub description: String,
+    pub available: bool,
+}
+
+impl From<&CardProfileInfo<'_>> for Profile {
+    fn from(value: &CardProfileInfo) -> Self {
+        Self {
+            name: value
+                .name
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            description: value
+                .description
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            available: value.available,
+        }
+    }
+}
+
+impl From<&CardInfo<'_>> for Card {
+    fn from(value: &CardInfo) -> Self {
+        let name = value
+            .name
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+
+        Self {
+            index: value.index,
+            description: value
+                .proplist
+                .get_str(properties::DEVICE_DESCRIPTION)
+                .unwrap_or_else(|| name.clone()),
+            name,
+            profiles: value.profiles.iter().map(Profile::from).collect(),
+            active_profile: value
+                .active_profile
+                .as_ref()
+                .and_then(|profile| profile.name.as_ref())
+                .map(ToString::to_string),
+        }
+    }
+}
+
+impl Client {
+    #[instrument(level = "trace")]
+    pub fn cards(&self) -> Arc<Mutex<Vec<Card>>> {
+        self.data.cards.clone()
+    }
+
+    #[instrument(level = "trace")]
+    pub fn set_card_profile(&self, index: u32, profile: &str) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            introspector.set_card_profile_by_index(index, profile, None);
+        }
+    }
+}
+
+pub fn on_event(
+    context: &Arc<Mutex<Context>>,
+    cards: &ArcMutVec<Card>,
+    tx: &broadcast::Sender<Event>,
+    op: Operation,
+    i: u32,
+) {
+    let introspect = lock!(context).introspect();
+
+    match op {
+        Operation::New => {
+            debug!("new card");
+            introspect.get_card_info_by_index(i, {
+                let cards = cards.clone();
+                let tx = tx.clone();
+
+                move |info| add(info, &cards, &tx)
+            });
+        }
+        Operation::Changed => {
+            debug!("card changed");
+            introspect.get_card_info_by_index(i, {
+                let cards = cards.clone();
+                let tx = tx.clone();
+
+                move |info| update(info, &cards, &tx)
+            });
+        }
+        Operation::Removed => {
+            debug!("card removed");
+            remove(i, cards, tx);
+        }
+    }
+}
+
+pub fn add(info: ListResult<&CardInfo>, cards: &ArcMutVec<Card>, tx: &broadcast::Sender<Event>) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    trace!("adding {info:?}");
+
+    lock!(cards).push(info.into());
+    send!(tx, Event::AddCard(info.into()));
+}
+
+fn update(info: ListResult<&CardInfo>, cards: &ArcMutVec<Card>, tx: &broadcast::Sender<Event>) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    trace!("updating {info:?}");
+
+    {
+        let mut cards = lock!(cards);
+        let Some(pos) = cards.iter().position(|card| card.index == info.index) else {
+            error!("received update to untracked card");
+            return;
+        };
+
+        cards[pos] = info.into();
+    }
+
+    send!(tx, Event::UpdateCard(info.into()));
+}
+
+fn remove(index: u32, cards: &ArcMutVec<Card>, tx: &broadcast::Sender<Event>) {
+    let mut cards = lock!(cards);
+
+    trace!("removing {index}");
+
+    if let Some(pos) = cards.iter().position(|card| card.index == index) {
+        let info = cards.remove(pos);
+        send!(tx, Event::RemoveCard(info.index));
+    }
+}
@@ -1,11 +1,17 @@
+mod card;
 mod sink;
 mod sink_input;
+mod source;
+mod source_output;
 
 use crate::{APP_ID, arc_mut, lock, register_client, send, spawn_blocking};
 use libpulse_binding::callbacks::ListResult;
-use libpulse_binding::context::introspect::{Introspector, ServerInfo};
+use libpulse_binding::context::introspect::{
+    Introspector, ServerInfo, SinkPortInfo, SourcePortInfo,
+};
 use libpulse_binding::context::subscribe::{Facility, InterestMaskSet, Operation};
 use libpulse_binding::context::{Context, FlagSet, State};
+use libpulse_binding::def::PortAvailable;
 use libpulse_binding::mainloop::standard::{IterateResult, Mainloop};
 use libpulse_binding::proplist::Proplist;
 use libpulse_binding::volume::{ChannelVolumes, Volume};
@@ -14,8 +20,11 @@ use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
 use tracing::{debug, error, info, trace, warn};
 
+pub use card::{Card, Profile};
 pub use sink::Sink;
 pub use sink_input::SinkInput;
+pub use source::Source;
+pub use source_output::SourceOutput;
 
 type ArcMutVec<T> = Arc<Mutex<Vec<T>>>;
 
@@ -28,6 +37,18 @@ pub enum Event {
     AddInput(SinkInput),
     UpdateInput(SinkInput),
     RemoveInput(u32),
+
+    AddSource(Source),
+    UpdateSource(Source),
+    RemoveSource(String),
+
+    AddOutput(SourceOutput),
+    UpdateOutput(SourceOutput),
+    RemoveOutput(u32),
+
+    AddCard(Card),
+    UpdateCard(Card),
+    RemoveCard(u32),
 }
 
 #[derive(Debug)]
@@ -44,8 +65,59 @@ pub struct Client {
 struct Data {
     sinks: ArcMutVec<Sink>,
     sink_inputs: ArcMutVec<SinkInput>,
+    sources: ArcMutVec<Source>,
+    source_outputs: ArcMutVec<SourceOutput>,
+    cards: ArcMutVec<Card>,
 
     default_sink_name: Arc<Mutex<Option<String>>>,
+    default_source_name: Arc<Mutex<Option<String>>>,
+}
+
+/// A connector on a sink or source,
+/// such as speakers or headphones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Port {
+    pub name: String,
+    pub description: String,
+    /// Whether something is plugged into the port.
+    /// This is `true` if the availability is unknown.
+    pub available: bool,
+}
+
+impl From<&SinkPortInfo<'_>> for Port {
+    fn from(value: &SinkPortInfo) -> Self {
+        Self {
+            name: value
+                .name
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            description: value
+                .description
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            available: value.available != PortAvailable::No,
+        }
+    }
+}
+
+impl From<&SourcePortInfo<'_>> for Port {
+    fn from(value: &SourcePortInfo) -> Self {
+        Self {
+            name: value
+                .name
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            description: value
+                .description
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            available: value.available != PortAvailable::No,
+        }
+    }
 }
 
 pub enum ConnectionState {
@@ -144,6 +216,55 @@ impl Client {
     }
 }
 
+/// Registers the `volume` ironvar namespace,
+/// which exposes the state of the active sink,
+/// and whether any application is recording audio.
+#[cfg(feature = "ipc")]
+pub fn register_namespace(client: &Arc<Client>) {
+    use crate::ironvar::ClientNamespace;
+    use tokio::sync::broadcast::error::RecvError;
+
+    let namespace = ClientNamespace::register("volume");
+
+    let client = client.clone();
+    let mut rx = client.subscribe();
+
+    namespace.set("mic_in_use", client.mic_in_use());
+
+    crate::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(Event::AddSink(_) | Event::UpdateSink(_) | Event::RemoveSink(_))
+                | Err(RecvError::Lagged(_)) => {
+                    let sink = lock!(client.sinks()).iter().find(|s| s.active).cloned();
+
+                    if let Some(sink) = sink {
+                        namespace.set("percent", sink.volume.round());
+                        namespace.set("muted", sink.muted);
+                        namespace.set("name", &sink.name);
+                        namespace.set("description", &sink.description);
+                    } else {
+                        for key in ["percent", "muted", "name", "description"] {
+                            namespace.remove(key);
+                        }
+                    }
+                }
+                Ok(
+                    Event::AddOutput(_)
+                    | Event::UpdateOutput(_)
+                    | Event::RemoveOutput(_)
+                    | Event::AddSource(_)
+                    | Event::RemoveSource(_),
+                ) => {
+                    namespace.set("mic_in_use", client.mic_in_use());
+                }
+                Ok(_) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
 /// Creates a new Pulse volume client.
 pub fn create_client() -> Arc<Client> {
     let client = Arc::new(Client::new());
@@ -198,6 +319,43 @@ fn on_state_change(context: &Arc<Mutex<Context>>, data: &Data, tx: &broadcast::S
                 move |info| sink_input::add(info, &inputs, &tx)
             });
 
+            let introspect3 = lock!(context).introspect();
+
+            introspect.get_source_info_list({
+                let sources = data.sources.clone();
+                let default_source = data.default_source_name.clone();
+
+                let tx = tx.clone();
+
+                move |info| match info {
+                    ListResult::Item(_) => source::add(info, &sources, &tx),
+                    ListResult::End => {
+                        introspect3.get_server_info({
+                            let sources = sources.clone();
+                            let default_source = default_source.clone();
+                            let tx = tx.clone();
+
+                            move |info| set_default_source(info, &sources, &default_source, &tx)
+                        });
+                    }
+                    ListResult::Error => error!("Error while receiving sources"),
+                }
+            });
+
+            introspect.get_source_output_info_list({
+                let outputs = data.source_outputs.clone();
+                let tx = tx.clone();
+
+                move |info| source_output::add(info, &outputs, &tx)
+            });
+
+            introspect.get_card_info_list({
+                let cards = data.cards.clone();
+                let tx = tx.clone();
+
+                move |info| card::add(info, &cards, &tx)
+            });
+
             let subscribe_callback = Box::new({
                 let context = context.clone();
                 let data = data.clone();
@@ -208,7 +366,12 @@ fn on_state_change(context: &Arc<Mutex<Context>>, data: &Data, tx: &broadcast::S
 
             lock!(context).set_subscribe_callback(Some(subscribe_callback));
             lock!(context).subscribe(
-                InterestMaskSet::SERVER | InterestMaskSet::SINK_INPUT | InterestMaskSet::SINK,
+                InterestMaskSet::SERVER
+                    | InterestMaskSet::SINK_INPUT
+                    | InterestMaskSet::SINK
+                    | InterestMaskSet::SOURCE_OUTPUT
+                    | InterestMaskSet::SOURCE
+                    | InterestMaskSet::CARD,
                 |_| (),
             );
         }
@@ -233,25 +396,29 @@ fn on_event(
     trace!("server event: {facility:?}, op: {op:?}, i: {i}");
 
     match facility {
-        Facility::Server => on_server_event(context, &data.sinks, &data.default_sink_name, tx),
+        Facility::Server => on_server_event(context, data, tx),
         Facility::Sink => sink::on_event(context, &data.sinks, &data.default_sink_name, tx, op, i),
         Facility::SinkInput => sink_input::on_event(context, &data.sink_inputs, tx, op, i),
+        Facility::Source => {
+            source::on_event(context, &data.sources, &data.default_source_name, tx, op, i)
+        }
+        Facility::SourceOutput => {
+            source_output::on_event(context, &data.source_outputs, tx, op, i);
+        }
+        Facility::Card => card::on_event(context, &data.cards, tx, op, i),
         _ => error!("Received unhandled facility: {facility:?}"),
     }
 }
 
-fn on_server_event(
-    context: &Arc<Mutex<Context>>,
-    sinks: &ArcMutVec<Sink>,
-    default_sink: &Arc<Mutex<Option<String>>>,
-    tx: &broadcast::Sender<Event>,
-) {
+fn on_server_event(context: &Arc<Mutex<Context>>, data: &Data, tx: &broadcast::Sender<Event>) {
     lock!(context).introspect().get_server_info({
-        let sinks = sinks.clone();
-        let default_sink = default_sink.clone();
+        let data = data.clone();
         let tx = tx.clone();
 
-        move |info| set_default_sink(info, &sinks, &default_sink, &tx)
+        move |info| {
+            set_default_sink(info, &data.sinks, &data.default_sink_name, &tx);
+            set_default_source(info, &data.sources, &data.default_source_name, &tx);
+        }
     });
 }
 
@@ -281,6 +448,33 @@ fn set_default_sink(
     *lock!(default_sink) = default_sink_name;
 }
 
+fn set_default_source(
+    info: &ServerInfo,
+    sources: &ArcMutVec<Source>,
+    default_source: &Arc<Mutex<Option<String>>>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let default_source_name = info.default_source_name.as_ref().map(ToString::to_string);
+
+    if default_source_name != *lock!(default_source) {
+        if let Some(ref default_source_name) = default_source_name {
+            if let Some(source) = lock!(sources)
+                .iter_mut()
+                .find(|s| s.name.as_str() == default_source_name.as_str())
+            {
+                source.active = true;
+                debug!("Set source active: {}", source.name);
+                send!(tx, Event::UpdateSource(source.clone()));
+            } else {
+                // the default may be a sink monitor, which is not tracked
+                debug!("Couldn't find source: {}", default_source_name);
+            }
+        }
+    }
+
+    *lock!(default_source) = default_source_name;
+}
+
 /// Converts a Pulse `ChannelVolumes` struct into a single percentage value,
 /// representing the average value across all channels.
 fn volume_to_percent(volume: ChannelVolumes) -> f64 {
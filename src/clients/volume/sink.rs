@@ -1,4 +1,6 @@
-use super::{ArcMutVec, Client, ConnectionState, Event, percent_to_volume, volume_to_percent};
+use super::{
+    ArcMutVec, Client, ConnectionState, Event, Port, percent_to_volume, volume_to_percent,
+};
 use crate::{lock, send};
 use libpulse_binding::callbacks::ListResult;
 use libpulse_binding::context::Context;
@@ -17,6 +19,11 @@ pub struct Sink {
     pub volume: f64,
     pub muted: bool,
     pub active: bool,
+
+    pub ports: Vec<Port>,
+    pub active_port: Option<String>,
+    /// Index of the card the sink belongs to, if any.
+    pub card: Option<u32>,
 }
 
 impl From<&SinkInfo<'_>> for Sink {
@@ -36,6 +43,13 @@ impl From<&SinkInfo<'_>> for Sink {
             muted: value.mute,
             volume: volume_to_percent(value.volume),
             active: value.state == SinkState::Running,
+            ports: value.ports.iter().map(Port::from).collect(),
+            active_port: value
+                .active_port
+                .as_ref()
+                .and_then(|port| port.name.as_ref())
+                .map(ToString::to_string),
+            card: value.card,
         }
     }
 }
@@ -76,6 +90,13 @@ impl Client {
         }
     }
 
+    #[instrument(level = "trace")]
+    pub fn set_sink_port(&self, name: &str, port: &str) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            introspector.set_sink_port_by_name(name, port, None);
+        }
+    }
+
     #[instrument(level = "trace")]
     pub fn set_sink_muted(&self, name: &str, muted: bool) {
         if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
@@ -0,0 +1,227 @@
+use super::{
+    ArcMutVec, Client, ConnectionState, Event, Port, percent_to_volume, volume_to_percent,
+};
+use crate::{lock, send};
+use libpulse_binding::callbacks::ListResult;
+use libpulse_binding::context::Context;
+use libpulse_binding::context::introspect::SourceInfo;
+use libpulse_binding::context::subscribe::Operation;
+use libpulse_binding::def::SourceState;
+use std::sync::{Arc, Mutex, mpsc};
+use tokio::sync::broadcast;
+use tracing::{debug, error, instrument, trace};
+
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub(super) index: u32,
+    pub name: String,
+    pub description: String,
+    pub volume: f64,
+    pub muted: bool,
+    pub active: bool,
+
+    pub ports: Vec<Port>,
+    pub active_port: Option<String>,
+    /// Index of the card the source belongs to, if any.
+    pub card: Option<u32>,
+}
+
+impl From<&SourceInfo<'_>> for Source {
+    fn from(value: &SourceInfo) -> Self {
+        Self {
+            index: value.index,
+            name: value
+                .name
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            description: value
+                .description
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            muted: value.mute,
+            volume: volume_to_percent(value.volume),
+            active: value.state == SourceState::Running,
+            ports: value.ports.iter().map(Port::from).collect(),
+            active_port: value
+                .active_port
+                .as_ref()
+                .and_then(|port| port.name.as_ref())
+                .map(ToString::to_string),
+            card: value.card,
+        }
+    }
+}
+
+/// Checks whether the source is the monitor of a sink,
+/// rather than a real input device.
+fn is_monitor(info: &SourceInfo) -> bool {
+    info.monitor_of_sink.is_some()
+}
+
+impl Client {
+    #[instrument(level = "trace")]
+    pub fn sources(&self) -> Arc<Mutex<Vec<Source>>> {
+        self.data.sources.clone()
+    }
+
+    #[instrument(level = "trace")]
+    pub fn set_default_source(&self, name: &str) {
+        if let ConnectionState::Connected { context, .. } = &*lock!(self.connection) {
+            lock!(context).set_default_source(name, |_| {});
+        }
+    }
+
+    #[instrument(level = "trace")]
+    pub fn set_source_volume(&self, name: &str, volume_percent: f64) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            let (tx, rx) = mpsc::channel();
+
+            introspector.get_source_info_by_name(name, move |info| {
+                let ListResult::Item(info) = info else {
+                    return;
+                };
+                send!(tx, info.volume);
+            });
+
+            let new_volume = percent_to_volume(volume_percent);
+
+            // the source may have been removed before its info was received
+            let Ok(mut volume) = rx.recv() else {
+                return;
+            };
+            for v in volume.get_mut() {
+                v.0 = new_volume;
+            }
+
+            introspector.set_source_volume_by_name(name, &volume, None);
+        }
+    }
+
+    #[instrument(level = "trace")]
+    pub fn set_source_port(&self, name: &str, port: &str) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            introspector.set_source_port_by_name(name, port, None);
+        }
+    }
+
+    #[instrument(level = "trace")]
+    pub fn set_source_muted(&self, name: &str, muted: bool) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            introspector.set_source_mute_by_name(name, muted, None);
+        }
+    }
+}
+
+pub fn on_event(
+    context: &Arc<Mutex<Context>>,
+    sources: &ArcMutVec<Source>,
+    default_source: &Arc<Mutex<Option<String>>>,
+    tx: &broadcast::Sender<Event>,
+    op: Operation,
+    i: u32,
+) {
+    let introspect = lock!(context).introspect();
+
+    match op {
+        Operation::New => {
+            debug!("new source");
+            introspect.get_source_info_by_index(i, {
+                let sources = sources.clone();
+                let tx = tx.clone();
+
+                move |info| add(info, &sources, &tx)
+            });
+        }
+        Operation::Changed => {
+            debug!("source changed");
+            introspect.get_source_info_by_index(i, {
+                let sources = sources.clone();
+                let default_source = default_source.clone();
+                let tx = tx.clone();
+
+                move |info| update(info, &sources, &default_source, &tx)
+            });
+        }
+        Operation::Removed => {
+            debug!("source removed");
+            remove(i, sources, tx);
+        }
+    }
+}
+
+pub fn add(
+    info: ListResult<&SourceInfo>,
+    sources: &ArcMutVec<Source>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    if is_monitor(info) {
+        return;
+    }
+
+    trace!("adding {info:?}");
+
+    lock!(sources).push(info.into());
+    send!(tx, Event::AddSource(info.into()));
+}
+
+fn update(
+    info: ListResult<&SourceInfo>,
+    sources: &ArcMutVec<Source>,
+    default_source: &Arc<Mutex<Option<String>>>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    if is_monitor(info) {
+        return;
+    }
+
+    trace!("updating {info:?}");
+
+    {
+        let mut sources = lock!(sources);
+        let Some(pos) = sources.iter().position(|source| source.index == info.index) else {
+            error!("received update to untracked source");
+            return;
+        };
+
+        sources[pos] = info.into();
+
+        // update in local copy
+        if !sources[pos].active {
+            if let Some(default_source) = &*lock!(default_source) {
+                sources[pos].active = &sources[pos].name == default_source;
+            }
+        }
+    }
+
+    let mut source: Source = info.into();
+
+    // update in broadcast copy
+    if !source.active {
+        if let Some(default_source) = &*lock!(default_source) {
+            source.active = &source.name == default_source;
+        }
+    }
+
+    send!(tx, Event::UpdateSource(source));
+}
+
+fn remove(index: u32, sources: &ArcMutVec<Source>, tx: &broadcast::Sender<Event>) {
+    trace!("removing {index}");
+
+    let mut sources = lock!(sources);
+
+    if let Some(pos) = sources.iter().position(|s| s.index == index) {
+        let info = sources.remove(pos);
+        send!(tx, Event::RemoveSource(info.name));
+    }
+}
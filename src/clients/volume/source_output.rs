@@ -0,0 +1,179 @@
+use super::{ArcMutVec, Client, ConnectionState, Event, percent_to_volume, volume_to_percent};
+use crate::{lock, send};
+use libpulse_binding::callbacks::ListResult;
+use libpulse_binding::context::Context;
+use libpulse_binding::context::introspect::SourceOutputInfo;
+use libpulse_binding::context::subscribe::Operation;
+use std::sync::{Arc, Mutex, mpsc};
+use tokio::sync::broadcast;
+use tracing::{debug, error, instrument, trace};
+
+#[derive(Debug, Clone)]
+pub struct SourceOutput {
+    pub index: u32,
+    pub name: String,
+    pub volume: f64,
+    pub muted: bool,
+    /// Index of the source the stream is recording from.
+    pub source: u32,
+    /// Whether the stream is paused.
+    pub corked: bool,
+
+    pub can_set_volume: bool,
+}
+
+impl From<&SourceOutputInfo<'_>> for SourceOutput {
+    fn from(value: &SourceOutputInfo) -> Self {
+        Self {
+            index: value.index,
+            name: value
+                .name
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            muted: value.mute,
+            volume: volume_to_percent(value.volume),
+            source: value.source,
+            corked: value.corked,
+            can_set_volume: value.has_volume && value.volume_writable,
+        }
+    }
+}
+
+impl Client {
+    #[instrument(level = "trace")]
+    pub fn source_outputs(&self) -> Arc<Mutex<Vec<SourceOutput>>> {
+        self.data.source_outputs.clone()
+    }
+
+    /// Checks whether any stream is recording from a microphone.
+    ///
+    /// Paused streams, and those recording from a sink monitor
+    /// (such as visualisers or desktop capture), are not counted.
+    #[instrument(level = "trace")]
+    pub fn mic_in_use(&self) -> bool {
+        let sources = lock!(self.data.sources);
+
+        lock!(self.data.source_outputs).iter().any(|output| {
+            !output.corked && sources.iter().any(|source| source.index == output.source)
+        })
+    }
+
+    #[instrument(level = "trace")]
+    pub fn set_output_volume(&self, index: u32, volume_percent: f64) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            let (tx, rx) = mpsc::channel();
+
+            introspector.get_source_output_info(index, move |info| {
+                let ListResult::Item(info) = info else {
+                    return;
+                };
+                send!(tx, info.volume);
+            });
+
+            let new_volume = percent_to_volume(volume_percent);
+
+            // the source output may have been removed before its info was received
+            let Ok(mut volume) = rx.recv() else {
+                return;
+            };
+            for v in volume.get_mut() {
+                v.0 = new_volume;
+            }
+
+            introspector.set_source_output_volume(index, &volume, None);
+        }
+    }
+
+    #[instrument(level = "trace")]
+    pub fn set_output_muted(&self, index: u32, muted: bool) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            introspector.set_source_output_mute(index, muted, None);
+        }
+    }
+}
+
+pub fn on_event(
+    context: &Arc<Mutex<Context>>,
+    outputs: &ArcMutVec<SourceOutput>,
+    tx: &broadcast::Sender<Event>,
+    op: Operation,
+    i: u32,
+) {
+    let introspect = lock!(context).introspect();
+
+    match op {
+        Operation::New => {
+            debug!("new source output");
+            introspect.get_source_output_info(i, {
+                let outputs = outputs.clone();
+                let tx = tx.clone();
+
+                move |info| add(info, &outputs, &tx)
+            });
+        }
+        Operation::Changed => {
+            debug!("source output changed");
+            introspect.get_source_output_info(i, {
+                let outputs = outputs.clone();
+                let tx = tx.clone();
+
+                move |info| update(info, &outputs, &tx)
+            });
+        }
+        Operation::Removed => {
+            debug!("source output removed");
+            remove(i, outputs, tx);
+        }
+    }
+}
+
+pub fn add(
+    info: ListResult<&SourceOutputInfo>,
+    outputs: &ArcMutVec<SourceOutput>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    trace!("adding {info:?}");
+
+    lock!(outputs).push(info.into());
+    send!(tx, Event::AddOutput(info.into()));
+}
+
+fn update(
+    info: ListResult<&SourceOutputInfo>,
+    outputs: &ArcMutVec<SourceOutput>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    trace!("updating {info:?}");
+
+    {
+        let mut outputs = lock!(outputs);
+        let Some(pos) = outputs.iter().position(|output| output.index == info.index) else {
+            error!("received update to untracked source output");
+            return;
+        };
+
+        outputs[pos] = info.into();
+    }
+
+    send!(tx, Event::UpdateOutput(info.into()));
+}
+
+fn remove(index: u32, outputs: &ArcMutVec<SourceOutput>, tx: &broadcast::Sender<Event>) {
+    let mut outputs = lock!(outputs);
+
+    trace!("removing {index}");
+
+    if let Some(pos) = outputs.iter().position(|s| s.index == index) {
+        let info = outputs.remove(pos);
+        send!(tx, Event::RemoveOutput(info.index));
+    }
+}
@@ -0,0 +1,562 @@
+use super::locate::KeyPath;
+use super::{Config, ModuleConfig, load_value};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SECTIONS: [&str; 3] = ["start", "center", "end"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A single problem found in a config file.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: PathBuf,
+    /// The (1-indexed) line the problem was found on, if it could be located.
+    pub line: Option<usize>,
+    /// The path to the offending key, for example `monitors.DP-1.end[2]`.
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.severity, self.file.display())?;
+
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+
+        if !self.path.is_empty() {
+            write!(f, ": {}", self.path)?;
+        }
+
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Validates the config file at `path`,
+/// returning every problem found.
+///
+/// If `outputs` is provided, the keys of the `monitors` map
+/// are checked against the list of output names.
+///
+/// Included and referenced files are loaded and checked as part of the config.
+pub fn check(path: &Path, outputs: Option<&[String]>) -> Vec<Diagnostic> {
+    let mut checker = Checker::new(path);
+
+    match load_value(path) {
+        Ok((value, files)) => {
+            checker.sources = files
+                .into_iter()
+                .filter_map(|file| fs::read_to_string(&file).ok().map(|source| (file, source)))
+                .collect();
+
+            checker.check(&value, outputs);
+        }
+        Err(err) => {
+            let message = strip_loader_prefix(&format!("{err:#}"));
+
+            // the innermost failing file is named last in the error chain
+            let file = message
+                .rsplit_once("config file '")
+                .and_then(|(_, rest)| rest.split_once('\''))
+                .map_or_else(|| path.to_path_buf(), |(file, _)| PathBuf::from(file));
+
+            let location = line_from_message(&message).map(|line| Location { file, line });
+            checker.error(location, &KeyPath::default(), message);
+        }
+    }
+
+    checker.diagnostics
+}
+
+#[derive(Debug, Clone)]
+struct Location {
+    file: PathBuf,
+    line: usize,
+}
+
+struct Checker<'a> {
+    file: &'a Path,
+    /// The contents of each loaded file.
+    sources: Vec<(PathBuf, String)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// A module found while walking the config.
+struct ModuleEntry<'a> {
+    path: KeyPath,
+    value: &'a Value,
+}
+
+impl<'a> Checker<'a> {
+    fn new(file: &'a Path) -> Self {
+        Self {
+            file,
+            sources: vec![],
+            diagnostics: vec![],
+        }
+    }
+
+    fn check(&mut self, value: &Value, outputs: Option<&[String]>) {
+        let mut bars = vec![(KeyPath::default(), value)];
+
+        if let Some(monitors) = value.get("monitors").and_then(Value::as_object) {
+            for (name, config) in monitors {
+                let path = KeyPath::default().key("monitors").key(name);
+
+                if let Some(outputs) = outputs {
+                    if !outputs.contains(name) {
+                        self.warning(
+                            &path,
+                            format!(
+                                "no connected output is named '{name}' (found: {})",
+                                outputs.join(", ")
+                            ),
+                        );
+                    }
+                }
+
+                match config {
+                    Value::Array(configs) => bars.extend(
+                        configs
+                            .iter()
+                            .enumerate()
+                            .map(|(i, config)| (path.index(i), config)),
+                    ),
+                    config => bars.push((path, config)),
+                }
+            }
+        }
+
+        let ironvars = value
+            .get("ironvar_defaults")
+            .and_then(Value::as_object)
+            .map(|vars| vars.keys().cloned().collect::<HashSet<_>>())
+            .unwrap_or_default();
+
+        for (path, bar) in bars {
+            let modules = self.collect_modules(&path, bar);
+
+            for module in &modules {
+                self.check_module(module, &ironvars);
+            }
+
+            self.check_duplicate_names(&modules);
+        }
+
+        // modules are validated individually above for better locations,
+        // so remove them before checking everything else.
+        let mut stripped = value.clone();
+        strip_modules(&mut stripped);
+        if let Some(Value::Object(monitors)) = stripped.get_mut("monitors") {
+            for config in monitors.values_mut() {
+                match config {
+                    Value::Array(configs) => configs.iter_mut().for_each(strip_modules),
+                    config => strip_modules(config),
+                }
+            }
+        }
+
+        if let Err(err) = serde_path_to_error::deserialize::<_, Config>(&stripped) {
+            let path = KeyPath::default().join_error_path(err.path());
+            let message = strip_loader_prefix(&err.inner().to_string());
+            self.error_at(&path, message);
+        }
+    }
+
+    /// Gets every module across the bar's sections, in order.
+    fn collect_modules<'v>(&mut self, bar_path: &KeyPath, bar: &'v Value) -> Vec<ModuleEntry<'v>> {
+        let mut modules = vec![];
+
+        for section in SECTIONS {
+            let Some(section_value) = bar.get(section) else {
+                continue;
+            };
+
+            let path = bar_path.key(section);
+
+            let Some(section_modules) = section_value.as_array() else {
+                self.error_at(&path, "expected a list of modules".to_string());
+                continue;
+            };
+
+            modules.extend(
+                section_modules
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| ModuleEntry {
+                        path: path.index(i),
+                        value,
+                    }),
+            );
+        }
+
+        modules
+    }
+
+    fn check_module(&mut self, module: &ModuleEntry, ironvars: &HashSet<String>) {
+        match module.value.get("type") {
+            Some(Value::String(kind)) => {
+                if let Err(err) = ModuleConfig::deserialize(module.value) {
+                    let message = err.to_string();
+
+                    if message.starts_with("unknown variant") {
+                        self.error_at(
+                            &module.path.key("type"),
+                            format!(
+                                "unknown module type '{kind}' ({})",
+                                message.replacen(&format!("unknown variant `{kind}`, "), "", 1)
+                            ),
+                        );
+                    } else {
+                        // the tagged enum loses track of where the error is,
+                        // so deserialize the module's own config again to find it.
+                        let mut fields = module.value.clone();
+                        if let Value::Object(map) = &mut fields {
+                            map.remove("type");
+                        }
+
+                        match ModuleConfig::check_fields(kind, &fields) {
+                            Err(err) => self.error_at(
+                                &module.path.join_error_path(err.path()),
+                                err.inner().to_string(),
+                            ),
+                            Ok(()) => self.error_at(&module.path, message),
+                        }
+                    }
+                }
+            }
+            Some(_) => self.error_at(
+                &module.path.key("type"),
+                "module type must be a string".to_string(),
+            ),
+            None => self.error_at(&module.path, "missing module 'type'".to_string()),
+        }
+
+        let mut strings = vec![];
+        collect_strings(module.value, &KeyPath::default(), &mut strings);
+
+        for (relative, key, value) in strings {
+            let path = module.path.join(&relative);
+
+            match key {
+                "type" | "name" | "class" => continue,
+                "mode" => {
+                    let is_script = relative
+                        .parent()
+                        .and_then(|parent| parent.get(module.value))
+                        .is_some_and(|parent| parent.get("cmd").is_some());
+
+                    if is_script && !matches!(value, "poll" | "watch") {
+                        self.error_at(
+                            &path,
+                            format!("invalid script mode '{value}' (expected 'poll' or 'watch')"),
+                        );
+                    }
+
+                    continue;
+                }
+                _ => {}
+            }
+
+            for mode in invalid_script_modes(value) {
+                self.error_at(
+                    &path,
+                    format!("invalid script mode '{mode}' (expected 'poll', 'p', 'watch' or 'w')"),
+                );
+            }
+
+            // dot-separated names belong to namespaces registered at runtime,
+            // which cannot be checked here.
+            for var in ironvar_references(value) {
+                if !var.contains('.') && !ironvars.contains(&var) {
+                    self.warning(
+                        &path,
+                        format!(
+                            "ironvar '{var}' has no value in 'ironvar_defaults' and will be empty until set"
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_duplicate_names(&mut self, modules: &[ModuleEntry]) {
+        let mut names = HashMap::new();
+
+        for module in modules {
+            let Some(name) = module.value.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+
+            if let Some(first) = names.get(name) {
+                self.error_at(
+                    &module.path.key("name"),
+                    format!("duplicate module name '{name}' (first used at {first})"),
+                );
+            } else {
+                names.insert(name, module.path.clone());
+            }
+        }
+    }
+
+    /// Finds the file and line the value at `path` is defined on.
+    ///
+    /// If the value itself cannot be found, such as for missing keys,
+    /// the closest parent which can be found is used instead.
+    fn locate(&self, path: &KeyPath) -> Option<Location> {
+        let mut path = path.clone();
+
+        while !path.is_empty() {
+            let location = self.sources.iter().find_map(|(file, source)| {
+                path.line(file, source).map(|line| Location {
+                    file: file.clone(),
+                    line,
+                })
+            });
+
+            if location.is_some() {
+                return location;
+            }
+
+            path = path.parent()?;
+        }
+
+        None
+    }
+
+    /// Adds an error for the value at `path`, located in the source files.
+    fn error_at(&mut self, path: &KeyPath, message: String) {
+        let location = self.locate(path);
+        self.error(location, path, message);
+    }
+
+    fn error(&mut self, location: Option<Location>, path: &KeyPath, message: String) {
+        self.push(Severity::Error, location, path, message);
+    }
+
+    fn warning(&mut self, path: &KeyPath, message: String) {
+        let location = self.locate(path);
+        self.push(Severity::Warning, location, path, message);
+    }
+
+    fn push(
+        &mut self,
+        severity: Severity,
+        location: Option<Location>,
+        path: &KeyPath,
+        message: String,
+    ) {
+        let (file, line) = location.map_or_else(
+            || (self.file.to_path_buf(), None),
+            |location| (location.file, Some(location.line)),
+        );
+
+        self.diagnostics.push(Diagnostic {
+            severity,
+            file,
+            line,
+            path: path.to_string(),
+            message,
+        });
+    }
+}
+
+/// Recursively collects every string value below `value`,
+/// alongside its key path and immediate key.
+fn collect_strings<'v>(
+    value: &'v Value,
+    path: &KeyPath,
+    out: &mut Vec<(KeyPath, &'v str, &'v str)>,
+) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = path.key(key);
+                if let Value::String(string) = value {
+                    out.push((path, key.as_str(), string.as_str()));
+                } else {
+                    collect_strings(value, &path, out);
+                }
+            }
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter().enumerate() {
+                let path = path.index(i);
+                if let Value::String(string) = value {
+                    out.push((path, "", string.as_str()));
+                } else {
+                    collect_strings(value, &path, out);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn strip_modules(bar: &mut Value) {
+    if let Value::Object(map) = bar {
+        for section in SECTIONS {
+            if let Some(modules) = map.get_mut(section) {
+                *modules = Value::Array(vec![]);
+            }
+        }
+    }
+}
+
+/// Gets any script modes in the `mode:interval:cmd` shorthand
+/// which are not valid.
+///
+/// This checks both the whole string and any `{{script}}` segments inside it.
+fn invalid_script_modes(input: &str) -> Vec<&str> {
+    let mut scripts = vec![input];
+
+    let mut rest = input;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+
+        scripts.push(rest[start + 2..start + end].trim());
+        rest = &rest[start + end + 2..];
+    }
+
+    scripts
+        .into_iter()
+        .filter_map(|script| {
+            let (mode, rest) = script.split_once(':')?;
+
+            // only treat this as an attempted mode if an interval follows,
+            // as commands can legitimately contain colons.
+            let is_mode_like = !mode.is_empty() && mode.chars().all(|c| c.is_ascii_lowercase());
+            let has_interval = rest
+                .split_once(':')
+                .map_or(rest, |(interval, _)| interval)
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_digit());
+
+            (is_mode_like && has_interval && !matches!(mode, "poll" | "p" | "watch" | "w"))
+                .then_some(mode)
+        })
+        .collect()
+}
+
+/// Gets the names of all `#ironvar` references in a string,
+/// including dot-separated references to namespaced values.
+/// Escaped hashes (`##`) are ignored.
+fn ironvar_references(input: &str) -> Vec<String> {
+    let chars = input.chars().collect::<Vec<_>>();
+    let mut vars = vec![];
+
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '#' {
+            if chars.get(i + 1) == Some(&'#') {
+                i += 2;
+                continue;
+            }
+
+            let name = chars[i + 1..]
+                .iter()
+                .take_while(|&&c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+                .collect::<String>();
+
+            i += name.len() + 1;
+
+            // allow for a full stop after a name, which cannot be a namespace separator
+            let name = name.trim_end_matches('.');
+            if !name.is_empty() {
+                vars.push(name.to_string());
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    vars
+}
+
+/// Attempts to pull a line number out of a parser error message.
+fn line_from_message(message: &str) -> Option<usize> {
+    let number_after = |pattern: &str| {
+        message.find(pattern).and_then(|i| {
+            message[i + pattern.len()..]
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect::<String>()
+                .parse()
+                .ok()
+        })
+    };
+
+    // json, yaml, toml and ron all include `line N`, corn uses `--> N:M`
+    number_after("line ").or_else(|| number_after("--> "))
+}
+
+fn strip_loader_prefix(message: &str) -> String {
+    message
+        .replace("An error occurred when deserializing: ", "")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_script_modes() {
+        assert!(invalid_script_modes("poll:1000:echo hi").is_empty());
+        assert!(invalid_script_modes("w:echo hi").is_empty());
+        assert!(invalid_script_modes("echo 'Uptime: 10'").is_empty());
+        assert_eq!(invalid_script_modes("pol:1000:echo hi"), vec!["pol"]);
+        assert_eq!(
+            invalid_script_modes("cpu {{wach:500:echo hi}}"),
+            vec!["wach"]
+        );
+    }
+
+    #[test]
+    fn test_ironvar_references() {
+        assert_eq!(
+            ironvar_references("#foo and #bar_baz"),
+            vec!["foo", "bar_baz"]
+        );
+        assert!(ironvar_references("escaped ##foo").is_empty());
+        assert_eq!(
+            ironvar_references("#workspace.name."),
+            vec!["workspace.name"]
+        );
+    }
+
+    #[test]
+    fn test_line_from_message() {
+        assert_eq!(
+            line_from_message("expected `,` at line 12 column 4"),
+            Some(12)
+        );
+        assert_eq!(line_from_message(" --> 3:7"), Some(3));
+        assert_eq!(line_from_message("missing field"), None);
+    }
+}
@@ -1,10 +1,12 @@
 use crate::dynamic_value::{DynamicBool, dynamic_string};
 use crate::script::{Script, ScriptInput};
+use crate::tasks::TaskScope;
 use glib::Propagation;
 use gtk::gdk::ScrollDirection;
 use gtk::prelude::*;
 use gtk::{EventBox, Justification, Orientation, Revealer, RevealerTransitionType};
 use serde::Deserialize;
+use std::collections::HashMap;
 use tracing::trace;
 
 /// The following are module-level options which are present on **all** modules.
@@ -15,7 +17,7 @@ use tracing::trace;
 /// For information on the Script type, and embedding scripts in strings,
 /// see [here](script).
 /// For information on styling, please see the [styling guide](styling-guide).
-#[derive(Debug, Default, Deserialize, Clone)]
+#[derive(Debug, Default, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CommonConfig {
     /// Sets the unique widget name,
@@ -36,6 +38,26 @@ pub struct CommonConfig {
     /// **Default**: `null`
     pub class: Option<String>,
 
+    /// Adds CSS classes to the module only while their dynamic boolean evaluates to true.
+    ///
+    /// Each key is a class name, and each value a [dynamic boolean](dynamic-values#dynamic-boolean).
+    /// Classes are also added to the module's popup container.
+    ///
+    /// This allows modules to be styled based on their state using CSS alone.
+    ///
+    /// **Default**: `{}`
+    ///
+    /// # Example
+    ///
+    /// ```corn
+    /// {
+    ///   classes_if.critical = "{= #upower.percent < 10 }"
+    ///   classes_if.muted = "#volume.muted"
+    /// }
+    /// ```
+    #[serde(default)]
+    pub classes_if: HashMap<String, DynamicBool>,
+
     /// Shows this text on hover.
     /// Supports embedding scripts between `{{double braces}}`.
     ///
@@ -159,7 +181,7 @@ pub struct CommonConfig {
     pub disable_popup: bool,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum TransitionType {
@@ -169,7 +191,7 @@ pub enum TransitionType {
     SlideEnd,
 }
 
-#[derive(Debug, Default, Deserialize, Clone, Copy)]
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq)]
 #[serde(rename_all = "snake_case")]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum ModuleOrientation {
@@ -198,7 +220,7 @@ impl From<ModuleOrientation> for Orientation {
     }
 }
 
-#[derive(Debug, Default, Deserialize, Clone, Copy)]
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq)]
 #[serde(rename_all = "snake_case")]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum ModuleJustification {
@@ -238,8 +260,8 @@ impl TransitionType {
 
 impl CommonConfig {
     /// Configures the module's container according to the common config options.
-    pub fn install_events(mut self, container: &EventBox, revealer: &Revealer) {
-        self.install_show_if(container, revealer);
+    pub fn install_events(mut self, container: &EventBox, revealer: &Revealer, tasks: &TaskScope) {
+        self.install_show_if(container, revealer, tasks);
 
         let left_click_script = self.on_click_left.map(Script::new_polling);
         let middle_click_script = self.on_click_middle.map(Script::new_polling);
@@ -302,13 +324,13 @@ impl CommonConfig {
 
         if let Some(tooltip) = self.tooltip {
             let container = container.clone();
-            dynamic_string(&tooltip, move |string| {
+            dynamic_string(&tooltip, tasks, move |string| {
                 container.set_tooltip_text(Some(&string));
             });
         }
     }
 
-    fn install_show_if(&mut self, container: &EventBox, revealer: &Revealer) {
+    fn install_show_if(&mut self, container: &EventBox, revealer: &Revealer, tasks: &TaskScope) {
         self.show_if.take().map_or_else(
             || {
                 container.show_all();
@@ -320,7 +342,7 @@ impl CommonConfig {
                     let revealer = revealer.clone();
                     let container = container.clone();
 
-                    show_if.subscribe(move |success| {
+                    show_if.subscribe(tasks, move |success| {
                         if success {
                             container.show_all();
                         }
@@ -1,4 +1,4 @@
-use super::{BarConfig, BarPosition, MonitorConfig};
+use super::{BarConfig, BarPosition, Config, MonitorConfig};
 use color_eyre::{Help, Report};
 use gtk::Orientation;
 use serde::{Deserialize, Deserializer};
@@ -85,3 +85,40 @@ impl BarPosition {
         }
     }
 }
+
+impl BarConfig {
+    /// Checks whether the bar-level options of two configs are identical,
+    /// ignoring the modules in each section.
+    pub fn settings_eq(&self, other: &Self) -> bool {
+        let settings = |config: &Self| Self {
+            start: None,
+            center: None,
+            end: None,
+            ..config.clone()
+        };
+
+        settings(self) == settings(other)
+    }
+}
+
+impl Config {
+    /// Gets the configs for each bar to show on the named monitor.
+    ///
+    /// If the monitor has an entry in `monitors`, its bars are used.
+    /// Otherwise, the top-level bar is used if it contains any modules.
+    pub fn bar_configs(&self, monitor_name: &str) -> Vec<BarConfig> {
+        let show_default_bar =
+            self.bar.start.is_some() || self.bar.center.is_some() || self.bar.end.is_some();
+
+        match self
+            .monitors
+            .as_ref()
+            .and_then(|monitors| monitors.get(monitor_name))
+        {
+            Some(MonitorConfig::Single(config)) => vec![config.clone()],
+            Some(MonitorConfig::Multiple(configs)) => configs.clone(),
+            None if show_default_bar => vec![self.bar.clone()],
+            None => vec![],
+        }
+    }
+}
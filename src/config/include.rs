@@ -0,0 +1,206 @@
+use color_eyre::eyre::WrapErr;
+use color_eyre::{Report, Result};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use universal_config::ConfigLoader;
+
+/// Top-level key containing a list of files to merge into the config.
+const INCLUDE_KEY: &str = "include";
+
+/// Key of an object which should be replaced by the contents of another file.
+const REF_KEY: &str = "$ref";
+
+/// Loads the config file at `path` as a raw value,
+/// resolving any `include` lists and `$ref` references.
+///
+/// Returns the merged value,
+/// alongside the absolute paths of every file which was loaded.
+/// Symlinks in these paths are not resolved.
+pub fn load_value(path: &Path) -> Result<(Value, Vec<PathBuf>)> {
+    let mut files = vec![];
+    let value = load_file(path, &mut files, &mut vec![])?;
+
+    Ok((value, files))
+}
+
+/// Loads a single file, recursively resolving its includes and references.
+///
+/// Paths are resolved relative to the directory of the file they appear in.
+/// `stack` contains the chain of files currently being loaded,
+/// and is used to detect cycles.
+fn load_file(path: &Path, files: &mut Vec<PathBuf>, stack: &mut Vec<PathBuf>) -> Result<Value> {
+    let file = std::path::absolute(path)
+        .wrap_err_with(|| format!("Failed to find config file '{}'", path.display()))?;
+
+    let path = path
+        .canonicalize()
+        .wrap_err_with(|| format!("Failed to find config file '{}'", path.display()))?;
+
+    if stack.contains(&path) {
+        return Err(Report::msg(format!(
+            "Config file '{}' includes itself",
+            path.display()
+        )));
+    }
+
+    let mut value: Value = ConfigLoader::load(&path)
+        .wrap_err_with(|| format!("Failed to load config file '{}'", path.display()))?;
+
+    if !files.contains(&file) {
+        files.push(file);
+    }
+
+    let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    stack.push(path);
+
+    let includes = match &mut value {
+        Value::Object(map) => map.remove(INCLUDE_KEY),
+        _ => None,
+    };
+
+    resolve_refs(&mut value, &dir, files, stack)?;
+
+    let mut merged = Value::Object(Map::new());
+
+    for include in include_paths(includes)? {
+        let included = load_file(&dir.join(include), files, stack)?;
+        merge(&mut merged, included);
+    }
+
+    // values in the including file take priority
+    merge(&mut merged, value);
+
+    stack.pop();
+    Ok(merged)
+}
+
+/// Gets the list of paths from an `include` value,
+/// which can be either a single string or a list of strings.
+fn include_paths(value: Option<Value>) -> Result<Vec<String>> {
+    match value {
+        None => Ok(vec![]),
+        Some(Value::String(path)) => Ok(vec![path]),
+        Some(Value::Array(paths)) => paths
+            .into_iter()
+            .map(|path| match path {
+                Value::String(path) => Ok(path),
+                _ => Err(Report::msg("Each 'include' entry must be a file path")),
+            })
+            .collect(),
+        Some(_) => Err(Report::msg(
+            "'include' must be a file path or list of file paths",
+        )),
+    }
+}
+
+/// Gets the referenced path if `map` is a `{ "$ref": "path" }` object.
+fn ref_path(map: &Map<String, Value>) -> Option<&str> {
+    if map.len() == 1 {
+        map.get(REF_KEY).and_then(Value::as_str)
+    } else {
+        None
+    }
+}
+
+/// Replaces any `$ref` objects inside `value` with the contents of the referenced file.
+///
+/// Where a reference inside a list points to a file containing a list,
+/// its items are spliced into the parent list.
+fn resolve_refs(
+    value: &mut Value,
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+    stack: &mut Vec<PathBuf>,
+) -> Result<()> {
+    match value {
+        Value::Object(map) => {
+            if let Some(path) = ref_path(map).map(PathBuf::from) {
+                *value = load_file(&dir.join(path), files, stack)?;
+            } else {
+                for value in map.values_mut() {
+                    resolve_refs(value, dir, files, stack)?;
+                }
+            }
+        }
+        Value::Array(values) => {
+            let mut resolved = Vec::with_capacity(values.len());
+
+            for mut value in values.drain(..) {
+                let is_ref = value.as_object().and_then(ref_path).is_some();
+                resolve_refs(&mut value, dir, files, stack)?;
+
+                match value {
+                    Value::Array(values) if is_ref => resolved.extend(values),
+                    value => resolved.push(value),
+                }
+            }
+
+            *values = resolved;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Deep-merges `other` into `base`.
+///
+/// Maps are merged key-by-key.
+/// Any other value in `other`, including lists, replaces the value in `base`.
+fn merge(base: &mut Value, other: Value) {
+    match (base, other) {
+        (Value::Object(base), Value::Object(other)) => {
+            for (key, value) in other {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, other) => *base = other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge_maps() {
+        let mut base = json!({ "position": "top", "monitors": { "DP-1": { "height": 32 } } });
+        let other = json!({ "monitors": { "HDMI-1": { "height": 24 } } });
+
+        merge(&mut base, other);
+
+        assert_eq!(
+            base,
+            json!({
+                "position": "top",
+                "monitors": { "DP-1": { "height": 32 }, "HDMI-1": { "height": 24 } }
+            })
+        );
+    }
+
+    #[test]
+    fn test_merge_replaces_lists() {
+        let mut base = json!({ "end": [{ "type": "clock" }] });
+        let other = json!({ "end": [{ "type": "tray" }] });
+
+        merge(&mut base, other);
+
+        assert_eq!(base, json!({ "end": [{ "type": "tray" }] }));
+    }
+
+    #[test]
+    fn test_include_paths() {
+        assert!(include_paths(None).unwrap().is_empty());
+        assert_eq!(
+            include_paths(Some(json!("base.corn"))).unwrap(),
+            vec!["base.corn"]
+        );
+        assert!(include_paths(Some(json!([1]))).is_err());
+    }
+}
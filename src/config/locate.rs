@@ -0,0 +1,294 @@
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// Message of the error used to stop parsing once the located value is reached.
+///
+/// Each format's deserializer adds its current position to the error,
+/// which gives the location of the value.
+const FOUND: &str = "ironbar: found value";
+
+/// The path to a value inside the config,
+/// for example `monitors.DP-1.end[2]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyPath(Vec<Segment>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+impl KeyPath {
+    /// Gets the path to the `key` of the map at this path.
+    pub fn key(&self, key: &str) -> Self {
+        let mut path = self.clone();
+        path.0.push(Segment::Key(key.to_string()));
+        path
+    }
+
+    /// Gets the path to item `index` of the list at this path.
+    pub fn index(&self, index: usize) -> Self {
+        let mut path = self.clone();
+        path.0.push(Segment::Index(index));
+        path
+    }
+
+    /// Gets the path to `other`, relative to this path.
+    pub fn join(&self, other: &Self) -> Self {
+        let mut path = self.clone();
+        path.0.extend(other.0.iter().cloned());
+        path
+    }
+
+    /// Gets the path to `path`, relative to this path.
+    pub fn join_error_path(&self, path: &serde_path_to_error::Path) -> Self {
+        use serde_path_to_error::Segment as ErrorSegment;
+
+        let mut joined = self.clone();
+        joined
+            .0
+            .extend(path.iter().filter_map(|segment| match segment {
+                ErrorSegment::Seq { index } => Some(Segment::Index(*index)),
+                ErrorSegment::Map { key } => Some(Segment::Key(key.clone())),
+                ErrorSegment::Enum { variant } => Some(Segment::Key(variant.clone())),
+                ErrorSegment::Unknown => None,
+            }));
+        joined
+    }
+
+    /// Gets the path to the value containing this one.
+    pub fn parent(&self) -> Option<Self> {
+        let mut path = self.clone();
+        path.0.pop().map(|_| path)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Gets the value at this path below `value`.
+    pub fn get<'v>(&self, value: &'v serde_json::Value) -> Option<&'v serde_json::Value> {
+        self.0
+            .iter()
+            .try_fold(value, |value, segment| match segment {
+                Segment::Key(key) => value.get(key),
+                Segment::Index(index) => value.get(index),
+            })
+    }
+
+    /// Gets the (1-indexed) line the value at this path starts on
+    /// in `source`, which is the contents of the config file at `file`.
+    ///
+    /// The file is parsed again using the deserializer for its format,
+    /// stopping once the value is reached.
+    /// This is not supported for Corn, which does not track positions.
+    pub fn line(&self, file: &Path, source: &str) -> Option<usize> {
+        let seed = Locate(&self.0);
+        let is_found = |err: &dyn Display| err.to_string().contains(FOUND);
+
+        match file
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+        {
+            #[cfg(feature = "config+json")]
+            "json" => {
+                let mut deserializer = serde_json::Deserializer::from_str(source);
+                match seed.deserialize(&mut deserializer) {
+                    Err(err) if is_found(&err) && err.line() > 0 => Some(err.line()),
+                    _ => None,
+                }
+            }
+            #[cfg(feature = "config+yaml")]
+            "yaml" | "yml" => match seed.deserialize(serde_yaml::Deserializer::from_str(source)) {
+                Err(err) if is_found(&err) => err.location().map(|location| location.line()),
+                _ => None,
+            },
+            #[cfg(feature = "config+toml")]
+            "toml" => match seed.deserialize(toml::Deserializer::new(source)) {
+                Err(err) if is_found(&err) => err
+                    .span()
+                    .map(|span| source[..span.start].matches('\n').count() + 1),
+                _ => None,
+            },
+            #[cfg(feature = "config+ron")]
+            "ron" => match ron::Options::default().from_str_seed(source, seed) {
+                Err(err) if is_found(&err.code) => Some(err.position.line),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl Display for KeyPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                Segment::Key(key) if i == 0 => write!(f, "{key}")?,
+                Segment::Key(key) => write!(f, ".{key}")?,
+                Segment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Walks down the remaining path segments while deserializing,
+/// erroring with [`FOUND`] once the value at the end is reached.
+///
+/// Succeeds without error if the path does not exist.
+struct Locate<'a>(&'a [Segment]);
+
+impl Locate<'_> {
+    fn scalar<E: de::Error>(self) -> Result<(), E> {
+        if self.0.is_empty() {
+            Err(E::custom(FOUND))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Locate<'_> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Locate<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_bool<E: de::Error>(self, _v: bool) -> Result<Self::Value, E> {
+        self.scalar()
+    }
+
+    fn visit_i64<E: de::Error>(self, _v: i64) -> Result<Self::Value, E> {
+        self.scalar()
+    }
+
+    fn visit_u64<E: de::Error>(self, _v: u64) -> Result<Self::Value, E> {
+        self.scalar()
+    }
+
+    fn visit_f64<E: de::Error>(self, _v: f64) -> Result<Self::Value, E> {
+        self.scalar()
+    }
+
+    fn visit_str<E: de::Error>(self, _v: &str) -> Result<Self::Value, E> {
+        self.scalar()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.scalar()
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.scalar()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let Some((segment, rest)) = self.0.split_first() else {
+            return Err(de::Error::custom(FOUND));
+        };
+
+        let Segment::Key(key) = segment else {
+            return Ok(());
+        };
+
+        while let Some(next) = map.next_key::<String>()? {
+            if next == *key {
+                return map.next_value_seed(Locate(rest));
+            }
+
+            map.next_value::<IgnoredAny>()?;
+        }
+
+        Ok(())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let Some((segment, rest)) = self.0.split_first() else {
+            return Err(de::Error::custom(FOUND));
+        };
+
+        let Segment::Index(index) = *segment else {
+            return Ok(());
+        };
+
+        for _ in 0..index {
+            if seq.next_element::<IgnoredAny>()?.is_none() {
+                return Ok(());
+            }
+        }
+
+        seq.next_element_seed(Locate(rest)).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "config+json")]
+    fn test_line_json() {
+        let source = r#"{
+  "position": "top",
+  "end": [
+    { "type": "clock" },
+    {
+      "type": "label",
+      "label": "hi"
+    }
+  ]
+}"#;
+
+        let path = KeyPath::default().key("end").index(1).key("label");
+        assert_eq!(path.to_string(), "end[1].label");
+        assert_eq!(path.line(Path::new("config.json"), source), Some(7));
+
+        let missing = KeyPath::default().key("start");
+        assert_eq!(missing.line(Path::new("config.json"), source), None);
+    }
+
+    #[test]
+    #[cfg(feature = "config+yaml")]
+    fn test_line_yaml() {
+        let source = "position: top\nend:\n  - type: clock\n  - type: label\n    label: hi\n";
+
+        let path = KeyPath::default().key("end").index(1).key("label");
+        assert_eq!(path.line(Path::new("config.yaml"), source), Some(5));
+    }
+}
@@ -1,8 +1,16 @@
+#[cfg(feature = "cli")]
+mod check;
 mod common;
 mod r#impl;
+mod include;
 mod layout;
+#[cfg(feature = "cli")]
+mod locate;
 mod truncate;
+mod watcher;
 
+#[cfg(feature = "bluetooth")]
+use crate::modules::bluetooth::BluetoothModule;
 #[cfg(feature = "cairo")]
 use crate::modules::cairo::CairoModule;
 #[cfg(feature = "clipboard")]
@@ -25,6 +33,8 @@ use crate::modules::music::MusicModule;
 use crate::modules::networkmanager::NetworkManagerModule;
 #[cfg(feature = "notifications")]
 use crate::modules::notifications::NotificationsModule;
+#[cfg(feature = "power_profile")]
+use crate::modules::power_profile::PowerProfileModule;
 #[cfg(feature = "script")]
 use crate::modules::script::ScriptModule;
 #[cfg(feature = "sway")]
@@ -40,7 +50,7 @@ use crate::modules::volume::VolumeModule;
 #[cfg(feature = "workspaces")]
 use crate::modules::workspaces::WorkspacesModule;
 
-use crate::modules::{AnyModuleFactory, ModuleFactory, ModuleInfo};
+use crate::modules::{AnyModuleFactory, ModuleFactory, ModuleInfo, ModuleRef};
 use cfg_if::cfg_if;
 use color_eyre::Result;
 use serde::Deserialize;
@@ -49,14 +59,20 @@ use std::collections::HashMap;
 #[cfg(feature = "schema")]
 use schemars::JsonSchema;
 
+#[cfg(feature = "cli")]
+pub use self::check::{Diagnostic, Severity, check};
 pub use self::common::{CommonConfig, ModuleJustification, ModuleOrientation, TransitionType};
+pub use self::include::load_value;
 pub use self::layout::LayoutConfig;
 pub use self::truncate::{EllipsizeMode, TruncateMode};
+pub use self::watcher::watch_config;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[serde(tag = "type", rename_all = "snake_case")]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub enum ModuleConfig {
+    #[cfg(feature = "bluetooth")]
+    Bluetooth(Box<BluetoothModule>),
     #[cfg(feature = "cairo")]
     Cairo(Box<CairoModule>),
     #[cfg(feature = "clipboard")]
@@ -79,6 +95,8 @@ pub enum ModuleConfig {
     NetworkManager(Box<NetworkManagerModule>),
     #[cfg(feature = "notifications")]
     Notifications(Box<NotificationsModule>),
+    #[cfg(feature = "power_profile")]
+    PowerProfile(Box<PowerProfileModule>),
     #[cfg(feature = "script")]
     Script(Box<ScriptModule>),
     #[cfg(feature = "sys_info")]
@@ -101,7 +119,7 @@ impl ModuleConfig {
         module_factory: &AnyModuleFactory,
         container: &gtk::Box,
         info: &ModuleInfo,
-    ) -> Result<()> {
+    ) -> Result<ModuleRef> {
         macro_rules! create {
             ($module:expr) => {
                 module_factory.create(*$module, container, info)
@@ -109,6 +127,8 @@ impl ModuleConfig {
         }
 
         match self {
+            #[cfg(feature = "bluetooth")]
+            Self::Bluetooth(module) => create!(module),
             #[cfg(feature = "cairo")]
             Self::Cairo(module) => create!(module),
             #[cfg(feature = "clipboard")]
@@ -131,6 +151,8 @@ impl ModuleConfig {
             Self::NetworkManager(module) => create!(module),
             #[cfg(feature = "notifications")]
             Self::Notifications(module) => create!(module),
+            #[cfg(feature = "power_profile")]
+            Self::PowerProfile(module) => create!(module),
             #[cfg(feature = "script")]
             Self::Script(module) => create!(module),
             #[cfg(feature = "sys_info")]
@@ -149,7 +171,70 @@ impl ModuleConfig {
     }
 }
 
-#[derive(Debug, Clone)]
+#[cfg(feature = "cli")]
+impl ModuleConfig {
+    /// Deserializes the config for the module with type `kind`,
+    /// tracking the path to any invalid value.
+    ///
+    /// `fields` should not include the `type` tag.
+    /// Unknown module types are ignored.
+    fn check_fields(
+        kind: &str,
+        fields: &serde_json::Value,
+    ) -> std::result::Result<(), serde_path_to_error::Error<serde_json::Error>> {
+        macro_rules! check {
+            ($module:ty) => {
+                serde_path_to_error::deserialize::<_, $module>(fields).map(|_| ())
+            };
+        }
+
+        match kind {
+            #[cfg(feature = "bluetooth")]
+            "bluetooth" => check!(BluetoothModule),
+            #[cfg(feature = "cairo")]
+            "cairo" => check!(CairoModule),
+            #[cfg(feature = "clipboard")]
+            "clipboard" => check!(ClipboardModule),
+            #[cfg(feature = "clock")]
+            "clock" => check!(ClockModule),
+            #[cfg(feature = "custom")]
+            "custom" => check!(CustomModule),
+            #[cfg(feature = "focused")]
+            "focused" => check!(FocusedModule),
+            #[cfg(feature = "keyboard")]
+            "keyboard" => check!(KeyboardModule),
+            #[cfg(feature = "label")]
+            "label" => check!(LabelModule),
+            #[cfg(feature = "launcher")]
+            "launcher" => check!(LauncherModule),
+            #[cfg(feature = "music")]
+            "music" => check!(MusicModule),
+            #[cfg(feature = "network_manager")]
+            "network_manager" => check!(NetworkManagerModule),
+            #[cfg(feature = "notifications")]
+            "notifications" => check!(NotificationsModule),
+            #[cfg(feature = "power_profile")]
+            "power_profile" => check!(PowerProfileModule),
+            #[cfg(feature = "script")]
+            "script" => check!(ScriptModule),
+            #[cfg(feature = "sys_info")]
+            "sys_info" => check!(SysInfoModule),
+            #[cfg(feature = "sway")]
+            "sway_mode" => check!(SwayModeModule),
+            #[cfg(feature = "tray")]
+            "tray" => check!(TrayModule),
+            #[cfg(feature = "upower")]
+            "upower" => check!(UpowerModule),
+            #[cfg(feature = "volume")]
+            "volume" => check!(VolumeModule),
+            #[cfg(feature = "workspaces")]
+            "workspaces" => check!(WorkspacesModule),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub enum MonitorConfig {
     Single(BarConfig),
@@ -191,7 +276,7 @@ pub struct MarginConfig {
 /// or within an object in the [monitors](#monitors) config,
 /// depending on your [use-case](#2-pick-your-use-case).
 ///
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct BarConfig {
     /// A unique identifier for the bar, used for controlling it over IPC.
@@ -363,7 +448,7 @@ impl Default for BarConfig {
     }
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct Config {
     /// A map of [ironvar](ironvar) keys and values
@@ -388,6 +473,24 @@ pub struct Config {
     /// ```
     pub ironvar_defaults: Option<HashMap<Box<str>, String>>,
 
+    /// A list of [ironvar](ironvar) keys whose values
+    /// should be saved and restored across restarts.
+    ///
+    /// Entries ending in `*` match any key starting with the rest of the entry.
+    /// Values are saved to `$XDG_STATE_HOME/ironbar/vars.json`.
+    ///
+    /// Restored values take priority over `ironvar_defaults`.
+    ///
+    /// **Default**: `[]`
+    ///
+    /// # Example
+    ///
+    /// ```corn
+    /// { persist_ironvars = [ "dnd" "counter_*" ] }
+    /// ```
+    #[serde(default)]
+    pub persist_ironvars: Vec<Box<str>>,
+
     /// The configuration for the bar.
     /// Setting through this will enable a single identical bar on each monitor.
     #[serde(flatten)]
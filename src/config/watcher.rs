@@ -0,0 +1,111 @@
+use crate::{arc_mut, lock, spawn};
+use notify::{Event, EventKind, RecursiveMode, Result, Watcher, recommended_watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tracing::{debug, error};
+
+/// The time to wait after a change before reloading,
+/// to allow multiple writes from a single save to settle.
+const DEBOUNCE_MS: u64 = 250;
+
+/// Installs a file watcher on the config files in `paths`.
+///
+/// The watched directories are only updated when `paths` changes,
+/// for example when a reload changes the set of included files.
+///
+/// The directory containing each path as given is watched,
+/// so that replacing a symlinked file is picked up.
+/// If the path is a symlink, the directory containing its target is watched too,
+/// so that editing the file it points to is also picked up.
+///
+/// `f` is called on the GTK main thread
+/// each time changes to any of the files are detected.
+pub fn watch_config<F>(mut paths: watch::Receiver<Vec<PathBuf>>, f: F)
+where
+    F: Fn() + 'static,
+{
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let watched_files = arc_mut!(HashSet::new());
+
+    let mut watcher = {
+        let watched_files = watched_files.clone();
+
+        recommended_watcher(move |res: Result<Event>| match res {
+            // editors often save by replacing the file, so creation is included here
+            Ok(event) if matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) => {
+                let files = lock!(watched_files);
+                if event.paths.iter().any(|path| files.contains(path)) {
+                    debug!("{event:?}");
+                    tx.send(()).ok();
+                }
+            }
+            Err(e) => error!("Error occurred when watching config: {:?}", e),
+            _ => {}
+        })
+        .expect("Failed to create config file watcher")
+    };
+
+    spawn(async move {
+        let mut watched_dirs = HashSet::new();
+
+        loop {
+            let files = resolve_paths(&paths.borrow_and_update());
+
+            let dirs = files
+                .iter()
+                .filter_map(|path| path.parent().map(Path::to_path_buf))
+                .collect::<HashSet<_>>();
+
+            // stop watching directories which no longer contain any config files,
+            // such as after an include is removed
+            for dir in watched_dirs.difference(&dirs) {
+                match watcher.unwatch(dir) {
+                    Ok(()) => debug!("Removed config file watcher on '{}'", dir.display()),
+                    Err(err) => error!("Failed to stop config file watcher: {err:?}"),
+                }
+            }
+
+            for dir in dirs.difference(&watched_dirs) {
+                match watcher.watch(dir, RecursiveMode::NonRecursive) {
+                    Ok(()) => debug!("Installed config file watcher on '{}'", dir.display()),
+                    Err(err) => error!("Failed to start config file watcher: {err:?}"),
+                }
+            }
+
+            *lock!(watched_files) = files;
+            watched_dirs = dirs;
+
+            if paths.changed().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    glib::spawn_future_local(async move {
+        while rx.recv().await.is_some() {
+            glib::timeout_future(Duration::from_millis(DEBOUNCE_MS)).await;
+            while rx.try_recv().is_ok() {}
+
+            f();
+        }
+    });
+}
+
+/// Gets the set of files to watch for `paths`,
+/// which includes each path as given
+/// alongside the target of any symlinks.
+fn resolve_paths(paths: &[PathBuf]) -> HashSet<PathBuf> {
+    let mut files = HashSet::new();
+
+    for path in paths {
+        if let Ok(target) = path.canonicalize() {
+            files.insert(target);
+        }
+
+        files.insert(path.clone());
+    }
+
+    files
+}
@@ -1,12 +1,15 @@
+use super::Expression;
 use crate::script::Script;
+use crate::tasks::TaskScope;
 #[cfg(feature = "ipc")]
 use crate::{Ironbar, send_async};
-use crate::{glib_recv_mpsc, spawn, try_send};
+use crate::{glib_recv_mpsc, try_send};
 use cfg_if::cfg_if;
 use serde::Deserialize;
 use tokio::sync::mpsc;
+use tracing::error;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[serde(untagged)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum DynamicBool {
@@ -15,16 +18,29 @@ pub enum DynamicBool {
     Script(Script),
     #[cfg(feature = "ipc")]
     Variable(Box<str>),
+    #[serde(skip)]
+    #[cfg_attr(feature = "schema", schemars(skip))]
+    Expression(Expression),
 }
 
 impl DynamicBool {
-    pub fn subscribe<F>(self, mut f: F)
+    /// Runs `f` each time the value changes.
+    /// The task watching the value is stopped along with `tasks`.
+    pub fn subscribe<F>(self, tasks: &TaskScope, mut f: F)
     where
         F: FnMut(bool) + 'static,
     {
         let value = match self {
             Self::Unknown(input) => {
-                if input.starts_with('#') {
+                if let Some(expression) = expression_body(&input) {
+                    match expression.parse() {
+                        Ok(expression) => Self::Expression(expression),
+                        Err(err) => {
+                            error!("{err:?}");
+                            return;
+                        }
+                    }
+                } else if input.starts_with('#') {
                     cfg_if! {
                         if #[cfg(feature = "ipc")] {
                             Self::Variable(input.into())
@@ -44,7 +60,7 @@ impl DynamicBool {
 
         glib_recv_mpsc!(rx, val => f(val));
 
-        spawn(async move {
+        tasks.spawn(async move {
             match value {
                 DynamicBool::Script(script) => {
                     script
@@ -65,12 +81,25 @@ impl DynamicBool {
                         send_async!(tx, has_value);
                     }
                 }
+                DynamicBool::Expression(expression) => {
+                    expression
+                        .watch(|value| {
+                            try_send!(tx, value.is_truthy());
+                        })
+                        .await;
+                }
                 DynamicBool::Unknown(_) => unreachable!(),
             }
         });
     }
 }
 
+/// Gets the contents of an `{= expression }` block,
+/// if the input consists of exactly one.
+fn expression_body(input: &str) -> Option<&str> {
+    input.trim().strip_prefix("{=")?.strip_suffix('}')
+}
+
 /// Check if a string ironvar is 'truthy',
 /// i.e should be evaluated to true.
 ///
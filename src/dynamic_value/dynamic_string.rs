@@ -1,31 +1,36 @@
+use super::Expression;
 #[cfg(feature = "ipc")]
 use crate::Ironbar;
 use crate::script::{OutputStream, Script};
-use crate::{arc_mut, glib_recv_mpsc, lock, spawn, try_send};
+use crate::tasks::TaskScope;
+use crate::{arc_mut, glib_recv_mpsc, lock, try_send};
 use tokio::sync::mpsc;
+use tracing::error;
 
 /// A segment of a dynamic string,
-/// containing either a static string
-/// or a script.
+/// containing either a static string,
+/// a script, a variable or an expression.
 #[derive(Debug)]
 enum DynamicStringSegment {
     Static(String),
     Script(Script),
     #[cfg(feature = "ipc")]
     Variable(Box<str>),
+    Expression(Expression),
 }
 
 /// Creates a new dynamic string, based off the input template.
 /// Runs `f` with the compiled string each time one of the scripts or variables updates.
+/// The tasks watching the scripts and variables are stopped along with `tasks`.
 ///
 /// # Example
 ///
 /// ```rs
-/// dynamic_string(&text, move |string| {
+/// dynamic_string(&text, &context.tasks, move |string| {
 ///     label.set_label_escaped(&string);
 /// });
 /// ```
-pub fn dynamic_string<F>(input: &str, mut f: F)
+pub fn dynamic_string<F>(input: &str, tasks: &TaskScope, mut f: F)
 where
     F: FnMut(String) + 'static,
 {
@@ -46,7 +51,7 @@ where
                 // insert blank value to preserve segment order
                 lock!(label_parts).push(String::new());
 
-                spawn(async move {
+                tasks.spawn(async move {
                     script
                         .run(None, |out, _| {
                             if let OutputStream::Stdout(out) = out {
@@ -69,7 +74,7 @@ where
                 // insert blank value to preserve segment order
                 lock!(label_parts).push(String::new());
 
-                spawn(async move {
+                tasks.spawn(async move {
                     let variable_manager = Ironbar::variable_manager();
                     let mut rx = variable_manager.subscribe(name);
 
@@ -85,6 +90,25 @@ where
                     }
                 });
             }
+            DynamicStringSegment::Expression(expression) => {
+                let tx = tx.clone();
+                let label_parts = label_parts.clone();
+
+                // insert blank value to preserve segment order
+                lock!(label_parts).push(String::new());
+
+                tasks.spawn(async move {
+                    expression
+                        .watch(|value| {
+                            let mut label_parts = lock!(label_parts);
+                            label_parts[i] = value.to_string();
+
+                            let string = label_parts.join("");
+                            try_send!(tx, string);
+                        })
+                        .await;
+                });
+            }
         }
     }
 
@@ -100,7 +124,7 @@ where
 /// Parses the input string into static and dynamic segments
 fn parse_input(input: &str) -> (Vec<DynamicStringSegment>, bool) {
     // short-circuit parser if it's all static
-    if !input.contains("{{") && !input.contains('#') {
+    if !input.contains("{{") && !input.contains("{=") && !input.contains('#') {
         return (vec![DynamicStringSegment::Static(input.to_string())], true);
     }
 
@@ -116,6 +140,7 @@ fn parse_input(input: &str) -> (Vec<DynamicStringSegment>, bool) {
 
         let (token, skip) = match char_pair {
             Some(['{', '{']) => parse_script(&chars),
+            Some(['{', '=']) => parse_expression(&chars),
             Some(['#', '#']) => (DynamicStringSegment::Static("#".to_string()), 2),
             #[cfg(feature = "ipc")]
             Some(['#', _]) => parse_variable(&chars),
@@ -148,6 +173,41 @@ fn parse_script(chars: &[char]) -> (DynamicStringSegment, usize) {
     (DynamicStringSegment::Script(script), len)
 }
 
+fn parse_expression(chars: &[char]) -> (DynamicStringSegment, usize) {
+    const SKIP_BRACKETS: usize = 3; // `{=` and `}`
+
+    // find the closing brace, ignoring any inside quoted strings
+    let mut quote = None;
+    let mut escaped = false;
+
+    let str = chars
+        .iter()
+        .skip(2)
+        .take_while(|&&c| {
+            match quote {
+                _ if escaped => escaped = false,
+                Some(_) if c == '\\' => escaped = true,
+                Some(q) if c == q => quote = None,
+                None if c == '"' || c == '\'' => quote = Some(c),
+                None if c == '}' => return false,
+                _ => {}
+            }
+            true
+        })
+        .collect::<String>();
+
+    let len = (str.chars().count() + SKIP_BRACKETS).min(chars.len());
+
+    match str.parse() {
+        Ok(expression) => (DynamicStringSegment::Expression(expression), len),
+        Err(err) => {
+            error!("{err:?}");
+            let raw = chars[..len].iter().collect();
+            (DynamicStringSegment::Static(raw), len)
+        }
+    }
+}
+
 #[cfg(feature = "ipc")]
 fn parse_variable(chars: &[char]) -> (DynamicStringSegment, usize) {
     const SKIP_HASH: usize = 1;
@@ -155,9 +215,12 @@ fn parse_variable(chars: &[char]) -> (DynamicStringSegment, usize) {
     let str = chars
         .iter()
         .skip(1)
-        .take_while(|&c| c.is_ascii_alphanumeric() || c == &'_' || c == &'-')
+        .take_while(|&c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
         .collect::<String>();
 
+    // allow for a full stop after a variable name, which cannot be a namespace separator
+    let str = str.trim_end_matches('.').to_string();
+
     let len = str.chars().count() + SKIP_HASH;
     let value = str.into();
 
@@ -167,7 +230,7 @@ fn parse_variable(chars: &[char]) -> (DynamicStringSegment, usize) {
 fn parse_static(chars: &[char]) -> (DynamicStringSegment, usize) {
     let mut str = chars
         .windows(2)
-        .take_while(|&win| win != ['{', '{'] && win[0] != '#')
+        .take_while(|&win| win != ['{', '{'] && win != ['{', '='] && win[0] != '#')
         .map(|w| w[0])
         .collect::<String>();
 
@@ -293,6 +356,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_namespaced_variable() {
+        const INPUT: &str = "#volume.percent%, #subject.";
+        let (tokens, _) = parse_input(INPUT);
+
+        assert_eq!(tokens.len(), 4);
+        assert!(
+            matches!(&tokens[0], DynamicStringSegment::Variable(name) if name.to_string() == "volume.percent")
+        );
+        assert!(matches!(&tokens[1], DynamicStringSegment::Static(str) if str == "%, "));
+        assert!(
+            matches!(&tokens[2], DynamicStringSegment::Variable(name) if name.to_string() == "subject")
+        );
+        assert!(matches!(&tokens[3], DynamicStringSegment::Static(str) if str == "."));
+    }
+
     #[test]
     fn test_escape_hash() {
         const INPUT: &str = "number ###num";
@@ -317,6 +396,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_expression() {
+        const INPUT: &str = "battery: {= round(#percent) + '%' } {{echo hi}}";
+        let (tokens, _) = parse_input(INPUT);
+
+        assert_eq!(tokens.len(), 4);
+        assert!(matches!(&tokens[0], DynamicStringSegment::Static(str) if str == "battery: "));
+        assert!(matches!(&tokens[1], DynamicStringSegment::Expression(_)));
+        assert!(matches!(&tokens[2], DynamicStringSegment::Static(str) if str == " "));
+        assert!(
+            matches!(&tokens[3], DynamicStringSegment::Script(script) if script.cmd == "echo hi")
+        );
+    }
+
+    #[test]
+    fn test_expression_brace_in_string() {
+        const INPUT: &str = "{= '}' + #a }";
+        let (tokens, _) = parse_input(INPUT);
+
+        assert_eq!(tokens.len(), 1);
+        assert!(matches!(&tokens[0], DynamicStringSegment::Expression(_)));
+    }
+
     #[test]
     fn test_pango_attribute() {
         const INPUT: &str = "<span color='#color'>hello</span>";
@@ -0,0 +1,759 @@
+#[cfg(feature = "ipc")]
+use crate::{Ironbar, ironvar::Namespace, spawn};
+use color_eyre::{Report, Result};
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use tracing::warn;
+
+/// Operator symbols, ordered so that longer symbols are matched first.
+const SYMBOLS: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "!", "+", "-", "*", "/", "%", "(", ")", ",",
+];
+
+/// The result of evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+impl Value {
+    /// Checks whether the value should be treated as `true`.
+    ///
+    /// Numbers are true if non-zero.
+    /// Strings follow the same rules as ironvars,
+    /// where an empty string, `0` and `false` are false.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Self::Bool(value) => *value,
+            Self::Number(value) => *value != 0.0 && !value.is_nan(),
+            Self::String(value) => !(value.is_empty() || value == "0" || value == "false"),
+        }
+    }
+
+    /// Gets the value as a number, if it is one or is a string containing one.
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Self::Bool(_) => None,
+            Self::Number(value) => Some(*value),
+            Self::String(value) => value.trim().parse().ok().filter(|n: &f64| n.is_finite()),
+        }
+    }
+
+    fn expect_number(&self, op: &str) -> Result<f64> {
+        self.as_number()
+            .ok_or_else(|| Report::msg(format!("Cannot use '{self}' as a number in '{op}'")))
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bool(value) => write!(f, "{value}"),
+            // show whole numbers without a trailing `.0`
+            Self::Number(value) if value.fract() == 0.0 && value.abs() < 1e15 => {
+                write!(f, "{}", *value as i64)
+            }
+            Self::Number(value) => write!(f, "{value}"),
+            Self::String(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    String(String),
+    Variable(String),
+    Ident(String),
+    Symbol(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    And,
+    Or,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinaryOp {
+    const fn symbol(self) -> &'static str {
+        match self {
+            Self::And => "&&",
+            Self::Or => "||",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Literal(Value),
+    Variable(Box<str>),
+    Not(Box<Expr>),
+    Negate(Box<Expr>),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+/// A parsed expression, which can be evaluated against the current ironvar values.
+///
+/// See the dynamic values documentation for the syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    root: Expr,
+    /// Every variable referenced in the expression.
+    variables: Vec<Box<str>>,
+}
+
+impl FromStr for Expression {
+    type Err = Report;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            pos: 0,
+            variables: vec![],
+        };
+
+        let root = parser.parse_or()?;
+
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(Report::msg(format!(
+                "Unexpected {token:?} in expression '{input}'"
+            )));
+        }
+
+        Ok(Self {
+            root,
+            variables: parser.variables,
+        })
+    }
+}
+
+impl Expression {
+    /// Evaluates the expression,
+    /// using `lookup` to get the current value of each variable.
+    pub fn evaluate(&self, lookup: &impl Fn(&str) -> Option<String>) -> Result<Value> {
+        evaluate(&self.root, lookup)
+    }
+
+    /// Evaluates the expression, logging any error and treating it as an empty string.
+    fn evaluate_or_empty(&self, lookup: &impl Fn(&str) -> Option<String>) -> Value {
+        self.evaluate(lookup).unwrap_or_else(|err| {
+            warn!("Failed to evaluate expression: {err}");
+            Value::String(String::new())
+        })
+    }
+
+    /// Evaluates the expression, calling `f` with the result.
+    ///
+    /// The expression is re-evaluated each time any of the variables it references changes.
+    /// Values in namespaces which do not push their changes, such as `sysinfo`,
+    /// are read when the expression is re-evaluated but do not trigger it themselves.
+    pub async fn watch<F>(self, mut f: F)
+    where
+        F: FnMut(Value),
+    {
+        #[cfg(feature = "ipc")]
+        {
+            use tokio::sync::broadcast::error::RecvError;
+            use tokio::sync::mpsc;
+
+            let variable_manager = Ironbar::variable_manager();
+            let lookup = |key: &str| variable_manager.get(key);
+
+            let (tx, mut rx) = mpsc::channel(32);
+
+            for key in &self.variables {
+                let mut var_rx = variable_manager.subscribe(key.clone());
+                let tx = tx.clone();
+
+                spawn(async move {
+                    while !matches!(var_rx.recv().await, Err(RecvError::Closed)) {
+                        if tx.send(()).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+
+            drop(tx);
+
+            f(self.evaluate_or_empty(&lookup));
+
+            while rx.recv().await.is_some() {
+                // several variables may change at once, so only evaluate for the latest
+                while rx.try_recv().is_ok() {}
+                f(self.evaluate_or_empty(&lookup));
+            }
+        }
+
+        #[cfg(not(feature = "ipc"))]
+        f(self.evaluate_or_empty(&|_| None));
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(i, char)) = chars.peek() {
+        let rest = &input[i..];
+
+        if char.is_whitespace() {
+            chars.next();
+        } else if char.is_ascii_digit()
+            || (char == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+
+            let number = rest[..len]
+                .parse()
+                .map_err(|_| Report::msg(format!("Invalid number '{}'", &rest[..len])))?;
+
+            tokens.push(Token::Number(number));
+            chars.nth(rest[..len].chars().count() - 1);
+        } else if char == '"' || char == '\'' {
+            chars.next();
+
+            let mut string = String::new();
+            let mut closed = false;
+
+            while let Some((_, c)) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some((_, escaped)) = chars.next() {
+                            string.push(escaped);
+                        }
+                    }
+                    c if c == char => {
+                        closed = true;
+                        break;
+                    }
+                    c => string.push(c),
+                }
+            }
+
+            if !closed {
+                return Err(Report::msg("Unterminated string in expression"));
+            }
+
+            tokens.push(Token::String(string));
+        } else if char == '#' {
+            // `-` is left out so that `#a-#b` subtracts,
+            // and a trailing full stop cannot be a namespace separator.
+            let name = rest[1..]
+                .chars()
+                .take_while(|&c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.'))
+                .collect::<String>();
+            let name = name.trim_end_matches('.').to_string();
+
+            if name.is_empty() {
+                return Err(Report::msg("Expected variable name after '#'"));
+            }
+
+            chars.nth(name.chars().count());
+            tokens.push(Token::Variable(name));
+        } else if char.is_alphabetic() || char == '_' {
+            let ident = rest
+                .chars()
+                .take_while(|&c| c.is_alphanumeric() || c == '_')
+                .collect::<String>();
+
+            chars.nth(ident.chars().count() - 1);
+            tokens.push(Token::Ident(ident));
+        } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            chars.nth(symbol.len() - 1);
+            tokens.push(Token::Symbol(symbol));
+        } else {
+            return Err(Report::msg(format!(
+                "Unexpected character '{char}' in expression"
+            )));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser over the expression tokens.
+/// Each `parse_` method handles one level of operator precedence, from lowest to highest.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    variables: Vec<Box<str>>,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Consumes the next token if it is the symbol or keyword `word`.
+    fn eat(&mut self, word: &str) -> bool {
+        let matches = match self.tokens.get(self.pos) {
+            Some(Token::Symbol(symbol)) => *symbol == word,
+            Some(Token::Ident(ident)) => ident == word,
+            _ => false,
+        };
+
+        if matches {
+            self.pos += 1;
+        }
+
+        matches
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<()> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(Report::msg(format!("Expected '{symbol}' in expression")))
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_and()?;
+
+        while self.eat("||") || self.eat("or") {
+            expr = Expr::Binary(Box::new(expr), BinaryOp::Or, Box::new(self.parse_and()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_not()?;
+
+        while self.eat("&&") || self.eat("and") {
+            expr = Expr::Binary(Box::new(expr), BinaryOp::And, Box::new(self.parse_not()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr> {
+        if self.eat("!") || self.eat("not") {
+            Ok(Expr::Not(Box::new(self.parse_not()?)))
+        } else {
+            self.parse_comparison()
+        }
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr> {
+        let expr = self.parse_sum()?;
+
+        let op = [
+            BinaryOp::Eq,
+            BinaryOp::Ne,
+            BinaryOp::Le,
+            BinaryOp::Ge,
+            BinaryOp::Lt,
+            BinaryOp::Gt,
+        ]
+        .into_iter()
+        .find(|op| self.eat(op.symbol()));
+
+        match op {
+            Some(op) => Ok(Expr::Binary(
+                Box::new(expr),
+                op,
+                Box::new(self.parse_sum()?),
+            )),
+            None => Ok(expr),
+        }
+    }
+
+    fn parse_sum(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_product()?;
+
+        loop {
+            let op = if self.eat("+") {
+                BinaryOp::Add
+            } else if self.eat("-") {
+                BinaryOp::Sub
+            } else {
+                break;
+            };
+
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.parse_product()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_product(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_unary()?;
+
+        loop {
+            let op = if self.eat("*") {
+                BinaryOp::Mul
+            } else if self.eat("/") {
+                BinaryOp::Div
+            } else if self.eat("%") {
+                BinaryOp::Rem
+            } else {
+                break;
+            };
+
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.parse_unary()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if self.eat("-") {
+            Ok(Expr::Negate(Box::new(self.parse_unary()?)))
+        } else {
+            self.parse_primary()
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Expr::Literal(Value::Number(number))),
+            Some(Token::String(string)) => Ok(Expr::Literal(Value::String(string))),
+            Some(Token::Variable(name)) => {
+                let name: Box<str> = name.into();
+                if !self.variables.contains(&name) {
+                    self.variables.push(name.clone());
+                }
+
+                Ok(Expr::Variable(name))
+            }
+            Some(Token::Ident(ident)) if ident == "true" => Ok(Expr::Literal(Value::Bool(true))),
+            Some(Token::Ident(ident)) if ident == "false" => Ok(Expr::Literal(Value::Bool(false))),
+            Some(Token::Ident(name)) => {
+                self.expect("(")?;
+
+                let mut args = vec![];
+                if !self.eat(")") {
+                    loop {
+                        args.push(self.parse_or()?);
+                        if self.eat(")") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+
+                Ok(Expr::Call(name, args))
+            }
+            Some(Token::Symbol("(")) => {
+                let expr = self.parse_or()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Some(token) => Err(Report::msg(format!("Unexpected {token:?} in expression"))),
+            None => Err(Report::msg("Unexpected end of expression")),
+        }
+    }
+}
+
+fn evaluate(expr: &Expr, lookup: &impl Fn(&str) -> Option<String>) -> Result<Value> {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        // unset variables are treated as empty, the same as in dynamic strings
+        Expr::Variable(name) => Ok(Value::String(lookup(name).unwrap_or_default())),
+        Expr::Not(expr) => Ok(Value::Bool(!evaluate(expr, lookup)?.is_truthy())),
+        Expr::Negate(expr) => Ok(Value::Number(-evaluate(expr, lookup)?.expect_number("-")?)),
+        Expr::Binary(left, BinaryOp::And, right) => Ok(Value::Bool(
+            evaluate(left, lookup)?.is_truthy() && evaluate(right, lookup)?.is_truthy(),
+        )),
+        Expr::Binary(left, BinaryOp::Or, right) => Ok(Value::Bool(
+            evaluate(left, lookup)?.is_truthy() || evaluate(right, lookup)?.is_truthy(),
+        )),
+        Expr::Binary(left, op, right) => {
+            binary(evaluate(left, lookup)?, *op, evaluate(right, lookup)?)
+        }
+        Expr::Call(name, args) => call(name, args, lookup),
+    }
+}
+
+/// Compares two values numerically if both are numbers,
+/// or as strings otherwise.
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left.as_number(), right.as_number()) {
+        (Some(left), Some(right)) => left.partial_cmp(&right),
+        _ => Some(left.to_string().cmp(&right.to_string())),
+    }
+}
+
+fn binary(left: Value, op: BinaryOp, right: Value) -> Result<Value> {
+    let symbol = op.symbol();
+
+    let value = match op {
+        BinaryOp::Eq => Value::Bool(compare(&left, &right) == Some(Ordering::Equal)),
+        BinaryOp::Ne => Value::Bool(compare(&left, &right) != Some(Ordering::Equal)),
+        BinaryOp::Lt => Value::Bool(compare(&left, &right) == Some(Ordering::Less)),
+        BinaryOp::Le => Value::Bool(matches!(
+            compare(&left, &right),
+            Some(Ordering::Less | Ordering::Equal)
+        )),
+        BinaryOp::Gt => Value::Bool(compare(&left, &right) == Some(Ordering::Greater)),
+        BinaryOp::Ge => Value::Bool(matches!(
+            compare(&left, &right),
+            Some(Ordering::Greater | Ordering::Equal)
+        )),
+        // adding anything other than two numbers joins them as strings
+        BinaryOp::Add => match (left.as_number(), right.as_number()) {
+            (Some(left), Some(right)) => Value::Number(left + right),
+            _ => Value::String(format!("{left}{right}")),
+        },
+        BinaryOp::Sub => Value::Number(left.expect_number(symbol)? - right.expect_number(symbol)?),
+        BinaryOp::Mul => Value::Number(left.expect_number(symbol)? * right.expect_number(symbol)?),
+        BinaryOp::Div | BinaryOp::Rem => {
+            let left = left.expect_number(symbol)?;
+            let right = right.expect_number(symbol)?;
+
+            if right == 0.0 {
+                return Err(Report::msg("Division by zero"));
+            }
+
+            if op == BinaryOp::Div {
+                Value::Number(left / right)
+            } else {
+                Value::Number(left % right)
+            }
+        }
+        BinaryOp::And | BinaryOp::Or => unreachable!("handled with short-circuiting"),
+    };
+
+    Ok(value)
+}
+
+fn call(name: &str, args: &[Expr], lookup: &impl Fn(&str) -> Option<String>) -> Result<Value> {
+    let arg_count = |min: usize, max: usize| {
+        if (min..=max).contains(&args.len()) {
+            Ok(())
+        } else if min == max {
+            Err(Report::msg(format!("'{name}' expects {min} arguments")))
+        } else if max == usize::MAX {
+            Err(Report::msg(format!(
+                "'{name}' expects at least {min} arguments"
+            )))
+        } else {
+            Err(Report::msg(format!(
+                "'{name}' expects between {min} and {max} arguments"
+            )))
+        }
+    };
+
+    // `if` only evaluates the branch it returns
+    if name == "if" {
+        arg_count(3, 3)?;
+        let branch = if evaluate(&args[0], lookup)?.is_truthy() {
+            &args[1]
+        } else {
+            &args[2]
+        };
+
+        return evaluate(branch, lookup);
+    }
+
+    let values = args
+        .iter()
+        .map(|arg| evaluate(arg, lookup))
+        .collect::<Result<Vec<_>>>()?;
+
+    let string = |index: usize| values[index].to_string();
+    let number = |index: usize| values[index].expect_number(name);
+
+    let value = match name {
+        "len" => {
+            arg_count(1, 1)?;
+            Value::Number(string(0).chars().count() as f64)
+        }
+        "upper" => {
+            arg_count(1, 1)?;
+            Value::String(string(0).to_uppercase())
+        }
+        "lower" => {
+            arg_count(1, 1)?;
+            Value::String(string(0).to_lowercase())
+        }
+        "trim" => {
+            arg_count(1, 1)?;
+            Value::String(string(0).trim().to_string())
+        }
+        "contains" => {
+            arg_count(2, 2)?;
+            Value::Bool(string(0).contains(&string(1)))
+        }
+        "starts_with" => {
+            arg_count(2, 2)?;
+            Value::Bool(string(0).starts_with(&string(1)))
+        }
+        "ends_with" => {
+            arg_count(2, 2)?;
+            Value::Bool(string(0).ends_with(&string(1)))
+        }
+        "replace" => {
+            arg_count(3, 3)?;
+            Value::String(string(0).replace(&string(1), &string(2)))
+        }
+        "substr" => {
+            arg_count(2, 3)?;
+            let start = number(1)?.max(0.0) as usize;
+            let len = if values.len() == 3 {
+                number(2)?.max(0.0) as usize
+            } else {
+                usize::MAX
+            };
+
+            Value::String(string(0).chars().skip(start).take(len).collect())
+        }
+        "concat" => Value::String(values.iter().map(ToString::to_string).collect()),
+        "round" => {
+            arg_count(1, 2)?;
+            let factor = if values.len() == 2 {
+                10_f64.powf(number(1)?)
+            } else {
+                1.0
+            };
+
+            Value::Number((number(0)? * factor).round() / factor)
+        }
+        "floor" => {
+            arg_count(1, 1)?;
+            Value::Number(number(0)?.floor())
+        }
+        "ceil" => {
+            arg_count(1, 1)?;
+            Value::Number(number(0)?.ceil())
+        }
+        "abs" => {
+            arg_count(1, 1)?;
+            Value::Number(number(0)?.abs())
+        }
+        "min" | "max" => {
+            arg_count(1, usize::MAX)?;
+            let numbers = (0..values.len()).map(number).collect::<Result<Vec<_>>>()?;
+
+            let value = if name == "min" {
+                numbers.into_iter().fold(f64::INFINITY, f64::min)
+            } else {
+                numbers.into_iter().fold(f64::NEG_INFINITY, f64::max)
+            };
+
+            Value::Number(value)
+        }
+        _ => return Err(Report::msg(format!("Unknown function '{name}'"))),
+    };
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn eval(input: &str, vars: &[(&str, &str)]) -> Value {
+        let vars = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>();
+
+        input
+            .parse::<Expression>()
+            .unwrap()
+            .evaluate(&|key| vars.get(key).cloned())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(eval("1 + 2 * 3", &[]), Value::Number(7.0));
+        assert_eq!(eval("(1 + 2) * 3", &[]), Value::Number(9.0));
+        assert_eq!(eval("-#a % 4", &[("a", "10")]), Value::Number(-2.0));
+        assert_eq!(eval("7 / 2", &[]).to_string(), "3.5");
+    }
+
+    #[test]
+    fn test_comparison() {
+        let vars = [("battery", "15"), ("charging", "false")];
+
+        assert!(eval("#battery < 20 and not #charging", &vars).is_truthy());
+        assert!(!eval("#battery >= 20 || #charging", &vars).is_truthy());
+        // numeric, not lexicographic
+        assert!(eval("#battery < 100", &vars).is_truthy());
+        assert!(eval("'abc' == \"abc\"", &[]).is_truthy());
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(eval("'vol: ' + 50", &[]).to_string(), "vol: 50");
+        assert_eq!(
+            eval("upper(#name)", &[("name", "dp-1")]).to_string(),
+            "DP-1"
+        );
+        assert_eq!(eval("substr('hello', 1, 3)", &[]).to_string(), "ell");
+        assert!(
+            eval(
+                "contains(#title, 'Firefox')",
+                &[("title", "Mozilla Firefox")]
+            )
+            .is_truthy()
+        );
+        assert_eq!(
+            eval("if(#x > 1, 'big', 'small')", &[("x", "0")]).to_string(),
+            "small"
+        );
+        assert_eq!(eval("round(2 / 3, 2)", &[]).to_string(), "0.67");
+    }
+
+    #[test]
+    fn test_variables() {
+        let expression = "#a + #b.c > #a".parse::<Expression>().unwrap();
+        assert_eq!(expression.variables, vec![Box::from("a"), Box::from("b.c")]);
+
+        // `-` is an operator rather than part of the name
+        assert_eq!(eval("#a-#b", &[("a", "3"), ("b", "1")]), Value::Number(2.0));
+
+        // unset variables are empty strings
+        assert!(!eval("#missing", &[]).is_truthy());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!("1 +".parse::<Expression>().is_err());
+        assert!("(1".parse::<Expression>().is_err());
+        assert!("'abc".parse::<Expression>().is_err());
+        assert!("1 2".parse::<Expression>().is_err());
+        assert!(
+            "'a' - 1"
+                .parse::<Expression>()
+                .unwrap()
+                .evaluate(&|_| None)
+                .is_err()
+        );
+    }
+}
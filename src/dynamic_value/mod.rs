@@ -2,6 +2,8 @@
 
 mod dynamic_bool;
 mod dynamic_string;
+mod expression;
 
 pub use dynamic_bool::DynamicBool;
 pub use dynamic_string::dynamic_string;
+pub use expression::Expression;
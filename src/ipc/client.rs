@@ -1,14 +1,39 @@
-use super::Ipc;
+use super::{Ipc, read_message};
 use crate::ipc::{Command, Response};
 use color_eyre::Result;
 use color_eyre::{Help, Report};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixStream;
 
 impl Ipc {
     /// Sends a command to the IPC server.
     /// The server response is returned.
     pub async fn send(&self, command: Command, debug: bool) -> Result<Response> {
+        let mut stream = self.send_command(&command, debug).await?;
+
+        read_message(&mut stream).await
+    }
+
+    /// Sends a `subscribe` command to the IPC server.
+    /// `f` is called with each event line received,
+    /// until the server closes the connection.
+    pub async fn subscribe<F>(&self, command: Command, debug: bool, mut f: F) -> Result<()>
+    where
+        F: FnMut(&str),
+    {
+        let stream = self.send_command(&command, debug).await?;
+
+        let mut lines = BufReader::new(stream).lines();
+        while let Some(line) = lines.next_line().await? {
+            f(&line);
+        }
+
+        Ok(())
+    }
+
+    /// Connects to the IPC server and writes the command,
+    /// returning the open stream.
+    async fn send_command(&self, command: &Command, debug: bool) -> Result<UnixStream> {
         let mut stream = match UnixStream::connect(&self.path).await {
             Ok(stream) => Ok(stream),
             Err(err) => Err(Report::new(err)
@@ -16,18 +41,14 @@ impl Ipc {
                 .suggestion("Is Ironbar running?")),
         }?;
 
-        let write_buffer = serde_json::to_vec(&command)?;
+        let write_buffer = serde_json::to_vec(command)?;
 
         if debug {
-            eprintln!("REQUEST JSON: {}", serde_json::to_string(&command)?);
+            eprintln!("REQUEST JSON: {}", serde_json::to_string(command)?);
         }
 
         stream.write_all(&write_buffer).await?;
 
-        let mut read_buffer = vec![0; 1024];
-        let bytes = stream.read(&mut read_buffer).await?;
-
-        let response = serde_json::from_slice(&read_buffer[..bytes])?;
-        Ok(response)
+        Ok(stream)
     }
 }
@@ -1,7 +1,8 @@
 use clap::ArgAction;
+use serde_json::Value;
 use std::path::PathBuf;
 
-use clap::{Args, Subcommand};
+use clap::{Args, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
 
 #[derive(Subcommand, Debug, Serialize, Deserialize)]
@@ -29,6 +30,33 @@ pub enum Command {
 
     /// Interact with a specific bar.
     Bar(BarCommand),
+
+    /// Send an action to a specific module.
+    /// The action is sent to every module with the given name, across all bars.
+    Msg(ModuleCommand),
+
+    /// Stream events as newline-delimited JSON.
+    /// The connection is kept open until the client disconnects.
+    Subscribe {
+        /// The types of event to stream.
+        /// If not provided, all events are streamed.
+        #[serde(default)]
+        events: Vec<EventType>,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum EventType {
+    /// Ironvar value changes, including namespaced variables.
+    Ironvar,
+    /// Bars being shown or hidden.
+    BarVisibility,
+    /// Popups being opened or closed.
+    Popup,
+    /// Modules updating, without their state.
+    Module,
 }
 
 #[derive(Subcommand, Debug, Serialize, Deserialize)]
@@ -53,6 +81,34 @@ pub enum IronvarCommand {
 
     /// Gets the current value of all `ironvar`s.
     List { namespace: Option<Box<str>> },
+
+    /// Mark an `ironvar` as persistent, so its value is restored after a restart.
+    Persist {
+        /// Variable key.
+        key: Box<str>,
+    },
+
+    /// Stop persisting an `ironvar` previously marked with `persist`.
+    /// Keys persisted by the config cannot be unmarked.
+    Unpersist {
+        /// Variable key.
+        key: Box<str>,
+    },
+}
+
+#[derive(Args, Debug, Serialize, Deserialize)]
+pub struct ModuleCommand {
+    /// The configured name of the module.
+    /// For modules without a name, this is the module type.
+    pub name: String,
+
+    /// The action to run.
+    /// The available actions depend on the module type.
+    pub action: String,
+
+    /// Arguments to pass to the action.
+    #[serde(default)]
+    pub args: Vec<String>,
 }
 
 #[derive(Args, Debug, Serialize, Deserialize)]
@@ -129,4 +185,53 @@ pub enum BarCommandType {
         )]
         exclusive: bool,
     },
+
+    // == Modules == \\
+    /// Add a module to the bar.
+    /// The change only lasts until the config is next reloaded.
+    AddModule {
+        /// The section to add the module to.
+        section: BarSection,
+        /// The module config, as a JSON object.
+        #[clap(value_parser = parse_json)]
+        config: Value,
+        /// The position within the section.
+        /// If not provided, the module is added to the end.
+        #[clap(long)]
+        index: Option<usize>,
+    },
+    /// Remove all modules with the given name from the bar.
+    /// The change only lasts until the config is next reloaded.
+    RemoveModule {
+        /// The configured name of the module.
+        /// For modules without a name, this is the module type.
+        module_name: String,
+    },
+    /// Move a module to a different position, optionally in another section.
+    /// If multiple modules share the name, the first is moved.
+    /// The change only lasts until the config is next reloaded.
+    MoveModule {
+        /// The configured name of the module.
+        /// For modules without a name, this is the module type.
+        module_name: String,
+        /// The section to move the module to.
+        section: BarSection,
+        /// The position within the section.
+        /// If not provided, the module is moved to the end.
+        #[clap(long)]
+        index: Option<usize>,
+    },
+}
+
+/// One of the three sections of a bar which modules are placed in.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BarSection {
+    Start,
+    Center,
+    End,
+}
+
+fn parse_json(value: &str) -> serde_json::Result<Value> {
+    serde_json::from_str(value)
 }
@@ -0,0 +1,66 @@
+use super::EventType;
+use serde::Serialize;
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+/// An event streamed to clients using the `subscribe` command.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// An ironvar value changed.
+    /// Namespaced variables use their full dot-separated key.
+    Ironvar { key: String, value: Option<String> },
+    /// A bar was shown or hidden.
+    BarVisibility { bar: String, visible: bool },
+    /// A popup was opened or closed.
+    Popup {
+        /// The name of the output the popup is on.
+        monitor: String,
+        /// The name of the module the popup belongs to.
+        module: Option<String>,
+        visible: bool,
+    },
+    /// A module received an update from its controller.
+    /// The update itself is not included.
+    Module {
+        /// The configured module name, or its type if not set.
+        name: String,
+        /// The module type.
+        module_type: String,
+    },
+}
+
+impl Event {
+    /// Gets the event type, as used in the `subscribe` command filter.
+    pub const fn event_type(&self) -> EventType {
+        match self {
+            Self::Ironvar { .. } => EventType::Ironvar,
+            Self::BarVisibility { .. } => EventType::BarVisibility,
+            Self::Popup { .. } => EventType::Popup,
+            Self::Module { .. } => EventType::Module,
+        }
+    }
+}
+
+fn channel() -> &'static broadcast::Sender<Event> {
+    static CHANNEL: OnceLock<broadcast::Sender<Event>> = OnceLock::new();
+    CHANNEL.get_or_init(|| broadcast::channel(64).0)
+}
+
+/// Sends an event to all subscribed IPC clients.
+/// This is a no-op if there are no subscribers.
+pub fn emit(event: Event) {
+    // an error only means nobody is listening
+    channel().send(event).ok();
+}
+
+/// Checks whether any IPC clients are subscribed,
+/// to avoid building events which would go unused.
+pub fn has_subscribers() -> bool {
+    channel().receiver_count() > 0
+}
+
+/// Subscribes to all IPC events.
+pub fn subscribe() -> broadcast::Receiver<Event> {
+    channel().subscribe()
+}
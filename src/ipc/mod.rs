@@ -1,9 +1,13 @@
 mod client;
 pub mod commands;
+pub mod events;
 pub mod responses;
 mod server;
 
+use color_eyre::Result;
+use serde::de::DeserializeOwned;
 use std::path::{Path, PathBuf};
+use tokio::io::{AsyncRead, AsyncReadExt};
 use tracing::warn;
 
 pub use commands::*;
@@ -37,3 +41,27 @@ impl Ipc {
         self.path.as_path()
     }
 }
+
+/// Reads a single JSON message from `stream`.
+///
+/// Messages are not framed, so data is read in chunks
+/// until it forms a complete JSON value or the stream ends.
+async fn read_message<T, R>(stream: &mut R) -> Result<T>
+where
+    T: DeserializeOwned,
+    R: AsyncRead + Unpin,
+{
+    let mut buffer = vec![];
+    let mut chunk = [0; 1024];
+
+    loop {
+        let bytes = stream.read(&mut chunk).await?;
+        buffer.extend_from_slice(&chunk[..bytes]);
+
+        match serde_json::from_slice(&buffer) {
+            Ok(message) => return Ok(message),
+            Err(err) if err.is_eof() && bytes > 0 => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
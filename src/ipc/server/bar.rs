@@ -1,8 +1,12 @@
 use super::Response;
 use crate::Ironbar;
 use crate::bar::Bar;
-use crate::ipc::{BarCommand, BarCommandType};
-use crate::modules::PopupButton;
+use crate::config::ModuleConfig;
+use crate::ipc::{BarCommand, BarCommandType, BarSection};
+use crate::modules::{ModuleLocation, PopupButton};
+use color_eyre::Result;
+use serde::Deserialize;
+use serde_json::Value;
 use std::rc::Rc;
 
 pub fn handle_command(command: &BarCommand, ironbar: &Rc<Ironbar>) -> Response {
@@ -47,8 +51,21 @@ pub fn handle_command(command: &BarCommand, ironbar: &Rc<Ironbar>) -> Response {
                 bar.set_exclusive(*exclusive);
                 Response::Ok
             }
+            AddModule {
+                section,
+                config,
+                index,
+            } => add_module(&bar, config, *section, *index),
+            RemoveModule { module_name } => to_response(bar.remove_module(module_name)),
+            MoveModule {
+                module_name,
+                section,
+                index,
+            } => to_response(bar.move_module(module_name, location(*section), *index)),
         })
         .reduce(|acc, rsp| match (acc, rsp) {
+            // Errors from any bar take priority.
+            (err @ Response::Err { .. }, _) | (_, err @ Response::Err { .. }) => err,
             // If all responses are Ok, return one Ok. We assume we'll never mix Ok and OkValue.
             (Response::Ok, _) => Response::Ok,
             // Two or more OkValues create a multi:
@@ -94,6 +111,28 @@ fn show_popup(bar: &Bar, widget_name: &str) -> Response {
     }
 }
 
+fn add_module(bar: &Bar, config: &Value, section: BarSection, index: Option<usize>) -> Response {
+    match ModuleConfig::deserialize(config) {
+        Ok(config) => to_response(bar.add_module(config, location(section), index)),
+        Err(err) => Response::error(&format!("Invalid module config: {err}")),
+    }
+}
+
+fn to_response(result: Result<()>) -> Response {
+    match result {
+        Ok(()) => Response::Ok,
+        Err(err) => Response::error(&err.to_string()),
+    }
+}
+
+const fn location(section: BarSection) -> ModuleLocation {
+    match section {
+        BarSection::Start => ModuleLocation::Left,
+        BarSection::Center => ModuleLocation::Center,
+        BarSection::End => ModuleLocation::Right,
+    }
+}
+
 fn hide_popup(bar: &Bar) -> Response {
     let popup = bar.popup();
     popup.hide();
@@ -12,6 +12,8 @@ pub fn handle_command(command: IronvarCommand) -> Response {
                 Err(err) => Response::error(&format!("{err}")),
             }
         }
+        IronvarCommand::Persist { key } => set_persistent(&key, true),
+        IronvarCommand::Unpersist { key } => set_persistent(&key, false),
         IronvarCommand::Get { mut key } => {
             let variable_manager = Ironbar::variable_manager();
             let mut ns: Arc<dyn Namespace + Sync + Send> = variable_manager;
@@ -72,3 +74,11 @@ pub fn handle_command(command: IronvarCommand) -> Response {
         }
     }
 }
+
+fn set_persistent(key: &str, persistent: bool) -> Response {
+    let variable_manager = Ironbar::variable_manager();
+    match variable_manager.set_persistent(key, persistent) {
+        Ok(()) => Response::Ok,
+        Err(err) => Response::error(&format!("{err}")),
+    }
+}
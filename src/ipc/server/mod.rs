@@ -1,5 +1,7 @@
 mod bar;
 mod ironvar;
+mod module;
+mod subscribe;
 
 use std::fs;
 use std::path::Path;
@@ -7,8 +9,7 @@ use std::rc::Rc;
 
 use color_eyre::{Report, Result};
 use gtk::Application;
-use gtk::prelude::*;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::AsyncWriteExt;
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::mpsc::{self, Receiver, Sender};
 use tracing::{debug, error, info, warn};
@@ -17,7 +18,7 @@ use crate::ipc::{Command, Response};
 use crate::style::load_css;
 use crate::{Ironbar, glib_recv_mpsc, send_async, spawn, try_send};
 
-use super::Ipc;
+use super::{Ipc, read_message};
 
 impl Ipc {
     /// Starts the IPC server on its socket.
@@ -75,22 +76,30 @@ impl Ipc {
     /// Takes an incoming connections,
     /// reads the command message, and sends the response.
     ///
-    /// The connection is closed once the response has been written.
+    /// The connection is closed once the response has been written,
+    /// except for subscriptions which stream events until the client disconnects.
     async fn handle_connection(
         mut stream: UnixStream,
         cmd_tx: &Sender<Command>,
         res_rx: &mut Receiver<Response>,
     ) -> Result<()> {
-        let (mut stream_read, mut stream_write) = stream.split();
-
-        let mut read_buffer = vec![0; 1024];
-        let bytes = stream_read.read(&mut read_buffer).await?;
-
         // FIXME: Error on invalid command
-        let command = serde_json::from_slice::<Command>(&read_buffer[..bytes])?;
+        let command = read_message::<Command, _>(&mut stream).await?;
 
         debug!("Received command: {command:?}");
 
+        // subscriptions hold the connection open,
+        // so must not block other connections.
+        if let Command::Subscribe { events } = command {
+            spawn(async move {
+                if let Err(err) = subscribe::stream_events(stream, events).await {
+                    debug!("Subscription closed: {err:?}");
+                }
+            });
+
+            return Ok(());
+        }
+
         send_async!(cmd_tx, command);
         let res = res_rx
             .recv()
@@ -98,8 +107,8 @@ impl Ipc {
             .unwrap_or(Response::Err { message: None });
         let res = serde_json::to_vec(&res)?;
 
-        stream_write.write_all(&res).await?;
-        stream_write.shutdown().await?;
+        stream.write_all(&res).await?;
+        stream.shutdown().await?;
 
         Ok(())
     }
@@ -118,29 +127,10 @@ impl Ipc {
                 gtk::Window::set_interactive_debugging(true);
                 Response::Ok
             }
-            Command::Reload => {
-                info!("Closing existing bars");
-                ironbar.bars.borrow_mut().clear();
-
-                let windows = application.windows();
-                for window in windows {
-                    window.close();
-                }
-
-                let wl = ironbar.clients.borrow_mut().wayland();
-                let outputs = wl.output_info_all();
-
-                ironbar.reload_config();
-
-                for output in outputs {
-                    match crate::load_output_bars(ironbar, application, &output) {
-                        Ok(mut bars) => ironbar.bars.borrow_mut().append(&mut bars),
-                        Err(err) => error!("{err:?}"),
-                    }
-                }
-
-                Response::Ok
-            }
+            Command::Reload => match ironbar.reload(application) {
+                Ok(()) => Response::Ok,
+                Err(err) => Response::error(&format!("{err}")),
+            },
             Command::LoadCss { path } => {
                 if path.exists() {
                     load_css(path, application.clone());
@@ -151,6 +141,8 @@ impl Ipc {
             }
             Command::Var(cmd) => ironvar::handle_command(cmd),
             Command::Bar(cmd) => bar::handle_command(&cmd, ironbar),
+            Command::Msg(cmd) => module::handle_command(&cmd, ironbar),
+            Command::Subscribe { .. } => unreachable!("handled by connection"),
         }
     }
 
@@ -0,0 +1,36 @@
+use super::Response;
+use crate::Ironbar;
+use crate::ipc::ModuleCommand;
+use std::rc::Rc;
+
+pub fn handle_command(command: &ModuleCommand, ironbar: &Rc<Ironbar>) -> Response {
+    let modules = ironbar.modules_by_name(&command.name);
+
+    if modules.is_empty() {
+        return Response::error("Invalid module name");
+    }
+
+    // modules sharing a name may be of different types,
+    // so each instance is reported separately.
+    let errors = modules
+        .iter()
+        .filter_map(|(bar_name, module)| {
+            module
+                .actions
+                .handle(&command.action, &command.args)
+                .err()
+                .map(|err| format!("{bar_name} (module {}): {err}", module.id))
+        })
+        .collect::<Vec<_>>();
+
+    if errors.is_empty() {
+        Response::Ok
+    } else {
+        Response::error(&format!(
+            "Action failed for {} of {} modules:\n{}",
+            errors.len(),
+            modules.len(),
+            errors.join("\n")
+        ))
+    }
+}
@@ -0,0 +1,42 @@
+use crate::ipc::EventType;
+use crate::ipc::events;
+use color_eyre::Result;
+use tokio::io::AsyncWriteExt;
+use tokio::net::UnixStream;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
+
+/// Writes events to the stream as newline-delimited JSON,
+/// until the client disconnects.
+///
+/// If `types` is empty, all events are written.
+pub async fn stream_events(mut stream: UnixStream, types: Vec<EventType>) -> Result<()> {
+    let wants = |event_type| types.is_empty() || types.contains(&event_type);
+
+    let mut rx = events::subscribe();
+
+    debug!("Started event subscription");
+
+    loop {
+        let event = match rx.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(count)) => {
+                warn!("Event subscriber fell behind, skipped {count} events");
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+
+        if !wants(event.event_type()) {
+            continue;
+        }
+
+        let mut line = serde_json::to_vec(&event)?;
+        line.push(b'\n');
+
+        // this errors once the client disconnects
+        stream.write_all(&line).await?;
+    }
+
+    Ok(())
+}
@@ -1,10 +1,18 @@
 #![doc = include_str!("../docs/Ironvars.md")]
 
-use crate::{arc_rw, read_lock, send, write_lock};
+mod client;
+mod persist;
+
+use crate::ipc::events::{self, Event};
+use crate::{Ironbar, arc_rw, lock, read_lock, send, write_lock};
 use color_eyre::{Report, Result};
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
-use tokio::sync::broadcast;
+use persist::Persistence;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::{broadcast, mpsc};
+use tracing::warn;
+
+pub use client::ClientNamespace;
 
 type NamespaceTrait = Arc<dyn Namespace + Sync + Send>;
 
@@ -31,6 +39,14 @@ pub trait WritableNamespace: Namespace {
 pub struct VariableManager {
     variables: Arc<RwLock<HashMap<Box<str>, IronVar>>>,
     namespaces: Arc<RwLock<HashMap<Box<str>, NamespaceTrait>>>,
+    /// Subscriptions to values inside namespaces, keyed by their full dot-separated path.
+    namespaced: RwLock<HashMap<Box<str>, IronVar>>,
+    persistence: Arc<Persistence>,
+
+    /// Namespaces which have been referenced before being registered.
+    requested: RwLock<HashSet<Box<str>>>,
+    request_tx: mpsc::UnboundedSender<Box<str>>,
+    request_rx: Mutex<Option<mpsc::UnboundedReceiver<Box<str>>>>,
 }
 
 impl Default for VariableManager {
@@ -41,21 +57,78 @@ impl Default for VariableManager {
 
 impl VariableManager {
     pub fn new() -> Self {
+        let (request_tx, request_rx) = mpsc::unbounded_channel();
+
         Self {
             variables: arc_rw!(HashMap::new()),
             namespaces: arc_rw!(HashMap::new()),
+            namespaced: RwLock::new(HashMap::new()),
+            persistence: Arc::new(Persistence::new()),
+            requested: RwLock::new(HashSet::new()),
+            request_tx,
+            request_rx: Mutex::new(Some(request_rx)),
         }
     }
 
     /// Subscribes to an `ironvar`, creating it if it does not exist.
     /// Any time the var is set, its value is sent on the channel.
+    ///
+    /// Values inside namespaces can be subscribed to using their full dot-separated key.
     pub fn subscribe(&self, key: Box<str>) -> broadcast::Receiver<Option<String>> {
+        if key.contains('.') {
+            self.request_namespace(&key);
+            let value = self.get(&key);
+
+            return write_lock!(self.namespaced)
+                .entry(key)
+                .or_insert_with(|| IronVar::new(value))
+                .subscribe();
+        }
+
         write_lock!(self.variables)
             .entry(key)
             .or_insert_with(|| IronVar::new(None))
             .subscribe()
     }
 
+    /// Sends a changed namespace value to any subscribers.
+    /// This must be called by namespaces each time one of their values changes.
+    pub fn notify(&self, key: &str, value: Option<String>) {
+        events::emit(Event::Ironvar {
+            key: key.to_string(),
+            value: value.clone(),
+        });
+
+        if let Some(var) = write_lock!(self.namespaced).get_mut(key) {
+            var.set(value);
+        }
+    }
+
+    /// Takes the receiver for namespaces which have been referenced before being registered.
+    /// The name of each namespace is sent once,
+    /// allowing the client behind it to be started on demand.
+    ///
+    /// Returns `None` if the receiver has already been taken.
+    pub fn take_namespace_requests(&self) -> Option<mpsc::UnboundedReceiver<Box<str>>> {
+        lock!(self.request_rx).take()
+    }
+
+    /// Requests the namespace of the dot-separated `key` be started,
+    /// if it is not already registered.
+    fn request_namespace(&self, key: &str) {
+        let Some((namespace, _)) = key.split_once('.') else {
+            return;
+        };
+
+        if read_lock!(self.namespaces).contains_key(namespace) {
+            return;
+        }
+
+        if write_lock!(self.requested).insert(namespace.into()) {
+            self.request_tx.send(namespace.into()).ok();
+        }
+    }
+
     fn key_is_valid(key: &str) -> bool {
         !key.is_empty()
             && key
@@ -63,6 +136,43 @@ impl VariableManager {
                 .all(|char| char.is_alphanumeric() || char == '_' || char == '-')
     }
 
+    /// Sets the keys and key prefixes from the config whose values should be persisted.
+    pub fn set_persisted_keys(&self, keys: Vec<Box<str>>) {
+        self.persistence.set_patterns(keys);
+    }
+
+    /// Sets the values of any persistent variables saved by a previous run.
+    /// Values are only restored once, on the first call.
+    pub fn restore_persisted(&self) {
+        for (key, value) in self.persistence.load() {
+            if self.persistence.is_persistent(&key) && self.set(&key, value).is_err() {
+                warn!("Ignoring invalid persisted ironvar: '{key}'");
+            }
+        }
+    }
+
+    /// Checks whether the variable with `key` is persisted across restarts.
+    pub fn is_persistent(&self, key: &str) -> bool {
+        self.persistence.is_persistent(key)
+    }
+
+    /// Marks or unmarks a variable as persistent.
+    /// Keys persisted by the config cannot be unmarked.
+    pub fn set_persistent(&self, key: &str, persistent: bool) -> Result<()> {
+        if !Self::key_is_valid(key) {
+            return Err(Report::msg("Invalid key"));
+        }
+
+        if persistent {
+            self.persistence.mark(key);
+        } else {
+            self.persistence.unmark(key)?;
+        }
+
+        self.persistence.schedule_write(&self.variables);
+        Ok(())
+    }
+
     pub fn register_namespace<N>(&self, name: &str, namespace: Arc<N>)
     where
         N: Namespace + Sync + Send + 'static,
@@ -74,16 +184,17 @@ impl VariableManager {
 impl Namespace for VariableManager {
     fn get(&self, key: &str) -> Option<String> {
         if key.contains('.') {
-            let Some((ns, key)) = key.split_once('.') else {
+            let Some((name, nested_key)) = key.split_once('.') else {
                 return None;
             };
 
-            let namespaces = read_lock!(self.namespaces);
-            let Some(ns) = namespaces.get(ns) else {
+            let ns = read_lock!(self.namespaces).get(name).cloned();
+            let Some(ns) = ns else {
+                self.request_namespace(key);
                 return None;
             };
 
-            ns.get(key).map(|v| v.to_owned())
+            get_nested(&ns, nested_key)
         } else {
             read_lock!(self.variables).get(key).and_then(IronVar::get)
         }
@@ -120,6 +231,11 @@ impl WritableNamespace for VariableManager {
     /// creating it if it does not exist.
     fn set(&self, key: &str, value: String) -> Result<()> {
         if Self::key_is_valid(key) {
+            events::emit(Event::Ironvar {
+                key: key.to_string(),
+                value: Some(value.clone()),
+            });
+
             if let Some(var) = write_lock!(self.variables).get_mut(&Box::from(key)) {
                 var.set(Some(value));
             } else {
@@ -127,6 +243,10 @@ impl WritableNamespace for VariableManager {
                 write_lock!(self.variables).insert(key.into(), var);
             }
 
+            if self.persistence.is_persistent(key) {
+                self.persistence.schedule_write(&self.variables);
+            }
+
             Ok(())
         } else {
             Err(Report::msg("Invalid key"))
@@ -134,6 +254,60 @@ impl WritableNamespace for VariableManager {
     }
 }
 
+/// Gets the value for a dot-separated `key` inside `namespace`,
+/// descending into child namespaces where the key is not found directly.
+fn get_nested(namespace: &NamespaceTrait, key: &str) -> Option<String> {
+    namespace.get(key).or_else(|| {
+        let (child, key) = key.split_once('.')?;
+        get_nested(&namespace.get_namespace(child)?, key)
+    })
+}
+
+/// The last known values of a namespace which reads its values on demand,
+/// used to find which values to push to subscribers after they change.
+#[derive(Debug, Default)]
+pub struct NamespaceValues(Mutex<HashMap<String, String>>);
+
+impl NamespaceValues {
+    /// Reads every value in `namespace` and its children,
+    /// notifying subscribers of any which changed since the last call.
+    pub fn update(&self, name: &str, namespace: &dyn Namespace) {
+        let mut current = HashMap::new();
+        flatten_namespace(namespace, name, &mut current);
+
+        let previous = std::mem::replace(&mut *lock!(self.0), current.clone());
+        let variable_manager = Ironbar::variable_manager();
+
+        for (key, value) in &current {
+            if previous.get(key) != Some(value) {
+                variable_manager.notify(key, Some(value.clone()));
+            }
+        }
+
+        for key in previous.keys().filter(|key| !current.contains_key(*key)) {
+            variable_manager.notify(key, None);
+        }
+    }
+}
+
+/// Recursively collects all values in a namespace and its children,
+/// keyed by their full dot-separated path.
+fn flatten_namespace(
+    namespace: &dyn Namespace,
+    prefix: &str,
+    values: &mut HashMap<String, String>,
+) {
+    for (key, value) in namespace.get_all() {
+        values.insert(format!("{prefix}.{key}"), value);
+    }
+
+    for name in namespace.namespaces() {
+        if let Some(child) = namespace.get_namespace(&name) {
+            flatten_namespace(&*child, &format!("{prefix}.{name}"), values);
+        }
+    }
+}
+
 /// Ironbar dynamic variable representation.
 /// Interact with them through the `VARIABLE_MANAGER` `VariableManager` singleton.
 #[derive(Debug)]
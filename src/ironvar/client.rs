@@ -0,0 +1,70 @@
+use super::{Namespace, NamespaceTrait};
+use crate::{Ironbar, read_lock, write_lock};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// A read-only namespace exposing the state of a client.
+///
+/// Unlike namespaces which read their values on demand,
+/// the client pushes each value as it changes,
+/// and any subscribers are notified immediately.
+#[derive(Debug)]
+pub struct ClientNamespace {
+    name: &'static str,
+    values: RwLock<HashMap<Box<str>, String>>,
+}
+
+impl ClientNamespace {
+    /// Creates a new namespace and registers it with the variable manager.
+    pub fn register(name: &'static str) -> Arc<Self> {
+        let namespace = Arc::new(Self {
+            name,
+            values: RwLock::new(HashMap::new()),
+        });
+
+        Ironbar::variable_manager().register_namespace(name, namespace.clone());
+        namespace
+    }
+
+    /// Sets the value for `key`, notifying subscribers if it changed.
+    pub fn set(&self, key: &str, value: impl ToString) {
+        let value = value.to_string();
+
+        let previous = write_lock!(self.values).insert(key.into(), value.clone());
+        if previous.as_ref() != Some(&value) {
+            self.notify(key, Some(value));
+        }
+    }
+
+    /// Removes the value for `key`, notifying subscribers if it was set.
+    pub fn remove(&self, key: &str) {
+        if write_lock!(self.values).remove(key).is_some() {
+            self.notify(key, None);
+        }
+    }
+
+    fn notify(&self, key: &str, value: Option<String>) {
+        Ironbar::variable_manager().notify(&format!("{}.{key}", self.name), value);
+    }
+}
+
+impl Namespace for ClientNamespace {
+    fn get(&self, key: &str) -> Option<String> {
+        read_lock!(self.values).get(key).cloned()
+    }
+
+    fn list(&self) -> Vec<String> {
+        read_lock!(self.values)
+            .keys()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    fn namespaces(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn get_namespace(&self, _key: &str) -> Option<NamespaceTrait> {
+        None
+    }
+}
@@ -0,0 +1,197 @@
+use super::IronVar;
+use crate::{read_lock, spawn, write_lock};
+use color_eyre::{Report, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tracing::{debug, error, warn};
+
+/// The time to wait after a change before writing to disk,
+/// so that rapid updates result in a single write.
+const WRITE_DELAY: Duration = Duration::from_secs(1);
+
+type Variables = Arc<RwLock<HashMap<Box<str>, IronVar>>>;
+
+/// The contents of the state file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StateFile {
+    /// Keys marked as persistent at runtime.
+    #[serde(default)]
+    marked: HashSet<Box<str>>,
+    /// The last known value of each persistent variable.
+    #[serde(default)]
+    values: HashMap<Box<str>, String>,
+}
+
+/// Tracks which ironvars should be persisted,
+/// and writes their values to the state file.
+#[derive(Debug)]
+pub struct Persistence {
+    path: Option<PathBuf>,
+    /// Keys and key prefixes from the config.
+    patterns: RwLock<Vec<Box<str>>>,
+    /// Keys marked as persistent over IPC.
+    marked: RwLock<HashSet<Box<str>>>,
+    restored: AtomicBool,
+    write_tx: OnceLock<mpsc::UnboundedSender<()>>,
+}
+
+impl Persistence {
+    pub fn new() -> Self {
+        Self {
+            path: dirs::state_dir().map(|dir| dir.join("ironbar").join("vars.json")),
+            patterns: RwLock::new(vec![]),
+            marked: RwLock::new(HashSet::new()),
+            restored: AtomicBool::new(false),
+            write_tx: OnceLock::new(),
+        }
+    }
+
+    /// Replaces the list of keys and key prefixes set in the config.
+    pub fn set_patterns(&self, patterns: Vec<Box<str>>) {
+        *write_lock!(self.patterns) = patterns;
+    }
+
+    /// Checks whether the variable with `key` should be persisted,
+    /// either because it matches the config or was marked at runtime.
+    pub fn is_persistent(&self, key: &str) -> bool {
+        self.matches_config(key) || read_lock!(self.marked).contains(key)
+    }
+
+    fn matches_config(&self, key: &str) -> bool {
+        read_lock!(self.patterns)
+            .iter()
+            .any(|pattern| matches_pattern(key, pattern))
+    }
+
+    /// Marks `key` as persistent.
+    pub fn mark(&self, key: &str) {
+        write_lock!(self.marked).insert(key.into());
+    }
+
+    /// Stops persisting `key`.
+    /// Keys matched by the config cannot be unmarked.
+    pub fn unmark(&self, key: &str) -> Result<()> {
+        if self.matches_config(key) {
+            return Err(Report::msg(
+                "Key is persisted by the config and cannot be unmarked",
+            ));
+        }
+
+        if write_lock!(self.marked).remove(key) {
+            Ok(())
+        } else {
+            Err(Report::msg("Key is not persistent"))
+        }
+    }
+
+    /// Reads the state file, restoring the set of marked keys
+    /// and returning the saved variable values.
+    ///
+    /// The file is only read on the first call.
+    /// After that the in-memory values are always the most recent,
+    /// so an empty map is returned.
+    pub fn load(&self) -> HashMap<Box<str>, String> {
+        if self.restored.swap(true, Ordering::Relaxed) {
+            return HashMap::new();
+        }
+
+        let Some(path) = &self.path else {
+            return HashMap::new();
+        };
+
+        let state = match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str::<StateFile>(&contents).unwrap_or_else(|err| {
+                warn!("Ignoring invalid ironvar state file: {err}");
+                StateFile::default()
+            }),
+            Err(_) => StateFile::default(),
+        };
+
+        write_lock!(self.marked).extend(state.marked);
+        state.values
+    }
+
+    /// Queues a write of all persistent variables to the state file.
+    ///
+    /// Writes are delayed, so that multiple changes in quick succession
+    /// only cause a single write.
+    pub fn schedule_write(self: &Arc<Self>, variables: &Variables) {
+        let tx = self.write_tx.get_or_init(|| {
+            let (tx, mut rx) = mpsc::unbounded_channel();
+
+            let persistence = self.clone();
+            let variables = variables.clone();
+
+            spawn(async move {
+                while rx.recv().await.is_some() {
+                    sleep(WRITE_DELAY).await;
+                    while rx.try_recv().is_ok() {}
+
+                    if let Err(err) = persistence.write(&variables) {
+                        error!("Failed to save ironvars: {err:?}");
+                    }
+                }
+            });
+
+            tx
+        });
+
+        tx.send(()).ok();
+    }
+
+    fn write(&self, variables: &Variables) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Err(Report::msg("Could not determine state directory"));
+        };
+
+        let values = read_lock!(variables)
+            .iter()
+            .filter(|(key, _)| self.is_persistent(key))
+            .filter_map(|(key, var)| var.get().map(|value| (key.clone(), value)))
+            .collect();
+
+        let state = StateFile {
+            marked: read_lock!(self.marked).clone(),
+            values,
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(path, serde_json::to_string_pretty(&state)?)?;
+        debug!("Saved ironvars to '{}'", path.display());
+
+        Ok(())
+    }
+}
+
+/// Checks whether `key` matches a persisted key pattern.
+/// Patterns ending in `*` match any key starting with the rest of the pattern.
+fn matches_pattern(key: &str, pattern: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => key.starts_with(prefix),
+        None => key == pattern,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("dnd", "dnd"));
+        assert!(!matches_pattern("dnd_enabled", "dnd"));
+        assert!(matches_pattern("counter_a", "counter_*"));
+        assert!(!matches_pattern("count", "counter_*"));
+        assert!(matches_pattern("anything", "*"));
+    }
+}
@@ -3,7 +3,7 @@
 use std::cell::RefCell;
 use std::env;
 use std::future::Future;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
@@ -18,8 +18,10 @@ use dirs::config_dir;
 use gtk::Application;
 use gtk::gdk::Display;
 use gtk::prelude::*;
+use serde::Deserialize;
 use smithay_client_toolkit::output::OutputInfo;
 use tokio::runtime::Runtime;
+use tokio::sync::watch;
 use tokio::task::{JoinHandle, block_in_place};
 use tracing::{debug, error, info, warn};
 use universal_config::ConfigLoader;
@@ -27,10 +29,10 @@ use universal_config::ConfigLoader;
 use crate::bar::{Bar, create_bar};
 use crate::clients::Clients;
 use crate::clients::wayland::OutputEventType;
-use crate::config::{Config, MonitorConfig};
+use crate::config::{BarConfig, Config};
 use crate::error::ExitCode;
 #[cfg(feature = "ipc")]
-use crate::ironvar::{VariableManager, WritableNamespace};
+use crate::ironvar::{Namespace, VariableManager, WritableNamespace};
 use crate::style::load_css;
 
 mod bar;
@@ -53,6 +55,7 @@ mod modules;
 mod popup;
 mod script;
 mod style;
+mod tasks;
 
 pub const APP_ID: &str = "dev.jstanger.ironbar";
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -84,7 +87,8 @@ fn run_with_args() {
     }
 
     match args.command {
-        Some(command) => {
+        Some(cli::Command::Check { path }) => check_config(path, args.format.unwrap_or_default()),
+        Some(cli::Command::Ipc(command)) => {
             if args.debug {
                 eprintln!("REQUEST: {command:?}");
             }
@@ -92,6 +96,20 @@ fn run_with_args() {
             let rt = create_runtime();
             rt.block_on(async move {
                 let ipc = ipc::Ipc::new();
+
+                // subscriptions stream events until the server closes the connection
+                if matches!(command, ipc::Command::Subscribe { .. }) {
+                    if let Err(err) = ipc
+                        .subscribe(command, args.debug, |line| println!("{line}"))
+                        .await
+                    {
+                        error!("{err:#}");
+                        exit(ExitCode::IpcResponseError as i32)
+                    }
+
+                    return;
+                }
+
                 match ipc.send(command, args.debug).await {
                     Ok(res) => {
                         if args.debug {
@@ -111,23 +129,93 @@ fn run_with_args() {
     }
 }
 
+/// Validates the config file at `path`, or the default config if not provided,
+/// printing any problems found.
+///
+/// Exits with a non-zero code if any errors are found.
+#[cfg(feature = "cli")]
+fn check_config(path: Option<PathBuf>, format: cli::Format) {
+    let Some(path) = path.or_else(|| find_config().0) else {
+        eprintln!("error: could not find a config file");
+        exit(ExitCode::InvalidConfig as i32);
+    };
+
+    // outputs can only be checked against a running compositor
+    let outputs = env::var_os("WAYLAND_DISPLAY").map(|_| {
+        let wayland = clients::wayland::Client::new();
+        wayland.roundtrip();
+
+        wayland
+            .output_info_all()
+            .into_iter()
+            .filter_map(|info| info.name)
+            .collect::<Vec<_>>()
+    });
+
+    let diagnostics = config::check(&path, outputs.as_deref());
+
+    match format {
+        cli::Format::Plain => {
+            for diagnostic in &diagnostics {
+                eprintln!("{diagnostic}");
+            }
+
+            if diagnostics.is_empty() {
+                println!("ok");
+            }
+        }
+        cli::Format::Json => println!(
+            "{}",
+            serde_json::to_string(&diagnostics).expect("to be valid json")
+        ),
+    }
+
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == config::Severity::Error)
+    {
+        exit(ExitCode::InvalidConfig as i32);
+    }
+}
+
 #[derive(Debug)]
 pub struct Ironbar {
     bars: Rc<RefCell<Vec<Bar>>>,
     clients: Rc<RefCell<Clients>>,
     config: Rc<RefCell<Config>>,
     config_dir: PathBuf,
+    config_path: Option<PathBuf>,
+    /// The main config file, and any files it includes.
+    config_files: watch::Sender<Vec<PathBuf>>,
 }
 
 impl Ironbar {
     fn new() -> Self {
-        let (config, config_dir) = load_config();
+        let (config_path, config_dir) = find_config();
+
+        let (mut config, config_files) = load_config(config_path.as_deref()).unwrap_or_else(|err| {
+            error!("Failed to load config: {}", err);
+            warn!("Falling back to the default config");
+            info!("If this is your first time using Ironbar, you should create a config in ~/.config/ironbar/");
+            info!("More info here: https://github.com/JakeStanger/ironbar/wiki/configuration-guide");
+
+            let config_files = config_path
+                .iter()
+                .filter_map(|path| std::path::absolute(path).ok())
+                .collect();
+
+            (Config::default(), config_files)
+        });
+
+        apply_ironvar_defaults(&mut config);
 
         Self {
             bars: Rc::new(RefCell::new(vec![])),
             clients: Rc::new(RefCell::new(Clients::new())),
             config: Rc::new(RefCell::new(config)),
             config_dir,
+            config_path,
+            config_files: watch::Sender::new(config_files),
         }
     }
 
@@ -162,6 +250,12 @@ impl Ironbar {
                 if #[cfg(feature = "ipc")] {
                     let ipc = ipc::Ipc::new();
                     ipc.start(app, instance.clone());
+
+                    // start clients on demand when their namespace is first referenced
+                    if let Some(rx) = Ironbar::variable_manager().take_namespace_requests() {
+                        let instance = instance.clone();
+                        glib_recv_mpsc!(rx, name => instance.clients.borrow_mut().start_namespace(&name));
+                    }
                 }
             }
 
@@ -183,6 +277,20 @@ impl Ironbar {
                 load_css(style_path, app.clone());
             }
 
+            if instance.config_path.is_some() {
+                let instance = instance.clone();
+                let app = app.clone();
+
+                config::watch_config(instance.config_files.subscribe(), move || {
+                    if let Err(err) = instance.reload(&app) {
+                        error!(
+                            "{:?}",
+                            err.wrap_err("Failed to reload config, keeping current bars")
+                        );
+                    }
+                });
+            }
+
             let (tx, rx) = mpsc::channel();
 
             #[cfg(feature = "ipc")]
@@ -282,11 +390,92 @@ impl Ironbar {
             .collect()
     }
 
-    /// Re-reads the config file from disk and replaces the active config.
-    /// Note this does *not* reload bars, which must be performed separately.
+    /// Gets clones of all modules across all bars with the given name,
+    /// alongside the name of the bar each belongs to.
     #[cfg(feature = "ipc")]
-    fn reload_config(&self) {
-        self.config.replace(load_config().0);
+    #[must_use]
+    pub fn modules_by_name(&self, name: &str) -> Vec<(String, modules::ModuleRef)> {
+        self.bars
+            .borrow()
+            .iter()
+            .flat_map(|bar| {
+                bar.modules_by_name(name)
+                    .into_iter()
+                    .map(|module| (bar.name().to_string(), module))
+            })
+            .collect()
+    }
+
+    /// Re-reads the config file from disk and applies it to the running bars.
+    ///
+    /// Only bars and modules whose config has changed are rebuilt.
+    /// If the new config fails to load, the current config and bars are kept.
+    pub fn reload(self: &Rc<Self>, app: &Application) -> Result<()> {
+        let (mut config, config_files) = load_config(self.config_path.as_deref())?;
+        info!("Reloading config");
+
+        // only notify the watcher if the set of included files changes
+        self.config_files.send_if_modified(|files| {
+            let changed = *files != config_files;
+            *files = config_files;
+            changed
+        });
+
+        apply_ironvar_defaults(&mut config);
+
+        // icon overrides are passed into every module, so any change means a full rebuild
+        let rebuild_all = config.icon_overrides != self.config.borrow().icon_overrides;
+        self.config.replace(config);
+
+        let outputs = self.clients.borrow_mut().wayland().output_info_all();
+
+        let mut old_bars = std::mem::take(&mut *self.bars.borrow_mut());
+        let mut new_bars = vec![];
+
+        for output in outputs {
+            let Some(monitor_name) = output.name.clone() else {
+                continue;
+            };
+
+            let (existing, rest): (Vec<_>, Vec<_>) = old_bars
+                .into_iter()
+                .partition(|bar| bar.monitor_name() == monitor_name);
+            old_bars = rest;
+
+            let mut existing = existing.into_iter();
+            let configs = self.config.borrow().bar_configs(&monitor_name);
+
+            for config in configs {
+                match existing.next() {
+                    Some(bar) if !rebuild_all && bar.can_reload(&config) => {
+                        bar.reload(config);
+                        new_bars.push(bar);
+                    }
+                    bar => {
+                        if let Some(bar) = bar {
+                            bar.close();
+                        }
+
+                        match create_output_bar(self, app, &output, config) {
+                            Ok(bar) => new_bars.push(bar),
+                            Err(err) => error!("{err:?}"),
+                        }
+                    }
+                }
+            }
+
+            for bar in existing {
+                bar.close();
+            }
+        }
+
+        for bar in old_bars {
+            bar.close();
+        }
+
+        self.bars.borrow_mut().append(&mut new_bars);
+
+        Ok(())
     }
 }
 
@@ -295,52 +484,100 @@ fn start_ironbar() {
     ironbar.start();
 }
 
-/// Loads the config file from disk.
-fn load_config() -> (Config, PathBuf) {
+/// Locates the config file, and the directory containing it.
+///
+/// If the `IRONBAR_CONFIG` environment variable is set, that path is used.
+/// Otherwise, the Ironbar config directory is searched
+/// for a `config` file in any supported format.
+fn find_config() -> (Option<PathBuf>, PathBuf) {
+    const EXTENSIONS: &[&str] = &[
+        #[cfg(feature = "config+json")]
+        "json",
+        #[cfg(feature = "config+yaml")]
+        "yaml",
+        #[cfg(feature = "config+toml")]
+        "toml",
+        #[cfg(feature = "config+corn")]
+        "corn",
+        #[cfg(feature = "config+ron")]
+        "ron",
+    ];
+
     let config_path = env::var("IRONBAR_CONFIG");
 
-    let (config, directory) = if let Ok(config_path) = config_path {
+    let (path, directory) = if let Ok(config_path) = config_path {
         let path = PathBuf::from(config_path);
-        (
-            ConfigLoader::load(&path),
-            path.parent()
-                .map(PathBuf::from)
-                .ok_or_else(|| Report::msg("Specified path has no parent")),
-        )
+        let directory = path
+            .parent()
+            .map(PathBuf::from)
+            .ok_or_else(|| Report::msg("Specified path has no parent"));
+
+        (Some(path), directory)
     } else {
         let config_loader = ConfigLoader::new("ironbar");
-        (
-            config_loader.find_and_load(),
-            config_loader.config_dir().map_err(Report::new),
-        )
-    };
+        let directory = config_loader.config_dir().map_err(Report::new);
 
-    let mut config = config.unwrap_or_else(|err| {
-        error!("Failed to load config: {}", err);
-        warn!("Falling back to the default config");
-        info!("If this is your first time using Ironbar, you should create a config in ~/.config/ironbar/");
-        info!("More info here: https://github.com/JakeStanger/ironbar/wiki/configuration-guide");
+        let path = directory.as_ref().ok().and_then(|dir| {
+            EXTENSIONS
+                .iter()
+                .map(|extension| dir.join(format!("config.{extension}")))
+                .find(|path| path.exists())
+        });
 
-        Config::default()
-    });
+        (path, directory)
+    };
 
     let directory = directory
         .and_then(|dir| dir.canonicalize().map_err(Report::new))
         .unwrap_or_else(|_| env::current_dir().expect("to have current working directory"));
 
-    debug!("Loaded config file");
+    (path, directory)
+}
+
+/// Loads the config file from disk,
+/// resolving any included files.
+///
+/// Returns the config alongside the paths of every file loaded.
+fn load_config(path: Option<&Path>) -> Result<(Config, Vec<PathBuf>)> {
+    let path = path.ok_or_else(|| Report::msg("Could not find a config file"))?;
+    let (value, files) = config::load_value(path)?;
+
+    let config = match Config::deserialize(value) {
+        Ok(config) => config,
+        // loading the file directly gives errors with line numbers,
+        // which are lost once it has been read into a value.
+        Err(_) if files.len() == 1 => ConfigLoader::load(path)?,
+        Err(err) => return Err(err.into()),
+    };
+
+    debug!("Loaded config file (from {} files)", files.len());
 
+    Ok((config, files))
+}
+
+/// Sets the initial values of any ironvars defined in the config.
+///
+/// Values saved for persistent ironvars are restored first,
+/// and take priority over the defaults.
+#[cfg_attr(not(feature = "ipc"), allow(unused_variables))]
+fn apply_ironvar_defaults(config: &mut Config) {
     #[cfg(feature = "ipc")]
-    if let Some(ironvars) = config.ironvar_defaults.take() {
+    {
         let variable_manager = Ironbar::variable_manager();
-        for (k, v) in ironvars {
+
+        variable_manager.set_persisted_keys(std::mem::take(&mut config.persist_ironvars));
+        variable_manager.restore_persisted();
+
+        for (k, v) in config.ironvar_defaults.take().unwrap_or_default() {
+            if variable_manager.is_persistent(&k) && variable_manager.get(&k).is_some() {
+                continue;
+            }
+
             if variable_manager.set(&k, v).is_err() {
                 warn!("Ignoring invalid ironvar: '{k}'");
             }
         }
     }
-
-    (config, directory)
 }
 
 /// Gets the GDK `Display` instance.
@@ -361,6 +598,25 @@ fn load_output_bars(
     app: &Application,
     output: &OutputInfo,
 ) -> Result<Vec<Bar>> {
+    let Some(monitor_name) = &output.name else {
+        return Err(Report::msg("Output missing monitor name"));
+    };
+
+    let configs = ironbar.config.borrow().bar_configs(monitor_name);
+
+    configs
+        .into_iter()
+        .map(|config| create_output_bar(ironbar, app, output, config))
+        .collect()
+}
+
+/// Creates a single bar on an output.
+fn create_output_bar(
+    ironbar: &Rc<Ironbar>,
+    app: &Application,
+    output: &OutputInfo,
+    config: BarConfig,
+) -> Result<Bar> {
     // Hack to track monitor positions due to new GTK3/wlroots bug:
     // https://github.com/swaywm/sway/issues/8164
     // This relies on Wayland always tracking monitors in the same order as GDK.
@@ -383,8 +639,7 @@ fn load_output_bars(
         lock!(map).len() - 1
     };
 
-    let config = ironbar.config.borrow();
-    let icon_overrides = Arc::new(config.icon_overrides.clone());
+    let icon_overrides = Arc::new(ironbar.config.borrow().icon_overrides.clone());
     let display = get_display();
 
     // let pos = output.logical_position.unwrap_or_default();
@@ -394,52 +649,15 @@ fn load_output_bars(
 
     let monitor = display.monitor(index as i32).expect("monitor to exist");
 
-    let show_default_bar =
-        config.bar.start.is_some() || config.bar.center.is_some() || config.bar.end.is_some();
-
-    let bars = match config
-        .monitors
-        .as_ref()
-        .and_then(|config| config.get(monitor_name))
-    {
-        Some(MonitorConfig::Single(config)) => {
-            vec![create_bar(
-                app,
-                &monitor,
-                monitor_name.to_string(),
-                output_size,
-                icon_overrides,
-                config.clone(),
-                ironbar.clone(),
-            )?]
-        }
-        Some(MonitorConfig::Multiple(configs)) => configs
-            .iter()
-            .map(|config| {
-                create_bar(
-                    app,
-                    &monitor,
-                    monitor_name.to_string(),
-                    output_size,
-                    icon_overrides.clone(),
-                    config.clone(),
-                    ironbar.clone(),
-                )
-            })
-            .collect::<Result<_>>()?,
-        None if show_default_bar => vec![create_bar(
-            app,
-            &monitor,
-            monitor_name.to_string(),
-            output_size,
-            icon_overrides,
-            config.bar.clone(),
-            ironbar.clone(),
-        )?],
-        None => vec![],
-    };
-
-    Ok(bars)
+    create_bar(
+        app,
+        &monitor,
+        monitor_name.to_string(),
+        output_size,
+        icon_overrides,
+        config,
+        ironbar.clone(),
+    )
 }
 
 fn create_runtime() -> Runtime {
@@ -0,0 +1,58 @@
+use color_eyre::{Report, Result};
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+use std::str::FromStr;
+use tokio::sync::mpsc;
+
+type ActionFn = dyn Fn(&str, &[String]) -> Result<()>;
+
+/// Handles actions sent to a single module instance over IPC.
+///
+/// Actions are parsed using the module's [`Module::ipc_action`](super::Module::ipc_action)
+/// implementation, and the resulting message is sent to its controller.
+#[derive(Clone)]
+pub struct ActionHandler(Rc<ActionFn>);
+
+impl ActionHandler {
+    pub fn new<T: 'static>(
+        parse: fn(&str, &[String]) -> Result<T>,
+        controller_tx: mpsc::Sender<T>,
+    ) -> Self {
+        Self(Rc::new(move |action, args| {
+            let message = parse(action, args)?;
+            controller_tx
+                .try_send(message)
+                .map_err(|_| Report::msg("Module is not currently accepting actions"))
+        }))
+    }
+
+    /// Runs `action`, returning an error if the action or its arguments are invalid.
+    pub fn handle(&self, action: &str, args: &[String]) -> Result<()> {
+        (self.0)(action, args)
+    }
+}
+
+impl Debug for ActionHandler {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ActionHandler").finish_non_exhaustive()
+    }
+}
+
+/// Creates the error for an action the module does not support,
+/// listing the actions which are.
+pub fn unknown_action(action: &str, supported: &[&str]) -> Report {
+    Report::msg(format!(
+        "Unknown action '{action}'. Supported actions: {}",
+        supported.join(", ")
+    ))
+}
+
+/// Parses the argument at `index`, named `name` for error messages.
+pub fn parse_arg<T: FromStr>(args: &[String], index: usize, name: &str) -> Result<T> {
+    let arg = args
+        .get(index)
+        .ok_or_else(|| Report::msg(format!("Missing argument '{name}'")))?;
+
+    arg.parse()
+        .map_err(|_| Report::msg(format!("Invalid value for '{name}': '{arg}'")))
+}
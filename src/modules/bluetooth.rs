@@ -0,0 +1,361 @@
+use crate::clients::bluetooth::{Client, ClientState};
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::image::ImageProvider;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{glib_recv, module_impl, send_async, try_send};
+use color_eyre::Result;
+use futures_lite::StreamExt;
+use futures_signals::signal::SignalExt;
+use gtk::prelude::*;
+use gtk::{Button, Image, Label, Orientation, Switch};
+use serde::Deserialize;
+use tokio::sync::{broadcast, mpsc};
+use tracing::error;
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BluetoothModule {
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon} {connected_count}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// Adapter state icons.
+    ///
+    /// See [icons](#icons).
+    #[serde(default)]
+    icons: Icons,
+
+    /// Size to render device icons at in the popup.
+    ///
+    /// **Default**: `24`
+    #[serde(default = "default_icon_size")]
+    icon_size: i32,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(default, flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("{icon} {connected_count}")
+}
+
+const fn default_icon_size() -> i32 {
+    24
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Icons {
+    /// Icon to show when the adapter is on.
+    ///
+    /// **Default**: `󰂯`
+    #[serde(default = "default_icon_enabled")]
+    enabled: String,
+
+    /// Icon to show when the adapter is off.
+    ///
+    /// **Default**: `󰂲`
+    #[serde(default = "default_icon_disabled")]
+    disabled: String,
+
+    /// Icon to show when at least one device is connected.
+    ///
+    /// **Default**: `󰂱`
+    #[serde(default = "default_icon_connected")]
+    connected: String,
+}
+
+impl Icons {
+    fn state_icon(&self, state: &ClientState) -> &str {
+        let powered = state
+            .adapter
+            .as_ref()
+            .is_some_and(|adapter| adapter.powered);
+
+        if !powered {
+            &self.disabled
+        } else if state.connected_devices().next().is_some() {
+            &self.connected
+        } else {
+            &self.enabled
+        }
+    }
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            enabled: default_icon_enabled(),
+            disabled: default_icon_disabled(),
+            connected: default_icon_connected(),
+        }
+    }
+}
+
+fn default_icon_enabled() -> String {
+    String::from("󰂯")
+}
+
+fn default_icon_disabled() -> String {
+    String::from("󰂲")
+}
+
+fn default_icon_connected() -> String {
+    String::from("󰂱")
+}
+
+#[derive(Debug, Clone)]
+pub enum Update {
+    /// Turns the adapter on or off,
+    /// or toggles it if `None`.
+    SetPowered(Option<bool>),
+    /// Connects to the device at this path,
+    /// or disconnects if already connected.
+    ToggleDevice(String),
+}
+
+async fn handle_update(client: &Client, update: Update) -> Result<()> {
+    let state = client.state();
+
+    match update {
+        Update::SetPowered(powered) => {
+            let current = state
+                .adapter
+                .as_ref()
+                .is_some_and(|adapter| adapter.powered);
+            let powered = powered.unwrap_or(!current);
+
+            // the popup switch also fires when updated to match the current state
+            if powered == current {
+                Ok(())
+            } else {
+                client.set_powered(powered).await
+            }
+        }
+        Update::ToggleDevice(path) => {
+            match state.devices.iter().find(|device| device.path == path) {
+                Some(device) if device.connected => client.disconnect(&path).await,
+                Some(_) => client.connect(&path).await,
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+impl Module<Button> for BluetoothModule {
+    type SendMessage = ClientState;
+    type ReceiveMessage = Update;
+
+    module_impl!("bluetooth");
+
+    #[cfg(feature = "ipc")]
+    fn ipc_action(action: &str, _args: &[String]) -> Result<Self::ReceiveMessage> {
+        use crate::modules::actions::unknown_action;
+
+        match action {
+            "power-on" => Ok(Update::SetPowered(Some(true))),
+            "power-off" => Ok(Update::SetPowered(Some(false))),
+            "toggle-power" => Ok(Update::SetPowered(None)),
+            _ => Err(unknown_action(
+                action,
+                &["power-on", "power-off", "toggle-power"],
+            )),
+        }
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let client = context.try_client::<Client>()?;
+
+        {
+            let mut stream = client.subscribe().to_stream();
+            let tx = context.tx.clone();
+
+            context.spawn(async move {
+                while let Some(state) = stream.next().await {
+                    send_async!(tx, ModuleUpdateEvent::Update(state));
+                }
+            });
+        }
+
+        context.spawn(async move {
+            while let Some(update) = rx.recv().await {
+                if let Err(err) = handle_update(&client, update).await {
+                    error!("{err:?}");
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button_label = Label::builder()
+            .use_markup(true)
+            .angle(self.layout.angle(info))
+            .justify(self.layout.justify.into())
+            .build();
+
+        let button = Button::new();
+        button.add(&button_label);
+
+        {
+            let tx = context.tx.clone();
+
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let rx = context.subscribe();
+            let icons = self.icons.clone();
+            let format = self.format.clone();
+            let button = button.clone();
+
+            glib_recv!(rx, state => {
+                // hide the widget on systems without bluetooth
+                button.set_visible(state.adapter.is_some());
+
+                let connected = state
+                    .connected_devices()
+                    .map(|device| device.name.as_str())
+                    .collect::<Vec<_>>();
+
+                let label = format
+                    .replace("{icon}", icons.state_icon(&state))
+                    .replace("{connected_count}", &connected.len().to_string())
+                    .replace("{connected}", &connected.join(", "))
+                    .replace(
+                        "{adapter}",
+                        state.adapter.as_ref().map(|adapter| adapter.name.as_str()).unwrap_or_default(),
+                    );
+
+                button_label.set_label_escaped(&label);
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 10);
+
+        let header = gtk::Box::new(Orientation::Horizontal, 5);
+        header.add_class("header");
+        container.add(&header);
+
+        let adapter_label = Label::new(None);
+        adapter_label.add_class("adapter");
+        header.add(&adapter_label);
+
+        let power_toggle = Switch::new();
+        power_toggle.add_class("power-toggle");
+        header.pack_end(&power_toggle, false, false, 0);
+
+        {
+            let tx = tx.clone();
+            power_toggle.connect_active_notify(move |switch| {
+                try_send!(tx, Update::SetPowered(Some(switch.is_active())));
+            });
+        }
+
+        let devices = gtk::Box::new(Orientation::Vertical, 2);
+        devices.add_class("devices");
+        container.add(&devices);
+
+        container.show_all();
+
+        let icon_theme = info.icon_theme.clone();
+        let icon_size = self.icon_size;
+
+        glib_recv!(rx, state => {
+            let powered = state.adapter.as_ref().is_some_and(|adapter| adapter.powered);
+
+            adapter_label.set_label(state.adapter.as_ref().map(|adapter| adapter.name.as_str()).unwrap_or_default());
+            power_toggle.set_active(powered);
+            devices.set_sensitive(powered);
+
+            for child in devices.children() {
+                devices.remove(&child);
+            }
+
+            for device in &state.devices {
+                let button = Button::new();
+                button.add_class("device");
+                if device.connected {
+                    button.add_class("connected");
+                }
+
+                let row = gtk::Box::new(Orientation::Horizontal, 5);
+                button.add(&row);
+
+                let icon = Image::new();
+                icon.add_class("icon");
+                let icon_name = device.icon.as_deref().unwrap_or("bluetooth");
+                ImageProvider::parse(icon_name, &icon_theme, true, icon_size)
+                    .map(|provider| provider.load_into_image(&icon));
+                row.add(&icon);
+
+                let name = Label::new(Some(&device.name));
+                name.add_class("name");
+                row.add(&name);
+
+                if let Some(battery) = device.battery {
+                    let label = Label::new(Some(&format!("{battery}%")));
+                    label.add_class("battery");
+                    row.pack_end(&label, false, false, 0);
+                }
+
+                {
+                    let tx = tx.clone();
+                    let path = device.path.clone();
+                    button.connect_clicked(move |_| {
+                        try_send!(tx, Update::ToggleDevice(path.clone()));
+                    });
+                }
+
+                devices.add(&button);
+            }
+
+            devices.show_all();
+        });
+
+        Some(container)
+    }
+}
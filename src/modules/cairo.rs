@@ -1,6 +1,6 @@
 use crate::config::CommonConfig;
 use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
-use crate::{glib_recv, module_impl, spawn, try_send};
+use crate::{glib_recv, module_impl, try_send};
 use cairo::{Format, ImageSurface};
 use glib::Propagation;
 use glib::translate::IntoGlibPtr;
@@ -17,7 +17,7 @@ use tokio::sync::mpsc::Receiver;
 use tokio::time::sleep;
 use tracing::{debug, error};
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CairoModule {
     /// The path to the Lua script to load.
@@ -77,7 +77,7 @@ impl Module<gtk::Box> for CairoModule {
         let path = self.path.clone();
 
         let tx = context.tx.clone();
-        spawn(async move {
+        context.spawn(async move {
             let parent = path.parent().expect("to have parent path");
 
             let mut watcher = recommended_watcher({
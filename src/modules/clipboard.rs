@@ -7,7 +7,7 @@ use crate::image::IconButton;
 use crate::modules::{
     Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
 };
-use crate::{glib_recv, module_impl, spawn, try_send};
+use crate::{glib_recv, module_impl, try_send};
 use glib::Propagation;
 use gtk::gdk_pixbuf::Pixbuf;
 use gtk::gio::{Cancellable, MemoryInputStream};
@@ -19,7 +19,7 @@ use std::ops::Deref;
 use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, error};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ClipboardModule {
     /// The icon to show on the bar widget button.
@@ -90,6 +90,17 @@ impl Module<Button> for ClipboardModule {
 
     module_impl!("clipboard");
 
+    #[cfg(feature = "ipc")]
+    fn ipc_action(action: &str, args: &[String]) -> color_eyre::Result<Self::ReceiveMessage> {
+        use crate::modules::actions::{parse_arg, unknown_action};
+
+        match action {
+            "copy" => parse_arg(args, 0, "id").map(UIEvent::Copy),
+            "remove" => parse_arg(args, 0, "id").map(UIEvent::Remove),
+            _ => Err(unknown_action(action, &["copy", "remove"])),
+        }
+    }
+
     fn spawn_controller(
         &self,
         _info: &ModuleInfo,
@@ -102,7 +113,7 @@ impl Module<Button> for ClipboardModule {
         let client = context.client::<clipboard::Client>();
 
         // listen to clipboard events
-        spawn(async move {
+        context.spawn(async move {
             let mut rx = client.subscribe(max_items);
 
             while let Some(event) = rx.recv().await {
@@ -131,7 +142,7 @@ impl Module<Button> for ClipboardModule {
         let client = context.client::<clipboard::Client>();
 
         // listen to ui events
-        spawn(async move {
+        context.spawn(async move {
             while let Some(event) = rx.recv().await {
                 match event {
                     UIEvent::Copy(id) => client.copy(id),
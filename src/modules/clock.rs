@@ -13,9 +13,9 @@ use crate::gtk_helpers::IronbarGtkExt;
 use crate::modules::{
     Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
 };
-use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use crate::{glib_recv, module_impl, send_async, try_send};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ClockModule {
     /// The format string to use for the date/time shown on the bar.
@@ -104,7 +104,7 @@ impl Module<Button> for ClockModule {
         _rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let tx = context.tx.clone();
-        spawn(async move {
+        context.spawn(async move {
             loop {
                 let date = Local::now();
                 send_async!(tx, ModuleUpdateEvent::Update(date));
@@ -9,7 +9,7 @@ use crate::gtk_helpers::IronbarLabelExt;
 use crate::modules::PopupButton;
 use crate::{build, try_send};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ButtonWidget {
     /// Widget name.
@@ -72,7 +72,7 @@ impl CustomWidget for ButtonWidget {
 
             button.add(&label);
 
-            dynamic_string(&text, move |string| {
+            dynamic_string(&text, context.tasks, move |string| {
                 label.set_label_escaped(&string);
             });
         }
@@ -8,7 +8,7 @@ use crate::image::ImageProvider;
 
 use super::{CustomWidget, CustomWidgetContext};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ImageWidget {
     /// Widget name.
@@ -50,7 +50,7 @@ impl CustomWidget for ImageWidget {
             let gtk_image = gtk_image.clone();
             let icon_theme = context.icon_theme.clone();
 
-            dynamic_string(&self.src, move |src| {
+            dynamic_string(&self.src, context.tasks, move |src| {
                 ImageProvider::parse(&src, &icon_theme, false, self.size)
                     .map(|image| image.load_into_image(&gtk_image));
             });
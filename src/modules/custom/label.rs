@@ -8,7 +8,7 @@ use crate::config::{LayoutConfig, TruncateMode};
 use crate::dynamic_value::dynamic_string;
 use crate::gtk_helpers::IronbarLabelExt;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct LabelWidget {
     /// Widget name.
@@ -57,7 +57,7 @@ impl CustomWidget for LabelWidget {
 
         {
             let label = label.clone();
-            dynamic_string(&self.label, move |string| {
+            dynamic_string(&self.label, context.tasks, move |string| {
                 label.set_label_escaped(&string);
             });
         }
@@ -17,7 +17,8 @@ use crate::modules::{
     ModuleUpdateEvent, PopupButton, PopupModuleFactory, WidgetContext, wrap_widget,
 };
 use crate::script::Script;
-use crate::{module_impl, send_async, spawn};
+use crate::tasks::TaskScope;
+use crate::{module_impl, send_async};
 use color_eyre::Result;
 use gtk::prelude::*;
 use gtk::{Button, IconTheme, Orientation};
@@ -27,7 +28,7 @@ use std::rc::Rc;
 use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, error};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CustomModule {
     /// Modules and widgets to add to the bar container.
@@ -45,7 +46,7 @@ pub struct CustomModule {
     pub common: Option<CommonConfig>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct WidgetConfig {
     /// One of a custom module native Ironbar module.
@@ -57,7 +58,7 @@ pub struct WidgetConfig {
     common: CommonConfig,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[serde(untagged)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum WidgetOrModule {
@@ -68,7 +69,7 @@ pub enum WidgetOrModule {
     Module(ModuleConfig),
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[serde(tag = "type", rename_all = "snake_case")]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum Widget {
@@ -95,6 +96,7 @@ struct CustomWidgetContext<'a> {
     icon_theme: &'a IconTheme,
     popup_buttons: Rc<RefCell<Vec<Button>>>,
     module_factory: AnyModuleFactory,
+    tasks: &'a TaskScope,
 }
 
 trait CustomWidget {
@@ -141,8 +143,9 @@ impl WidgetOrModule {
         match self {
             WidgetOrModule::Widget(widget) => widget.add_to(parent, context, common),
             WidgetOrModule::Module(config) => {
-                if let Err(err) = config.create(&context.module_factory, parent, context.info) {
-                    error!("{err:?}");
+                match config.create(&context.module_factory, parent, context.info) {
+                    Ok(module) => context.tasks.add_child(module.tasks),
+                    Err(err) => error!("{err:?}"),
                 }
             }
         }
@@ -158,6 +161,7 @@ impl Widget {
                     &$widget.into_widget(context.clone()),
                     common,
                     context.bar_orientation,
+                    context.tasks,
                 )
             };
         }
@@ -195,7 +199,7 @@ impl Module<gtk::Box> for CustomModule {
         mut rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let tx = context.tx.clone();
-        spawn(async move {
+        context.spawn(async move {
             while let Some(event) = rx.recv().await {
                 if event.cmd.starts_with('!') {
                     let script = Script::from(&event.cmd[1..]);
@@ -238,6 +242,7 @@ impl Module<gtk::Box> for CustomModule {
             popup_buttons: popup_buttons.clone(),
             module_factory: BarModuleFactory::new(context.ironbar.clone(), context.popup.clone())
                 .into(),
+            tasks: &context.tasks,
         };
 
         self.bar.clone().into_iter().for_each(|widget| {
@@ -296,6 +301,7 @@ impl Module<gtk::Box> for CustomModule {
                     context.button_id,
                 )
                 .into(),
+                tasks: &context.tasks,
             };
 
             for widget in popup {
@@ -8,11 +8,11 @@ use crate::config::ModuleOrientation;
 use crate::dynamic_value::dynamic_string;
 use crate::modules::custom::set_length;
 use crate::script::{OutputStream, Script, ScriptInput};
-use crate::{build, glib_recv_mpsc, spawn, try_send};
+use crate::{build, glib_recv_mpsc, try_send};
 
 use super::{CustomWidget, CustomWidgetContext};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ProgressWidget {
     /// Widget name.
@@ -83,7 +83,7 @@ impl CustomWidget for ProgressWidget {
 
             let (tx, rx) = mpsc::channel(128);
 
-            spawn(async move {
+            context.tasks.spawn(async move {
                 script
                     .run(None, move |stream, _success| match stream {
                         OutputStream::Stdout(out) => match out.parse::<f64>() {
@@ -102,7 +102,7 @@ impl CustomWidget for ProgressWidget {
             let progress = progress.clone();
             progress.set_show_text(true);
 
-            dynamic_string(&text, move |string| {
+            dynamic_string(&text, context.tasks, move |string| {
                 progress.set_text(Some(&string));
             });
         }
@@ -11,11 +11,11 @@ use tracing::error;
 use crate::config::ModuleOrientation;
 use crate::modules::custom::set_length;
 use crate::script::{OutputStream, Script, ScriptInput};
-use crate::{build, glib_recv_mpsc, spawn, try_send};
+use crate::{build, glib_recv_mpsc, try_send};
 
 use super::{CustomWidget, CustomWidgetContext, ExecEvent};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SliderWidget {
     /// Widget name.
@@ -156,7 +156,7 @@ impl CustomWidget for SliderWidget {
 
             let (tx, rx) = mpsc::channel(128);
 
-            spawn(async move {
+            context.tasks.spawn(async move {
                 script
                     .run(None, move |stream, _success| match stream {
                         OutputStream::Stdout(out) => match out.parse() {
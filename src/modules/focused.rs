@@ -4,7 +4,7 @@ use crate::gtk_helpers::IronbarGtkExt;
 use crate::gtk_helpers::IronbarLabelExt;
 use crate::image::ImageProvider;
 use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
-use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use crate::{glib_recv, module_impl, send_async, try_send};
 use color_eyre::Result;
 use gtk::Label;
 use gtk::prelude::*;
@@ -12,7 +12,7 @@ use serde::Deserialize;
 use tokio::sync::mpsc;
 use tracing::debug;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct FocusedModule {
     /// Whether to show icon on the bar.
@@ -79,7 +79,7 @@ impl Module<gtk::Box> for FocusedModule {
         let tx = context.tx.clone();
         let wl = context.client::<wayland::Client>();
 
-        spawn(async move {
+        context.spawn(async move {
             let mut current = None;
 
             let mut wlrx = wl.subscribe_toplevels();
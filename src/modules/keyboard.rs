@@ -13,9 +13,9 @@ use crate::clients::libinput::{Event, Key, KeyEvent};
 use crate::config::{CommonConfig, LayoutConfig};
 use crate::gtk_helpers::IronbarGtkExt;
 use crate::image::{IconButton, IconLabel};
-use crate::{glib_recv, module_impl, module_update, send_async, spawn, try_send};
+use crate::{glib_recv, module_impl, module_update, send_async, try_send};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct KeyboardModule {
     /// Whether to show capslock indicator.
@@ -71,7 +71,7 @@ pub struct KeyboardModule {
     pub common: Option<CommonConfig>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 struct Icons {
     /// Icon to show when capslock is enabled.
@@ -186,7 +186,7 @@ impl Module<gtk::Box> for KeyboardModule {
         let client = context.ironbar.clients.borrow_mut().libinput(&self.seat);
 
         let tx = context.tx.clone();
-        spawn(async move {
+        context.spawn(async move {
             let mut rx = client.subscribe();
             while let Ok(ev) = rx.recv().await {
                 match ev {
@@ -210,7 +210,7 @@ impl Module<gtk::Box> for KeyboardModule {
         {
             let client = client.clone();
             let tx = context.tx.clone();
-            spawn(async move {
+            context.spawn(async move {
                 let mut srx = client.subscribe();
 
                 trace!("Set up keyboard_layout subscription");
@@ -236,7 +236,7 @@ impl Module<gtk::Box> for KeyboardModule {
         }
 
         // Change keyboard layout
-        spawn(async move {
+        context.spawn(async move {
             trace!("Setting up keyboard_layout UI event handler");
 
             while let Some(()) = rx.recv().await {
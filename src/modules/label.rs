@@ -8,7 +8,7 @@ use gtk::Label;
 use serde::Deserialize;
 use tokio::sync::mpsc;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct LabelModule {
     /// The text to show on the label.
@@ -56,7 +56,7 @@ impl Module<Label> for LabelModule {
         _rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let tx = context.tx.clone();
-        dynamic_string(&self.label, move |string| {
+        dynamic_string(&self.label, &context.tasks, move |string| {
             try_send!(tx, ModuleUpdateEvent::Update(string));
         });
 
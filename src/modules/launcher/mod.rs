@@ -11,7 +11,7 @@ use crate::desktop_file::find_desktop_file;
 use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
 use crate::modules::launcher::item::ImageTextButton;
 use crate::modules::launcher::pagination::{IconContext, Pagination};
-use crate::{arc_mut, glib_recv, lock, module_impl, send_async, spawn, try_send, write_lock};
+use crate::{arc_mut, glib_recv, lock, module_impl, send_async, try_send, write_lock};
 use color_eyre::{Help, Report};
 use gtk::prelude::*;
 use gtk::{Button, Orientation};
@@ -23,7 +23,7 @@ use std::sync::Arc;
 use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, error, trace};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct LauncherModule {
     /// List of app IDs (or classes) to always show regardless of open state,
@@ -114,7 +114,7 @@ pub struct LauncherModule {
     pub common: Option<CommonConfig>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 struct Icons {
     /// Icon to show for page back button.
@@ -209,6 +209,18 @@ impl Module<gtk::Box> for LauncherModule {
 
     module_impl!("launcher");
 
+    #[cfg(feature = "ipc")]
+    fn ipc_action(action: &str, args: &[String]) -> crate::Result<Self::ReceiveMessage> {
+        use crate::modules::actions::{parse_arg, unknown_action};
+
+        match action {
+            "focus" => parse_arg(args, 0, "app_id").map(ItemEvent::FocusItem),
+            "open" => parse_arg(args, 0, "app_id").map(ItemEvent::OpenItem),
+            "minimize" => parse_arg(args, 0, "app_id").map(ItemEvent::MinimizeItem),
+            _ => Err(unknown_action(action, &["focus", "open", "minimize"])),
+        }
+    }
+
     fn spawn_controller(
         &self,
         info: &ModuleInfo,
@@ -244,7 +256,7 @@ impl Module<gtk::Box> for LauncherModule {
         let tx2 = context.tx.clone();
 
         let wl = context.client::<wayland::Client>();
-        spawn(async move {
+        context.spawn(async move {
             let items = items2;
             let tx = tx2;
 
@@ -385,7 +397,7 @@ impl Module<gtk::Box> for LauncherModule {
         // listen to ui events
         let minimize_focused = self.minimize_focused;
         let wl = context.client::<wayland::Client>();
-        spawn(async move {
+        context.spawn(async move {
             while let Some(event) = rx.recv().await {
                 if let ItemEvent::OpenItem(app_id) = event {
                     find_desktop_file(&app_id).map_or_else(
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::future::Future;
 use std::rc::Rc;
 use std::sync::Arc;
 
@@ -9,14 +10,21 @@ use gtk::gdk::{EventMask, Monitor};
 use gtk::prelude::*;
 use gtk::{Application, Button, EventBox, IconTheme, Orientation, Revealer, Widget};
 use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
 use tracing::debug;
 
 use crate::clients::{ClientResult, ProvidesClient, ProvidesFallibleClient};
 use crate::config::{BarPosition, CommonConfig, TransitionType};
+use crate::dynamic_value::DynamicBool;
 use crate::gtk_helpers::{IronbarGtkExt, WidgetGeometry};
 use crate::popup::Popup;
+use crate::tasks::TaskScope;
 use crate::{Ironbar, glib_recv_mpsc, send};
 
+#[cfg(feature = "ipc")]
+pub mod actions;
+#[cfg(feature = "bluetooth")]
+pub mod bluetooth;
 #[cfg(feature = "cairo")]
 pub mod cairo;
 #[cfg(feature = "clipboard")]
@@ -47,6 +55,8 @@ pub mod music;
 pub mod networkmanager;
 #[cfg(feature = "notifications")]
 pub mod notifications;
+#[cfg(feature = "power_profile")]
+pub mod power_profile;
 
 #[cfg(feature = "script")]
 pub mod script;
@@ -63,7 +73,7 @@ pub mod volume;
 #[cfg(feature = "workspaces")]
 pub mod workspaces;
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ModuleLocation {
     Left,
     Center,
@@ -107,6 +117,8 @@ where
     pub tx: mpsc::Sender<ModuleUpdateEvent<TSend>>,
     pub update_tx: broadcast::Sender<TSend>,
     pub controller_tx: mpsc::Sender<TReceive>,
+    /// The module's tasks, stopped when it is removed.
+    pub tasks: TaskScope,
 
     // TODO: Don't like this - need some serious refactoring to deal with it
     //  This is a hack to be able to pass data from module -> popup creation
@@ -142,6 +154,18 @@ where
     pub fn subscribe(&self) -> broadcast::Receiver<TSend> {
         self.update_tx.subscribe()
     }
+
+    /// Spawns a task on the Tokio runtime,
+    /// which is stopped when the module is removed.
+    ///
+    /// This is a shorthand for `context.tasks.spawn`.
+    pub fn spawn<F>(&self, f: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.tasks.spawn(f)
+    }
 }
 
 pub struct ModuleParts<W: IsA<Widget>> {
@@ -179,6 +203,51 @@ impl<W: IsA<Widget>> ModuleParts<W> {
             }
         }
     }
+
+    /// Adds or removes each class as its condition changes.
+    pub fn setup_dynamic_classes(
+        &self,
+        classes_if: HashMap<String, DynamicBool>,
+        tasks: &TaskScope,
+    ) {
+        for (class, condition) in classes_if {
+            let widget = self.widget.clone().upcast::<Widget>();
+            let popup = self.popup.as_ref().map(|popup| popup.container.clone());
+
+            condition.subscribe(tasks, move |active| {
+                if active {
+                    widget.add_class(&class);
+                    if let Some(ref popup) = popup {
+                        popup.add_class(&class);
+                    }
+                } else {
+                    widget.remove_class(&class);
+                    if let Some(ref popup) = popup {
+                        popup.remove_class(&class);
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// A reference to a module which has been added to a container.
+#[derive(Debug, Clone)]
+pub struct ModuleRef {
+    /// The module's unique ID.
+    /// This is also used as its popup content key.
+    pub id: usize,
+    /// The top-level container wrapping the module widget.
+    pub widget: EventBox,
+    /// The tasks spawned by the module, stopped when it is removed.
+    pub tasks: TaskScope,
+    /// The module's configured name,
+    /// or its type name if one is not set.
+    #[cfg(feature = "ipc")]
+    pub name: String,
+    /// Handler for IPC actions addressed to this module.
+    #[cfg(feature = "ipc")]
+    pub actions: actions::ActionHandler,
 }
 
 #[derive(Debug, Clone)]
@@ -281,23 +350,64 @@ where
         None
     }
 
+    /// Parses an action sent to the module over IPC
+    /// into a message for its controller.
+    ///
+    /// Modules which support actions should override this,
+    /// returning an error for any unknown action or invalid arguments.
+    #[cfg(feature = "ipc")]
+    fn ipc_action(action: &str, _args: &[String]) -> Result<Self::ReceiveMessage>
+    where
+        Self: Sized,
+    {
+        Err(actions::unknown_action(action, &[]))
+    }
+
     fn take_common(&mut self) -> CommonConfig;
 }
 
 pub trait ModuleFactory {
+    /// Creates the module and adds it to `container`.
+    ///
+    /// Tasks spawned through the module's context are collected into its [`TaskScope`],
+    /// so they can be stopped when it is removed.
     fn create<TModule, TWidget, TSend, TRev>(
+        &self,
+        module: TModule,
+        container: &gtk::Box,
+        info: &ModuleInfo,
+    ) -> Result<ModuleRef>
+    where
+        TModule: Module<TWidget, SendMessage = TSend, ReceiveMessage = TRev>,
+        TWidget: IsA<Widget>,
+        TSend: Debug + Clone + Send + 'static,
+        TRev: 'static,
+    {
+        let tasks = TaskScope::default();
+        let module = self.build(module, container, info, &tasks);
+
+        if module.is_err() {
+            tasks.abort();
+        }
+
+        module
+    }
+
+    fn build<TModule, TWidget, TSend, TRev>(
         &self,
         mut module: TModule,
         container: &gtk::Box,
         info: &ModuleInfo,
-    ) -> Result<()>
+        tasks: &TaskScope,
+    ) -> Result<ModuleRef>
     where
         TModule: Module<TWidget, SendMessage = TSend, ReceiveMessage = TRev>,
         TWidget: IsA<Widget>,
         TSend: Debug + Clone + Send + 'static,
+        TRev: 'static,
     {
         let id = Ironbar::unique_id();
-        let common = module.take_common();
+        let mut common = module.take_common();
 
         debug!("adding module {} (id: {})", TModule::name(), id);
 
@@ -313,12 +423,17 @@ pub trait ModuleFactory {
             tx: ui_tx,
             update_tx: tx.clone(),
             controller_tx,
+            tasks: tasks.clone(),
             _update_rx: rx,
             button_id: usize::MAX, // hack :(
         };
 
         module.spawn_controller(info, &context, controller_rx)?;
 
+        #[cfg(feature = "ipc")]
+        let actions =
+            actions::ActionHandler::new(TModule::ipc_action, context.controller_tx.clone());
+
         let module_name = TModule::name();
         let instance_name = common
             .name
@@ -336,21 +451,46 @@ pub trait ModuleFactory {
                 .add_class(&format!("popup-{module_name}"));
 
             self.popup()
-                .register_content(id, instance_name, popup_content);
+                .register_content(id, instance_name.clone(), popup_content);
+        }
+
+        #[cfg(feature = "ipc")]
+        {
+            use crate::ipc::events::{self, Event};
+
+            let name = instance_name.clone();
+            glib_recv!(tx.subscribe(), _update => {
+                if events::has_subscribers() {
+                    events::emit(Event::Module {
+                        name: name.clone(),
+                        module_type: module_name.to_string(),
+                    });
+                }
+            });
         }
 
         self.setup_receiver(tx, ui_rx, module_name, id, common.disable_popup);
 
         module_parts.setup_identifiers(&common);
+        module_parts.setup_dynamic_classes(std::mem::take(&mut common.classes_if), tasks);
 
         let ev_container = wrap_widget(
             &module_parts.widget,
             common,
             info.bar_position.orientation(),
+            tasks,
         );
         container.add(&ev_container);
 
-        Ok(())
+        Ok(ModuleRef {
+            id,
+            widget: ev_container,
+            tasks: tasks.clone(),
+            #[cfg(feature = "ipc")]
+            name: instance_name,
+            #[cfg(feature = "ipc")]
+            actions,
+        })
     }
 
     fn setup_receiver<TSend>(
@@ -563,6 +703,7 @@ pub fn wrap_widget<W: IsA<Widget>>(
     widget: &W,
     common: CommonConfig,
     orientation: Orientation,
+    tasks: &TaskScope,
 ) -> EventBox {
     let transition_type = common
         .transition_type
@@ -584,7 +725,7 @@ pub fn wrap_widget<W: IsA<Widget>>(
     container.add_events(EventMask::SCROLL_MASK | EventMask::SMOOTH_SCROLL_MASK);
     container.add(&revealer);
 
-    common.install_events(&container, &revealer);
+    common.install_events(&container, &revealer, tasks);
 
     container
 }
@@ -3,7 +3,7 @@ use dirs::{audio_dir, home_dir};
 use serde::Deserialize;
 use std::path::PathBuf;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Icons {
     /// Icon to display when playing.
@@ -53,6 +53,51 @@ pub struct Icons {
     /// **Default**: `󰠃`
     #[serde(default = "default_icon_artist")]
     pub(crate) artist: String,
+
+    /// Icon to display for the shuffle button while shuffle is enabled,
+    /// and for the `{shuffle}` token.
+    ///
+    /// **Default**: `󰒟`
+    #[serde(default = "default_icon_shuffle")]
+    pub(crate) shuffle: String,
+
+    /// Icon to display for the shuffle button while shuffle is disabled.
+    ///
+    /// **Default**: `󰒞`
+    #[serde(default = "default_icon_shuffle_off")]
+    pub(crate) shuffle_off: String,
+
+    /// Icon to display for the loop button while looping is disabled.
+    ///
+    /// **Default**: `󰑗`
+    #[serde(default = "default_icon_loop_none")]
+    pub(crate) loop_none: String,
+
+    /// Icon to display for the loop button while looping the playlist,
+    /// and for the `{loop}` token.
+    ///
+    /// **Default**: `󰑖`
+    #[serde(default = "default_icon_loop_playlist")]
+    pub(crate) loop_playlist: String,
+
+    /// Icon to display for the loop button while looping the current track,
+    /// and for the `{loop}` token.
+    ///
+    /// **Default**: `󰑘`
+    #[serde(default = "default_icon_loop_track")]
+    pub(crate) loop_track: String,
+
+    /// Icon to display for the button which moves a queued track to play next.
+    ///
+    /// **Default**: `󰐑`
+    #[serde(default = "default_icon_queue_next")]
+    pub(crate) queue_next: String,
+
+    /// Icon to display for the button which removes a track from the queue.
+    ///
+    /// **Default**: `󰅖`
+    #[serde(default = "default_icon_queue_remove")]
+    pub(crate) queue_remove: String,
 }
 
 impl Default for Icons {
@@ -66,11 +111,18 @@ impl Default for Icons {
             track: default_icon_track(),
             album: default_icon_album(),
             artist: default_icon_artist(),
+            shuffle: default_icon_shuffle(),
+            shuffle_off: default_icon_shuffle_off(),
+            loop_none: default_icon_loop_none(),
+            loop_playlist: default_icon_loop_playlist(),
+            loop_track: default_icon_loop_track(),
+            queue_next: default_icon_queue_next(),
+            queue_remove: default_icon_queue_remove(),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
 #[serde(rename_all = "snake_case")]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum PlayerType {
@@ -78,6 +130,8 @@ pub enum PlayerType {
     Mpd,
     #[cfg(feature = "music+mpris")]
     Mpris,
+    #[cfg(feature = "music+script")]
+    Script,
 }
 
 impl Default for PlayerType {
@@ -87,6 +141,8 @@ impl Default for PlayerType {
                 Self::Mpris
             } else if #[cfg(feature = "music+mpd")] {
                 Self::Mpd
+            } else if #[cfg(feature = "music+script")] {
+                Self::Script
             } else {
                 compile_error!("No player type feature enabled")
             }
@@ -94,7 +150,7 @@ impl Default for PlayerType {
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MusicModule {
     /// Type of player to connect to
@@ -122,6 +178,24 @@ pub struct MusicModule {
     #[serde(default = "crate::config::default_true")]
     pub(crate) show_status_icon: bool,
 
+    /// Whether to show the current line of the track's lyrics
+    /// on the bar.
+    ///
+    /// Lyrics are read from an `.lrc` file alongside the track,
+    /// or from the track's tags.
+    /// Only timestamped (LRC format) lyrics can be shown on the bar.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub(crate) show_lyrics: bool,
+
+    /// Whether to show the track's lyrics inside the popup,
+    /// scrolling along with the track where they are timestamped.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub(crate) show_popup_lyrics: bool,
+
     /// Size to render the icons at, in pixels (image icons only).
     ///
     /// **Default** `32`
@@ -151,6 +225,65 @@ pub struct MusicModule {
     #[serde(default = "default_music_dir")]
     pub(crate) music_dir: PathBuf,
 
+    /// *[MPD Only]*
+    /// Whether to show the upcoming tracks in the queue inside the popup.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    pub(crate) show_queue: bool,
+
+    /// *[MPD Only]*
+    /// The maximum number of upcoming tracks to show inside the popup.
+    ///
+    /// **Default**: `50`
+    #[serde(default = "default_max_queue_items")]
+    pub(crate) max_queue_items: usize,
+
+    // -- MPRIS --
+    /// *[MPRIS Only]*
+    /// Players to prefer when several are running, highest priority first.
+    /// Players can be matched by their name (such as `Spotify`)
+    /// or their D-Bus name (such as `spotify` or `firefox`), ignoring case.
+    ///
+    /// A player which starts playing takes over from the current player
+    /// unless the current player is still playing and has a higher priority.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    pub(crate) player_priority: Vec<String>,
+
+    /// *[MPRIS Only]*
+    /// If not empty, only the listed players are shown and controlled.
+    /// Players are matched in the same way as `player_priority`.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    pub(crate) player_allowlist: Vec<String>,
+
+    /// *[MPRIS Only]*
+    /// Players which are never shown or controlled.
+    /// Players are matched in the same way as `player_priority`.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    pub(crate) player_denylist: Vec<String>,
+
+    /// *[MPRIS Only]*
+    /// Whether scrolling over the widget cycles between the running players.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub(crate) scroll_players: bool,
+
+    // -- Script --
+    /// *[Script Only]*
+    /// Commands used to watch and control the player.
+    ///
+    /// See [scripted players](#scripted-players).
+    #[cfg(feature = "music+script")]
+    #[serde(default)]
+    pub(crate) scripts: crate::clients::music::script::Commands,
+
     // -- Common --
     /// See [truncate options](module-level-options#truncate-mode).
     ///
@@ -206,10 +339,42 @@ fn default_icon_artist() -> String {
     String::from("󰠃")
 }
 
+fn default_icon_shuffle() -> String {
+    String::from("󰒟")
+}
+
+fn default_icon_shuffle_off() -> String {
+    String::from("󰒞")
+}
+
+fn default_icon_loop_none() -> String {
+    String::from("󰑗")
+}
+
+fn default_icon_loop_playlist() -> String {
+    String::from("󰑖")
+}
+
+fn default_icon_loop_track() -> String {
+    String::from("󰑘")
+}
+
+fn default_icon_queue_next() -> String {
+    String::from("󰐑")
+}
+
+fn default_icon_queue_remove() -> String {
+    String::from("󰅖")
+}
+
 fn default_music_dir() -> PathBuf {
     audio_dir().unwrap_or_else(|| home_dir().map(|dir| dir.join("Music")).unwrap_or_default())
 }
 
+const fn default_max_queue_items() -> usize {
+    50
+}
+
 const fn default_icon_size() -> i32 {
     24
 }
@@ -0,0 +1,215 @@
+use crate::clients::music::Track;
+use crate::spawn_blocking;
+use std::fs;
+use std::time::Duration;
+use tracing::debug;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    /// Time into the track the line starts at.
+    /// Always zero for unsynced lyrics.
+    pub time: Duration,
+    pub text: String,
+}
+
+/// The lyrics of a track, which may or may not be timestamped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lyrics {
+    lines: Vec<Line>,
+    synced: bool,
+}
+
+impl Lyrics {
+    /// Loads the lyrics for `track`.
+    ///
+    /// An `.lrc` file alongside the track is preferred,
+    /// falling back to lyrics embedded in the track's tags.
+    pub async fn load(track: &Track) -> Option<Self> {
+        let from_file = match track.file_path.as_ref() {
+            Some(path) => {
+                let path = path.with_extension("lrc");
+
+                spawn_blocking(move || {
+                    let text = fs::read_to_string(&path).ok()?;
+                    debug!("Loaded lyrics from '{}'", path.display());
+                    Some(text)
+                })
+                .await
+                .ok()
+                .flatten()
+            }
+            None => None,
+        };
+
+        from_file
+            .or_else(|| track.lyrics.clone())
+            .and_then(|text| Self::parse(&text))
+    }
+
+    /// Parses lyrics in the LRC format.
+    /// If there are no timestamps, each line of `text` is treated as an unsynced line.
+    ///
+    /// Returns `None` if there are no lines.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut synced = vec![];
+        let mut plain = vec![];
+        let mut offset_ms = 0;
+
+        for line in text.lines().map(str::trim) {
+            let mut rest = line;
+            let mut times = vec![];
+            let mut is_tag = false;
+
+            while let Some((tag, remainder)) =
+                rest.strip_prefix('[').and_then(|r| r.split_once(']'))
+            {
+                if let Some(time) = parse_timestamp(tag) {
+                    times.push(time);
+                } else if let Some(offset) = tag.strip_prefix("offset:") {
+                    offset_ms = offset.trim().parse().unwrap_or_default();
+                    is_tag = true;
+                } else if tag.contains(':') {
+                    // metadata such as `[ar:Artist]`
+                    is_tag = true;
+                } else {
+                    // not a tag, such as `[Chorus]`
+                    break;
+                }
+
+                rest = remainder;
+            }
+
+            if !times.is_empty() {
+                let text = rest.trim();
+                synced.extend(times.into_iter().map(|time| Line {
+                    time,
+                    text: text.to_string(),
+                }));
+            } else if !is_tag && !line.is_empty() {
+                plain.push(Line {
+                    time: Duration::ZERO,
+                    text: line.to_string(),
+                });
+            }
+        }
+
+        if synced.is_empty() {
+            return (!plain.is_empty()).then_some(Self {
+                lines: plain,
+                synced: false,
+            });
+        }
+
+        // a positive offset shows lyrics sooner
+        for line in &mut synced {
+            let time = line.time.as_millis() as i64 - offset_ms;
+            line.time = Duration::from_millis(time.max(0) as u64);
+        }
+
+        synced.sort_by_key(|line| line.time);
+
+        Some(Self {
+            lines: synced,
+            synced: true,
+        })
+    }
+
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    /// Gets the index of the line being sung `elapsed` into the track,
+    /// or `None` if the lyrics are not synced or have not started yet.
+    pub fn line_index(&self, elapsed: Duration) -> Option<usize> {
+        if !self.synced {
+            return None;
+        }
+
+        self.lines
+            .partition_point(|line| line.time <= elapsed)
+            .checked_sub(1)
+    }
+}
+
+/// Parses an LRC `mm:ss.xx` timestamp.
+fn parse_timestamp(tag: &str) -> Option<Duration> {
+    let (minutes, seconds) = tag.split_once(':')?;
+
+    let minutes = minutes.parse::<u64>().ok()?;
+    let seconds = seconds.parse::<f64>().ok()?;
+
+    if !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+
+    Some(Duration::from_secs(minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lyrics: &Lyrics) -> Vec<&str> {
+        lyrics
+            .lines()
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_synced() {
+        let lyrics = Lyrics::parse(
+            "[ar:Artist]\n[ti:Title]\n[00:01.50]First\n[00:04.00]Second\n[00:06.25]\n",
+        )
+        .expect("to parse");
+
+        assert_eq!(texts(&lyrics), vec!["First", "Second", ""]);
+        assert_eq!(lyrics.lines()[0].time, Duration::from_millis(1500));
+        assert_eq!(lyrics.lines()[2].time, Duration::from_millis(6250));
+    }
+
+    #[test]
+    fn test_parse_repeated_lines() {
+        let lyrics =
+            Lyrics::parse("[00:10.00][01:10.00]Chorus\n[00:20.00]Verse").expect("to parse");
+
+        assert_eq!(texts(&lyrics), vec!["Chorus", "Verse", "Chorus"]);
+        assert_eq!(lyrics.lines()[2].time, Duration::from_secs(70));
+    }
+
+    #[test]
+    fn test_parse_offset() {
+        let lyrics =
+            Lyrics::parse("[offset:+500]\n[00:00.20]First\n[00:02.00]Second").expect("to parse");
+
+        assert_eq!(lyrics.lines()[0].time, Duration::ZERO);
+        assert_eq!(lyrics.lines()[1].time, Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_parse_plain() {
+        let lyrics = Lyrics::parse("[Chorus]\nFirst line\n\nSecond line").expect("to parse");
+
+        assert_eq!(
+            texts(&lyrics),
+            vec!["[Chorus]", "First line", "Second line"]
+        );
+        assert_eq!(lyrics.line_index(Duration::from_secs(10)), None);
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        assert_eq!(Lyrics::parse("[ar:Artist]\n\n"), None);
+    }
+
+    #[test]
+    fn test_line_index() {
+        let lyrics = Lyrics::parse("[00:01.00]First\n[00:04.00]Second").expect("to parse");
+
+        assert_eq!(lyrics.line_index(Duration::ZERO), None);
+        assert_eq!(lyrics.line_index(Duration::from_secs(1)), Some(0));
+        assert_eq!(lyrics.line_index(Duration::from_secs(3)), Some(0));
+        assert_eq!(lyrics.line_index(Duration::from_secs(60)), Some(1));
+    }
+}
@@ -1,20 +1,27 @@
-use std::cell::RefMut;
-use std::path::PathBuf;
+use std::cell::{Cell, RefMut};
+use std::rc::Rc;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 use color_eyre::Result;
 use glib::{Propagation, PropertySet};
+#[cfg(feature = "music+mpris")]
+use gtk::gdk::{EventMask, ScrollDirection};
+use gtk::pango::EllipsizeMode;
 use gtk::prelude::*;
-use gtk::{Button, IconTheme, Label, Orientation, Scale};
+use gtk::{
+    Button, ComboBoxText, IconTheme, Justification, Label, Orientation, PolicyType, Scale,
+    ScrolledWindow,
+};
 use regex::Regex;
 use tokio::sync::{broadcast, mpsc};
 use tracing::error;
 
 use crate::clients::Clients;
 use crate::clients::music::{
-    self, MusicClient, PlayerState, PlayerUpdate, ProgressTick, Status, Track,
+    self, LoopStatus, MusicClient, PlayerInfo, PlayerState, PlayerUpdate, ProgressTick, QueueItem,
+    Status, Track,
 };
 use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
 use crate::image::{IconButton, IconLabel, ImageProvider};
@@ -22,12 +29,14 @@ use crate::modules::PopupButton;
 use crate::modules::{
     Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, WidgetContext,
 };
-use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use crate::{glib_recv, module_impl, send_async, try_send};
 
 pub use self::config::MusicModule;
-use self::config::PlayerType;
+use self::config::{Icons, PlayerType};
+use self::lyrics::Lyrics;
 
 mod config;
+mod lyrics;
 
 #[derive(Debug)]
 pub enum PlayerCommand {
@@ -37,6 +46,19 @@ pub enum PlayerCommand {
     Next,
     Volume(u8),
     Seek(Duration),
+    Shuffle(bool),
+    Loop(LoopStatus),
+    /// Plays the queued track with this ID.
+    PlayAt(u64),
+    /// Removes the queued track with this ID.
+    RemoveFromQueue(u64),
+    /// Moves the queued track with this ID to play next.
+    MoveToNext(u64),
+    LoadPlaylist(String),
+    /// Switches to controlling the player with this ID or name.
+    SelectPlayer(String),
+    /// Switches to the next (positive) or previous (negative) player.
+    CyclePlayer(i32),
 }
 
 /// Formats a duration given in seconds
@@ -60,6 +82,17 @@ fn get_tokens(re: &Regex, format_string: &str) -> Vec<String> {
 pub enum ControllerEvent {
     Update(Option<SongUpdate>),
     UpdateProgress(ProgressTick),
+    Queue(Vec<QueueItem>),
+    Playlists(Vec<String>),
+    /// The lyrics of a new track.
+    Lyrics(Option<Lyrics>),
+    /// The index of the lyrics line currently being sung.
+    LyricsLine(Option<usize>),
+    Players {
+        players: Vec<PlayerInfo>,
+        /// The ID of the player being controlled.
+        current: Option<String>,
+    },
 }
 
 #[derive(Clone, Debug)]
@@ -69,28 +102,87 @@ pub struct SongUpdate {
     display_string: String,
 }
 
-fn get_client(
-    mut clients: RefMut<'_, Clients>,
-    player_type: PlayerType,
-    host: String,
-    music_dir: PathBuf,
-) -> Arc<dyn MusicClient> {
-    let client_type = match player_type {
+fn get_client(mut clients: RefMut<'_, Clients>, module: &MusicModule) -> Arc<dyn MusicClient> {
+    let client_type = match module.player_type {
         #[cfg(feature = "music+mpd")]
-        PlayerType::Mpd => music::ClientType::Mpd { host, music_dir },
+        PlayerType::Mpd => music::ClientType::Mpd {
+            host: module.host.clone(),
+            music_dir: module.music_dir.clone(),
+        },
         #[cfg(feature = "music+mpris")]
-        PlayerType::Mpris => music::ClientType::Mpris,
+        PlayerType::Mpris => music::ClientType::Mpris(music::mpris::PlayerSelection {
+            priority: module.player_priority.clone(),
+            allowlist: module.player_allowlist.clone(),
+            denylist: module.player_denylist.clone(),
+        }),
+        #[cfg(feature = "music+script")]
+        PlayerType::Script => music::ClientType::Script(module.scripts.clone()),
     };
 
     clients.music(client_type)
 }
 
+/// Gets the ID of the player `offset` places away from the player with ID `current` in `players`,
+/// wrapping around at either end.
+fn cycle_player(players: &[PlayerInfo], current: Option<&str>, offset: i32) -> Option<String> {
+    if players.is_empty() {
+        return None;
+    }
+
+    let len = players.len() as i32;
+    let index = current
+        .and_then(|current| players.iter().position(|player| player.id == current))
+        .map_or(0, |index| (index as i32 + offset).rem_euclid(len));
+
+    players.get(index as usize).map(|player| player.id.clone())
+}
+
 impl Module<Button> for MusicModule {
     type SendMessage = ControllerEvent;
     type ReceiveMessage = PlayerCommand;
 
     module_impl!("music");
 
+    #[cfg(feature = "ipc")]
+    fn ipc_action(action: &str, args: &[String]) -> Result<Self::ReceiveMessage> {
+        use crate::modules::actions::{parse_arg, unknown_action};
+
+        match action {
+            "previous" | "prev" => Ok(PlayerCommand::Previous),
+            "play" => Ok(PlayerCommand::Play),
+            "pause" => Ok(PlayerCommand::Pause),
+            "next" => Ok(PlayerCommand::Next),
+            "volume" => parse_arg(args, 0, "percent").map(PlayerCommand::Volume),
+            "seek" => parse_arg(args, 0, "seconds")
+                .map(|secs| PlayerCommand::Seek(Duration::from_secs(secs))),
+            "shuffle" => parse_arg(args, 0, "enabled").map(PlayerCommand::Shuffle),
+            "loop" => parse_arg(args, 0, "mode").map(PlayerCommand::Loop),
+            "play-at" => parse_arg(args, 0, "id").map(PlayerCommand::PlayAt),
+            "play-next" => parse_arg(args, 0, "id").map(PlayerCommand::MoveToNext),
+            "remove" => parse_arg(args, 0, "id").map(PlayerCommand::RemoveFromQueue),
+            "load-playlist" => parse_arg(args, 0, "name").map(PlayerCommand::LoadPlaylist),
+            "player" => parse_arg(args, 0, "name").map(PlayerCommand::SelectPlayer),
+            _ => Err(unknown_action(
+                action,
+                &[
+                    "previous",
+                    "play",
+                    "pause",
+                    "next",
+                    "volume",
+                    "seek",
+                    "shuffle",
+                    "loop",
+                    "play-at",
+                    "play-next",
+                    "remove",
+                    "load-playlist",
+                    "player",
+                ],
+            )),
+        }
+    }
+
     fn spawn_controller(
         &self,
         _info: &ModuleInfo,
@@ -98,32 +190,57 @@ impl Module<Button> for MusicModule {
         mut rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let format = self.format.clone();
+        let icons = self.icons.clone();
+        let load_lyrics = self.show_lyrics || self.show_popup_lyrics;
 
         let re = Regex::new(r"\{([\w-]+)}")?;
         let tokens = get_tokens(&re, self.format.as_str());
 
-        let client = get_client(
-            context.ironbar.clients.borrow_mut(),
-            self.player_type,
-            self.host.clone(),
-            self.music_dir.clone(),
-        );
+        let client = get_client(context.ironbar.clients.borrow_mut(), self);
+
+        if load_lyrics {
+            client.enable_lyrics();
+        }
 
         // receive player updates
         {
             let tx = context.tx.clone();
             let client = client.clone();
 
-            spawn(async move {
+            context.spawn(async move {
                 loop {
                     let mut rx = client.subscribe_change();
 
+                    let mut lyrics = None;
+                    // file path and title of the track the lyrics are for
+                    let mut lyrics_track = None;
+                    let mut lyrics_line = None;
+
                     while let Ok(update) = rx.recv().await {
                         match update {
                             PlayerUpdate::Update(track, status) => match *track {
                                 Some(track) => {
-                                    let display_string =
-                                        replace_tokens(format.as_str(), &tokens, &track);
+                                    let track_key = (track.file_path.clone(), track.title.clone());
+                                    if load_lyrics && lyrics_track.as_ref() != Some(&track_key) {
+                                        lyrics = Lyrics::load(&track).await;
+                                        lyrics_track = Some(track_key);
+                                        lyrics_line = None;
+
+                                        send_async!(
+                                            tx,
+                                            ModuleUpdateEvent::Update(ControllerEvent::Lyrics(
+                                                lyrics.clone()
+                                            ))
+                                        );
+                                    }
+
+                                    let display_string = replace_tokens(
+                                        format.as_str(),
+                                        &tokens,
+                                        &track,
+                                        &status,
+                                        &icons,
+                                    );
 
                                     let update = SongUpdate {
                                         song: track,
@@ -138,16 +255,60 @@ impl Module<Button> for MusicModule {
                                         )))
                                     );
                                 }
-                                None => send_async!(
-                                    tx,
-                                    ModuleUpdateEvent::Update(ControllerEvent::Update(None))
-                                ),
+                                None => {
+                                    if lyrics_track.take().is_some() {
+                                        lyrics = None;
+                                        send_async!(
+                                            tx,
+                                            ModuleUpdateEvent::Update(ControllerEvent::Lyrics(
+                                                None
+                                            ))
+                                        );
+                                    }
+
+                                    send_async!(
+                                        tx,
+                                        ModuleUpdateEvent::Update(ControllerEvent::Update(None))
+                                    );
+                                }
                             },
-                            PlayerUpdate::ProgressTick(progress_tick) => send_async!(
+                            PlayerUpdate::ProgressTick(progress_tick) => {
+                                if let (Some(lyrics), Some(elapsed)) =
+                                    (&lyrics, progress_tick.elapsed)
+                                {
+                                    let line = lyrics.line_index(elapsed);
+                                    if line != lyrics_line {
+                                        lyrics_line = line;
+                                        send_async!(
+                                            tx,
+                                            ModuleUpdateEvent::Update(ControllerEvent::LyricsLine(
+                                                line
+                                            ))
+                                        );
+                                    }
+                                }
+
+                                send_async!(
+                                    tx,
+                                    ModuleUpdateEvent::Update(ControllerEvent::UpdateProgress(
+                                        progress_tick
+                                    ))
+                                );
+                            }
+                            PlayerUpdate::Queue(queue) => send_async!(
+                                tx,
+                                ModuleUpdateEvent::Update(ControllerEvent::Queue(queue))
+                            ),
+                            PlayerUpdate::Playlists(playlists) => send_async!(
+                                tx,
+                                ModuleUpdateEvent::Update(ControllerEvent::Playlists(playlists))
+                            ),
+                            PlayerUpdate::Players { players, current } => send_async!(
                                 tx,
-                                ModuleUpdateEvent::Update(ControllerEvent::UpdateProgress(
-                                    progress_tick
-                                ))
+                                ModuleUpdateEvent::Update(ControllerEvent::Players {
+                                    players,
+                                    current
+                                })
                             ),
                         }
                     }
@@ -157,7 +318,7 @@ impl Module<Button> for MusicModule {
 
         // listen to ui events
         {
-            spawn(async move {
+            context.spawn(async move {
                 while let Some(event) = rx.recv().await {
                     let res = match event {
                         PlayerCommand::Previous => client.prev(),
@@ -166,6 +327,22 @@ impl Module<Button> for MusicModule {
                         PlayerCommand::Next => client.next(),
                         PlayerCommand::Volume(vol) => client.set_volume_percent(vol),
                         PlayerCommand::Seek(duration) => client.seek(duration),
+                        PlayerCommand::Shuffle(shuffle) => client.set_shuffle(shuffle),
+                        PlayerCommand::Loop(loop_status) => client.set_loop_status(loop_status),
+                        PlayerCommand::PlayAt(id) => client.play_at(id),
+                        PlayerCommand::RemoveFromQueue(id) => client.remove_from_queue(id),
+                        PlayerCommand::MoveToNext(id) => client.move_to_next(id),
+                        PlayerCommand::LoadPlaylist(name) => client.load_playlist(&name),
+                        PlayerCommand::SelectPlayer(player) => client.set_player(&player),
+                        PlayerCommand::CyclePlayer(offset) => {
+                            let current = client.current_player();
+                            match cycle_player(&client.players(), current.as_deref(), offset) {
+                                Some(player) if Some(&player) != current.as_ref() => {
+                                    client.set_player(&player)
+                                }
+                                _ => Ok(()),
+                            }
+                        }
                     };
 
                     if let Err(err) = res {
@@ -204,14 +381,26 @@ impl Module<Button> for MusicModule {
             .justify(self.layout.justify.into())
             .build();
 
+        let lyrics_label = Label::builder()
+            .angle(self.layout.angle(info))
+            .justify(self.layout.justify.into())
+            .build();
+        lyrics_label.add_class("lyrics");
+        lyrics_label.set_no_show_all(true);
+
         if let Some(truncate) = self.truncate {
             label.truncate(truncate);
+            lyrics_label.truncate(truncate);
         }
 
         button_contents.add(&*icon_pause);
         button_contents.add(&*icon_play);
         button_contents.add(&label);
 
+        if self.show_lyrics {
+            button_contents.add(&lyrics_label);
+        }
+
         {
             let tx = context.tx.clone();
 
@@ -220,13 +409,61 @@ impl Module<Button> for MusicModule {
             });
         }
 
+        // only MPRIS clients can have several players to switch between
+        #[cfg(feature = "music+mpris")]
+        if self.scroll_players && matches!(self.player_type, PlayerType::Mpris) {
+            let tx = context.controller_tx.clone();
+
+            // smooth scrolling is left disabled,
+            // so that each notch of the wheel switches a single player.
+            button.add_events(EventMask::SCROLL_MASK);
+            button.connect_scroll_event(move |_, event| {
+                let offset = match event.direction() {
+                    ScrollDirection::Up => -1,
+                    ScrollDirection::Down => 1,
+                    _ => return Propagation::Proceed,
+                };
+
+                try_send!(tx, PlayerCommand::CyclePlayer(offset));
+                Propagation::Proceed
+            });
+        }
+
         {
             let button = button.clone();
 
             let tx = context.tx.clone();
             let rx = context.subscribe();
 
+            let mut lyrics: Option<Lyrics> = None;
+
             glib_recv!(rx, event => {
+                match event {
+                    ControllerEvent::Lyrics(new_lyrics) => {
+                        lyrics = new_lyrics;
+                        lyrics_label.hide();
+                        continue;
+                    }
+                    ControllerEvent::LyricsLine(index) => {
+                        let text = index
+                            .zip(lyrics.as_ref())
+                            .and_then(|(index, lyrics)| lyrics.lines().get(index))
+                            .map(|line| line.text.as_str())
+                            .filter(|text| !text.is_empty());
+
+                        match text {
+                            Some(text) => {
+                                lyrics_label.set_label(text);
+                                lyrics_label.show();
+                            }
+                            None => lyrics_label.hide(),
+                        }
+
+                        continue;
+                    }
+                    _ => {}
+                }
+
                 let ControllerEvent::Update(mut event) = event else {
                     continue;
                 };
@@ -280,6 +517,11 @@ impl Module<Button> for MusicModule {
         let icon_theme = info.icon_theme;
 
         let container = gtk::Box::new(Orientation::Vertical, 10);
+
+        let players_box = gtk::Box::new(Orientation::Horizontal, 5);
+        players_box.add_class("players");
+        container.add(&players_box);
+
         let main_container = gtk::Box::new(Orientation::Horizontal, 10);
 
         let album_image = gtk::Image::builder()
@@ -323,6 +565,19 @@ impl Module<Button> for MusicModule {
         controls_box.add(&*btn_pause);
         controls_box.add(&*btn_next);
 
+        let btn_shuffle = Button::new();
+        btn_shuffle.add_class("btn-shuffle");
+        let icon_shuffle = IconLabel::new(&icons.shuffle_off, icon_theme, self.icon_size);
+        btn_shuffle.add(&*icon_shuffle);
+
+        let btn_loop = Button::new();
+        btn_loop.add_class("btn-loop");
+        let icon_loop = IconLabel::new(&icons.loop_none, icon_theme, self.icon_size);
+        btn_loop.add(&*icon_loop);
+
+        controls_box.add(&btn_shuffle);
+        controls_box.add(&btn_loop);
+
         info_box.add(&controls_box);
 
         let volume_box = gtk::Box::new(Orientation::Vertical, 5);
@@ -363,6 +618,29 @@ impl Module<Button> for MusicModule {
             try_send!(tx_next, PlayerCommand::Next);
         });
 
+        // latest status, used to work out what the shuffle and loop buttons switch to
+        let status = Rc::new(Cell::new(Status::default()));
+
+        {
+            let tx = tx.clone();
+            let status = status.clone();
+            btn_shuffle.connect_clicked(move |_| {
+                if let Some(shuffle) = status.get().shuffle {
+                    try_send!(tx, PlayerCommand::Shuffle(!shuffle));
+                }
+            });
+        }
+
+        {
+            let tx = tx.clone();
+            let status = status.clone();
+            btn_loop.connect_clicked(move |_| {
+                if let Some(loop_status) = status.get().loop_status {
+                    try_send!(tx, PlayerCommand::Loop(next_loop_status(loop_status)));
+                }
+            });
+        }
+
         let tx_vol = tx.clone();
         volume_slider.connect_change_value(move |_, _, val| {
             try_send!(tx_vol, PlayerCommand::Volume(val as u8));
@@ -386,6 +664,31 @@ impl Module<Button> for MusicModule {
         progress_box.add(&progress_label);
         container.add(&progress_box);
 
+        let lyrics_box = gtk::Box::new(Orientation::Vertical, 5);
+
+        let lyrics_window = ScrolledWindow::builder()
+            .hscrollbar_policy(PolicyType::Never)
+            .min_content_height(150)
+            .build();
+        lyrics_window.add_class("lyrics");
+        lyrics_window.add(&lyrics_box);
+
+        let show_popup_lyrics = self.show_popup_lyrics;
+        if show_popup_lyrics {
+            container.add(&lyrics_window);
+        }
+
+        let queue = QueueUi::new(
+            &icons,
+            icon_theme,
+            self.icon_size,
+            self.max_queue_items,
+            tx.clone(),
+        );
+        if self.show_queue {
+            container.add(&queue.container);
+        }
+
         let drag_lock = Arc::new(AtomicBool::new(false));
         {
             let drag_lock = drag_lock.clone();
@@ -396,6 +699,7 @@ impl Module<Button> for MusicModule {
         }
 
         {
+            let tx = tx.clone();
             let drag_lock = drag_lock.clone();
             progress.connect_button_release_event(move |scale, _| {
                 let value = scale.value();
@@ -407,12 +711,22 @@ impl Module<Button> for MusicModule {
         }
 
         container.show_all();
+        players_box.hide();
+        lyrics_window.hide();
+        queue.update_visibility();
 
         {
             let icon_theme = icon_theme.clone();
             let image_size = self.cover_image_size;
 
             let mut prev_cover = None;
+
+            let mut queue_items: Vec<QueueItem> = vec![];
+            let mut queue_start = None;
+
+            let mut lyrics_lines: Vec<Label> = vec![];
+            let mut active_line: Option<usize> = None;
+
             glib_recv!(rx, event =>  {
                 match event {
                     ControllerEvent::Update(Some(update)) => {
@@ -436,6 +750,17 @@ impl Module<Button> for MusicModule {
                             }
                         }
 
+                        // the current track is not upcoming unless it is yet to start
+                        let start = match update.status.state {
+                            PlayerState::Stopped => update.status.playlist_position,
+                            _ => update.status.playlist_position + 1,
+                        };
+
+                        if queue_start != Some(start) {
+                            queue_start = Some(start);
+                            queue.set_items(&queue_items, start);
+                        }
+
                         update_popup_metadata_label(update.song.title, &title_label);
                         update_popup_metadata_label(update.song.album, &album_label);
                         update_popup_metadata_label(update.song.artist, &artist_label);
@@ -462,13 +787,41 @@ impl Module<Button> for MusicModule {
                             }
                         }
 
-                        let enable_prev = update.status.playlist_position > 0;
+                        btn_prev.set_sensitive(update.status.can_go_previous);
+                        btn_next.set_sensitive(update.status.can_go_next);
 
-                        let enable_next =
-                            update.status.playlist_position < update.status.playlist_length;
+                        status.set(update.status);
 
-                        btn_prev.set_sensitive(enable_prev);
-                        btn_next.set_sensitive(enable_next);
+                        if let Some(shuffle) = update.status.shuffle {
+                            if shuffle {
+                                icon_shuffle.set_label(Some(&icons.shuffle));
+                                btn_shuffle.add_class("active");
+                            } else {
+                                icon_shuffle.set_label(Some(&icons.shuffle_off));
+                                btn_shuffle.remove_class("active");
+                            }
+                            btn_shuffle.show();
+                        } else {
+                            btn_shuffle.hide();
+                        }
+
+                        if let Some(loop_status) = update.status.loop_status {
+                            let icon = match loop_status {
+                                LoopStatus::None => &icons.loop_none,
+                                LoopStatus::Playlist => &icons.loop_playlist,
+                                LoopStatus::Track => &icons.loop_track,
+                            };
+                            icon_loop.set_label(Some(icon));
+
+                            if loop_status == LoopStatus::None {
+                                btn_loop.remove_class("active");
+                            } else {
+                                btn_loop.add_class("active");
+                            }
+                            btn_loop.show();
+                        } else {
+                            btn_loop.hide();
+                        }
 
                         if let Some(volume) = update.status.volume_percent {
                             volume_slider.set_value(f64::from(volume));
@@ -477,6 +830,77 @@ impl Module<Button> for MusicModule {
                             volume_box.hide();
                         }
                     }
+                    ControllerEvent::Players { players, current } => {
+                        for child in players_box.children() {
+                            players_box.remove(&child);
+                        }
+
+                        for player in players {
+                            let button = Button::with_label(&player.name);
+                            button.add_class("player");
+
+                            if Some(&player.id) == current.as_ref() {
+                                button.add_class("active");
+                            }
+
+                            let tx = tx.clone();
+                            button.connect_clicked(move |_| {
+                                try_send!(tx, PlayerCommand::SelectPlayer(player.id.clone()));
+                            });
+
+                            players_box.add(&button);
+                        }
+
+                        // a switcher is only useful with something to switch to
+                        if players_box.children().len() > 1 {
+                            players_box.show_all();
+                        } else {
+                            players_box.hide();
+                        }
+                    }
+                    ControllerEvent::Queue(items) => {
+                        queue_items = items;
+                        queue.set_items(&queue_items, queue_start.unwrap_or_default());
+                    }
+                    ControllerEvent::Playlists(playlists) => queue.set_playlists(&playlists),
+                    ControllerEvent::Lyrics(lyrics) => {
+                        for child in lyrics_box.children() {
+                            lyrics_box.remove(&child);
+                        }
+
+                        lyrics_lines.clear();
+                        active_line = None;
+
+                        match lyrics {
+                            Some(lyrics) if show_popup_lyrics => {
+                                for line in lyrics.lines() {
+                                    let label = Label::new(Some(&line.text));
+                                    label.add_class("line");
+                                    label.set_line_wrap(true);
+                                    label.set_justify(Justification::Center);
+
+                                    lyrics_box.add(&label);
+                                    lyrics_lines.push(label);
+                                }
+
+                                lyrics_window.vadjustment().set_value(0.0);
+                                lyrics_window.show_all();
+                            }
+                            _ => lyrics_window.hide(),
+                        }
+                    }
+                    ControllerEvent::LyricsLine(index) => {
+                        if let Some(label) = active_line.and_then(|index| lyrics_lines.get(index)) {
+                            label.remove_class("active");
+                        }
+
+                        active_line = index;
+
+                        if let Some(label) = index.and_then(|index| lyrics_lines.get(index)) {
+                            label.add_class("active");
+                            scroll_to_line(&lyrics_window, label);
+                        }
+                    }
                     ControllerEvent::UpdateProgress(progress_tick)
                         if !drag_lock.load(Ordering::Relaxed) =>
                     {
@@ -505,6 +929,20 @@ impl Module<Button> for MusicModule {
     }
 }
 
+/// Scrolls `window` so that `line` is in the middle.
+fn scroll_to_line(window: &ScrolledWindow, line: &Label) {
+    let adjustment = window.vadjustment();
+    let allocation = line.allocation();
+
+    let value = f64::from(allocation.y()) + f64::from(allocation.height()) / 2.0
+        - adjustment.page_size() / 2.0;
+
+    adjustment.set_value(value.clamp(
+        adjustment.lower(),
+        adjustment.upper() - adjustment.page_size(),
+    ));
+}
+
 fn update_popup_metadata_label(text: Option<String>, label: &IconPrefixedLabel) {
     match text {
         Some(value) => {
@@ -517,12 +955,27 @@ fn update_popup_metadata_label(text: Option<String>, label: &IconPrefixedLabel)
     }
 }
 
+/// Gets the loop status the loop button switches to from `loop_status`.
+fn next_loop_status(loop_status: LoopStatus) -> LoopStatus {
+    match loop_status {
+        LoopStatus::None => LoopStatus::Playlist,
+        LoopStatus::Playlist => LoopStatus::Track,
+        LoopStatus::Track => LoopStatus::None,
+    }
+}
+
 /// Replaces each of the formatting tokens in the formatting string
 /// with actual data pulled from the music player
-fn replace_tokens(format_string: &str, tokens: &Vec<String>, song: &Track) -> String {
+fn replace_tokens(
+    format_string: &str,
+    tokens: &Vec<String>,
+    song: &Track,
+    status: &Status,
+    icons: &Icons,
+) -> String {
     let mut compiled_string = format_string.to_string();
     for token in tokens {
-        let value = get_token_value(song, token);
+        let value = get_token_value(song, status, icons, token);
         compiled_string = compiled_string.replace(format!("{{{token}}}").as_str(), value.as_str());
     }
     compiled_string
@@ -530,7 +983,7 @@ fn replace_tokens(format_string: &str, tokens: &Vec<String>, song: &Track) -> St
 
 /// Converts a string format token value
 /// into its respective value.
-fn get_token_value(song: &Track, token: &str) -> String {
+fn get_token_value(song: &Track, status: &Status, icons: &Icons, token: &str) -> String {
     match token {
         "title" => song.title.clone(),
         "album" => song.album.clone(),
@@ -539,11 +992,185 @@ fn get_token_value(song: &Track, token: &str) -> String {
         "disc" => song.disc.map(|x| x.to_string()),
         "genre" => song.genre.clone(),
         "track" => song.track.map(|x| x.to_string()),
+        "shuffle" => status
+            .shuffle
+            .filter(|&shuffle| shuffle)
+            .map(|_| icons.shuffle.clone()),
+        "loop" => status
+            .loop_status
+            .and_then(|loop_status| match loop_status {
+                LoopStatus::None => None,
+                LoopStatus::Playlist => Some(icons.loop_playlist.clone()),
+                LoopStatus::Track => Some(icons.loop_track.clone()),
+            }),
         _ => Some(token.to_string()),
     }
     .unwrap_or_default()
 }
 
+/// The upcoming tracks in the queue,
+/// and a selector to add stored playlists to it.
+struct QueueUi {
+    container: gtk::Box,
+    list_window: ScrolledWindow,
+    list: gtk::Box,
+    playlist_selector: ComboBoxText,
+
+    icon_next: String,
+    icon_remove: String,
+    icon_theme: IconTheme,
+    icon_size: i32,
+    max_items: usize,
+
+    tx: mpsc::Sender<PlayerCommand>,
+}
+
+impl QueueUi {
+    fn new(
+        icons: &Icons,
+        icon_theme: &IconTheme,
+        icon_size: i32,
+        max_items: usize,
+        tx: mpsc::Sender<PlayerCommand>,
+    ) -> Self {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+        container.add_class("queue");
+
+        let playlist_selector = ComboBoxText::new();
+        playlist_selector.add_class("playlist-selector");
+        playlist_selector.set_tooltip_text(Some("Add a playlist to the queue"));
+
+        {
+            let tx = tx.clone();
+            playlist_selector.connect_changed(move |selector| {
+                if let Some(name) = selector.active_text() {
+                    try_send!(tx, PlayerCommand::LoadPlaylist(name.to_string()));
+
+                    // this triggers another change without an active entry,
+                    // so the same playlist can be picked again
+                    selector.set_active(None);
+                }
+            });
+        }
+
+        let list = gtk::Box::new(Orientation::Vertical, 0);
+
+        let list_window = ScrolledWindow::builder()
+            .hscrollbar_policy(PolicyType::Never)
+            .max_content_height(200)
+            .propagate_natural_height(true)
+            .build();
+        list_window.add_class("items");
+        list_window.add(&list);
+
+        container.add(&playlist_selector);
+        container.add(&list_window);
+
+        Self {
+            container,
+            list_window,
+            list,
+            playlist_selector,
+            icon_next: icons.queue_next.clone(),
+            icon_remove: icons.queue_remove.clone(),
+            icon_theme: icon_theme.clone(),
+            icon_size,
+            max_items,
+            tx,
+        }
+    }
+
+    /// Shows the tracks in `items` from position `start` onwards.
+    fn set_items(&self, items: &[QueueItem], start: u32) {
+        for child in self.list.children() {
+            self.list.remove(&child);
+        }
+
+        let upcoming = items
+            .iter()
+            .filter(|item| item.position >= start)
+            .take(self.max_items);
+
+        for item in upcoming {
+            let row = gtk::Box::new(Orientation::Horizontal, 5);
+            row.add_class("item");
+
+            let text = match &item.artist {
+                Some(artist) => format!("{} / {artist}", item.title),
+                None => item.title.clone(),
+            };
+
+            let label = Label::new(Some(&text));
+            label.set_ellipsize(EllipsizeMode::End);
+            label.set_xalign(0.0);
+
+            let btn_play = Button::new();
+            btn_play.add_class("title");
+            btn_play.add(&label);
+
+            let btn_next = IconButton::new(&self.icon_next, &self.icon_theme, self.icon_size);
+            btn_next.add_class("btn-play-next");
+
+            let btn_remove = IconButton::new(&self.icon_remove, &self.icon_theme, self.icon_size);
+            btn_remove.add_class("btn-remove");
+
+            // IDs are used rather than positions,
+            // which may have shifted before the list is refreshed
+            let id = item.id;
+
+            let tx = self.tx.clone();
+            btn_play.connect_clicked(move |_| {
+                try_send!(tx, PlayerCommand::PlayAt(id));
+            });
+
+            let tx = self.tx.clone();
+            btn_next.connect_clicked(move |_| {
+                try_send!(tx, PlayerCommand::MoveToNext(id));
+            });
+
+            let tx = self.tx.clone();
+            btn_remove.connect_clicked(move |_| {
+                try_send!(tx, PlayerCommand::RemoveFromQueue(id));
+            });
+
+            row.pack_start(&btn_play, true, true, 0);
+            row.add(&*btn_next);
+            row.add(&*btn_remove);
+
+            self.list.add(&row);
+        }
+
+        self.update_visibility();
+    }
+
+    fn set_playlists(&self, playlists: &[String]) {
+        self.playlist_selector.remove_all();
+
+        for playlist in playlists {
+            self.playlist_selector.append_text(playlist);
+        }
+
+        self.update_visibility();
+    }
+
+    /// Hides each part of the queue while it has nothing to show.
+    fn update_visibility(&self) {
+        let has_items = !self.list.children().is_empty();
+        let has_playlists = self
+            .playlist_selector
+            .model()
+            .is_some_and(|model| model.iter_first().is_some());
+
+        self.list_window.set_visible(has_items);
+        if has_items {
+            self.list.show_all();
+        }
+
+        self.playlist_selector.set_visible(has_playlists);
+        self.container.set_visible(has_items || has_playlists);
+    }
+}
+
 #[derive(Clone, Debug)]
 struct IconPrefixedLabel {
     label: Label,
@@ -1,24 +1,42 @@
 use color_eyre::Result;
 use futures_lite::StreamExt;
+use futures_signals::map_ref;
 use futures_signals::signal::SignalExt;
-use gtk::prelude::ContainerExt;
-use gtk::{Box as GtkBox, Image};
+use gtk::prelude::*;
+use gtk::{Box as GtkBox, Button, Image, Label, Orientation, Switch};
 use serde::Deserialize;
-use tokio::sync::mpsc::Receiver;
+use std::collections::HashMap;
+use tokio::select;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error};
 
-use crate::clients::networkmanager::{Client, ClientState};
+use crate::clients::networkmanager::{
+    AccessPoint, Client, ClientState, ConnectionDetails, ConnectionKind, Networks,
+};
 use crate::config::CommonConfig;
-use crate::gtk_helpers::IronbarGtkExt;
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
 use crate::image::ImageProvider;
-use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
-use crate::{glib_recv, module_impl, send_async, spawn};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{glib_recv, module_impl, send_async, try_send};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct NetworkManagerModule {
     #[serde(default = "default_icon_size")]
     icon_size: i32,
 
+    /// The format string to use for the label shown next to the icon.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// The label is hidden while the formatted string is empty.
+    ///
+    /// **Default**: `""`
+    #[serde(default)]
+    format: String,
+
     #[serde(flatten)]
     pub common: Option<CommonConfig>,
 }
@@ -27,25 +45,236 @@ const fn default_icon_size() -> i32 {
     24
 }
 
-impl Module<GtkBox> for NetworkManagerModule {
-    type SendMessage = ClientState;
-    type ReceiveMessage = ();
+#[derive(Clone, Debug)]
+pub enum Event {
+    State(ClientState, ConnectionDetails),
+    Networks(Networks),
+}
+
+#[derive(Clone, Debug)]
+pub enum Update {
+    /// The popup was opened (`true`) or closed (`false`).
+    /// A Wi-Fi scan is requested each time it opens.
+    PopupVisible(bool),
+    /// Connects to, or disconnects from, the Wi-Fi network with this SSID.
+    ToggleWifi(String),
+    /// Activates or deactivates the saved connection at this path.
+    ToggleConnection(String),
+    SetWirelessEnabled(bool),
+    SetNetworkingEnabled(bool),
+}
+
+/// Gets the icon for a Wi-Fi connection with signal `strength`.
+fn wifi_icon(strength: Option<u8>) -> &'static str {
+    match strength {
+        Some(80..) => "network-wireless-signal-excellent-symbolic",
+        Some(55..80) => "network-wireless-signal-good-symbolic",
+        Some(30..55) => "network-wireless-signal-ok-symbolic",
+        Some(5..30) => "network-wireless-signal-weak-symbolic",
+        Some(_) => "network-wireless-signal-none-symbolic",
+        None => "network-wireless-symbolic",
+    }
+}
+
+fn format_label(format: &str, details: &ConnectionDetails) -> String {
+    let strength = details
+        .strength
+        .map(|strength| strength.to_string())
+        .unwrap_or_default();
+
+    format
+        .replace("{ssid}", details.ssid.as_deref().unwrap_or_default())
+        .replace("{strength}", &strength)
+        .replace(
+            "{interface}",
+            details.interface.as_deref().unwrap_or_default(),
+        )
+        .replace(
+            "{ipv4}",
+            details.ipv4_address.as_deref().unwrap_or_default(),
+        )
+        .replace(
+            "{ipv6}",
+            details.ipv6_address.as_deref().unwrap_or_default(),
+        )
+        .replace("{vpn}", details.vpn_name.as_deref().unwrap_or_default())
+}
+
+/// Gets the strongest access point for each SSID,
+/// alongside whether it is connected.
+///
+/// Connected networks come first, then the rest by signal strength.
+/// Hidden networks are skipped.
+fn visible_access_points(networks: &Networks) -> Vec<(&AccessPoint, bool)> {
+    let mut strongest = HashMap::<&str, &AccessPoint>::new();
+
+    for access_point in &networks.access_points {
+        if access_point.ssid.is_empty() {
+            continue;
+        }
+
+        strongest
+            .entry(&access_point.ssid)
+            .and_modify(|current| {
+                if access_point.strength > current.strength {
+                    *current = access_point;
+                }
+            })
+            .or_insert(access_point);
+    }
+
+    let mut access_points = strongest
+        .into_values()
+        .map(|access_point| {
+            let active = networks
+                .wifi_connection(&access_point.ssid)
+                .is_some_and(|connection| connection.active_path.is_some());
+
+            (access_point, active)
+        })
+        .collect::<Vec<_>>();
+
+    access_points.sort_by(|(a, a_active), (b, b_active)| {
+        b_active
+            .cmp(a_active)
+            .then(b.strength.cmp(&a.strength))
+            .then(a.ssid.cmp(&b.ssid))
+    });
+
+    access_points
+}
+
+async fn handle_update(client: &Client, networks: &Networks, update: Update) -> Result<()> {
+    match update {
+        Update::PopupVisible(true) => {
+            // NetworkManager rejects scans shortly after the previous one
+            if let Err(err) = client.request_scan().await {
+                debug!("Failed to request wifi scan: {err:?}");
+            }
+            Ok(())
+        }
+        Update::PopupVisible(false) => Ok(()),
+        Update::ToggleWifi(ssid) => match networks.wifi_connection(&ssid) {
+            Some(connection) => match &connection.active_path {
+                Some(active_path) => client.deactivate_connection(active_path).await,
+                None => client.activate_connection(&connection.path).await,
+            },
+            None => {
+                let access_point = networks
+                    .access_points
+                    .iter()
+                    .filter(|access_point| access_point.ssid == ssid)
+                    .max_by_key(|access_point| access_point.strength);
+
+                match access_point {
+                    Some(access_point) => client.connect_access_point(access_point).await,
+                    None => Ok(()),
+                }
+            }
+        },
+        Update::ToggleConnection(path) => {
+            let connection = networks
+                .connections
+                .iter()
+                .find(|connection| connection.path == path);
+
+            match connection.map(|connection| &connection.active_path) {
+                Some(Some(active_path)) => client.deactivate_connection(active_path).await,
+                Some(None) => client.activate_connection(&path).await,
+                None => Ok(()),
+            }
+        }
+        // the popup switches also fire when updated to match the current state
+        Update::SetWirelessEnabled(enabled) if enabled != networks.wireless_enabled => {
+            client.set_wireless_enabled(enabled).await
+        }
+        Update::SetNetworkingEnabled(enabled) if enabled != networks.networking_enabled => {
+            client.set_networking_enabled(enabled).await
+        }
+        Update::SetWirelessEnabled(_) | Update::SetNetworkingEnabled(_) => Ok(()),
+    }
+}
+
+impl Module<Button> for NetworkManagerModule {
+    type SendMessage = Event;
+    type ReceiveMessage = Update;
 
     module_impl!("network_manager");
 
     fn spawn_controller(
         &self,
         _: &ModuleInfo,
-        context: &WidgetContext<ClientState, ()>,
-        _: Receiver<()>,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let client = context.try_client::<Client>()?;
-        let mut client_signal = client.subscribe().to_stream();
+        let mut client_signal = map_ref! {
+            let state = client.subscribe(),
+            let details = client.subscribe_details() =>
+            (state.clone(), details.clone())
+        }
+        .to_stream();
         let widget_transmitter = context.tx.clone();
 
-        spawn(async move {
-            while let Some(state) = client_signal.next().await {
-                send_async!(widget_transmitter, ModuleUpdateEvent::Update(state));
+        context.spawn(async move {
+            while let Some((state, details)) = client_signal.next().await {
+                send_async!(
+                    widget_transmitter,
+                    ModuleUpdateEvent::Update(Event::State(state, details))
+                );
+            }
+        });
+
+        let tx = context.tx.clone();
+
+        context.spawn(async move {
+            let mut networks_rx = client.subscribe_networks();
+            let mut networks = Networks::default();
+
+            // the networks are only shown in the popup,
+            // so changes while it is closed are only read once it next opens
+            let mut popup_visible = false;
+            let mut stale = true;
+
+            loop {
+                if popup_visible && stale {
+                    stale = false;
+
+                    match client.networks().await {
+                        Ok(new_networks) if new_networks != networks => {
+                            networks = new_networks;
+                            send_async!(
+                                tx,
+                                ModuleUpdateEvent::Update(Event::Networks(networks.clone()))
+                            );
+                        }
+                        Ok(_) => {}
+                        Err(err) => error!("Failed to read networks: {err:?}"),
+                    }
+                }
+
+                select! {
+                    update = rx.recv() => {
+                        let Some(update) = update else {
+                            break;
+                        };
+
+                        if let Update::PopupVisible(visible) = update {
+                            popup_visible = visible;
+                        }
+
+                        if let Err(err) = handle_update(&client, &networks, update).await {
+                            error!("{err:?}");
+                        }
+                    }
+                    result = networks_rx.recv() => {
+                        if let Err(RecvError::Closed) = result {
+                            break;
+                        }
+
+                        stale = true;
+                    }
+                }
             }
         });
 
@@ -54,35 +283,260 @@ impl Module<GtkBox> for NetworkManagerModule {
 
     fn into_widget(
         self,
-        context: WidgetContext<ClientState, ()>,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
         info: &ModuleInfo,
-    ) -> Result<ModuleParts<GtkBox>> {
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+
         let container = GtkBox::new(info.bar_position.orientation(), 0);
+        button.add(&container);
+
         let icon = Image::new();
         icon.add_class("icon");
         container.add(&icon);
 
+        let label = Label::new(None);
+        label.add_class("label");
+        label.set_no_show_all(true);
+        container.add(&label);
+
+        {
+            let tx = context.tx.clone();
+
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
         let icon_theme = info.icon_theme.clone();
 
         let initial_icon_name = "content-loading-symbolic";
         ImageProvider::parse(initial_icon_name, &icon_theme, false, self.icon_size)
             .map(|provider| provider.load_into_image(&icon));
 
-        let widget_receiver = context.subscribe();
-        glib_recv!(widget_receiver, state => {
-            let icon_name = match state {
-                ClientState::WiredConnected => "network-wired-symbolic",
-                ClientState::WifiConnected => "network-wireless-symbolic",
-                ClientState::CellularConnected => "network-cellular-symbolic",
-                ClientState::VpnConnected => "network-vpn-symbolic",
-                ClientState::WifiDisconnected => "network-wireless-acquiring-symbolic",
-                ClientState::Offline => "network-wireless-disabled-symbolic",
-                ClientState::Unknown => "dialog-question-symbolic",
+        {
+            let widget_receiver = context.subscribe();
+            let format = self.format.clone();
+            let icon_size = self.icon_size;
+
+            glib_recv!(widget_receiver, event => {
+                let Event::State(state, details) = event else {
+                    continue;
+                };
+
+                let icon_name = match state {
+                    ClientState::WiredConnected => "network-wired-symbolic",
+                    ClientState::WifiConnected => wifi_icon(details.strength),
+                    ClientState::CellularConnected => "network-cellular-symbolic",
+                    ClientState::VpnConnected => "network-vpn-symbolic",
+                    ClientState::WifiDisconnected => "network-wireless-acquiring-symbolic",
+                    ClientState::Offline => "network-wireless-disabled-symbolic",
+                    ClientState::Unknown => "dialog-question-symbolic",
+                };
+                ImageProvider::parse(icon_name, &icon_theme, false, icon_size)
+                    .map(|provider| provider.load_into_image(&icon));
+
+                let text = format_label(&format, &details);
+                label.set_label_escaped(&text);
+                label.set_visible(!text.is_empty());
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Option<GtkBox> {
+        let container = GtkBox::new(Orientation::Vertical, 10);
+
+        let toggles = GtkBox::new(Orientation::Horizontal, 5);
+        toggles.add_class("toggles");
+        container.add(&toggles);
+
+        let wifi_toggle = add_toggle(&toggles, "Wi-Fi", "wifi-toggle");
+        let networking_toggle = add_toggle(&toggles, "Networking", "networking-toggle");
+
+        {
+            let tx = tx.clone();
+            wifi_toggle.connect_active_notify(move |switch| {
+                try_send!(tx, Update::SetWirelessEnabled(switch.is_active()));
+            });
+        }
+
+        {
+            let tx = tx.clone();
+            networking_toggle.connect_active_notify(move |switch| {
+                try_send!(tx, Update::SetNetworkingEnabled(switch.is_active()));
+            });
+        }
+
+        let (access_points_section, access_points) =
+            add_section(&container, "Wi-Fi", "access-points");
+        let (connections_section, connections) =
+            add_section(&container, "Connections", "connections");
+        let (vpns_section, vpns) = add_section(&container, "VPN", "vpns");
+
+        // scan each time the popup opens so the access point list is current
+        {
+            let tx = tx.clone();
+            container.connect_map(move |_| {
+                try_send!(tx, Update::PopupVisible(true));
+            });
+        }
+
+        {
+            let tx = tx.clone();
+            container.connect_unmap(move |_| {
+                try_send!(tx, Update::PopupVisible(false));
+            });
+        }
+
+        container.show_all();
+
+        let icon_theme = info.icon_theme.clone();
+        let icon_size = self.icon_size;
+
+        glib_recv!(rx, event => {
+            let Event::Networks(networks) = event else {
+                continue;
             };
-            ImageProvider::parse(icon_name, &icon_theme, false, self.icon_size)
-                .map(|provider| provider.load_into_image(&icon));
+
+            wifi_toggle.set_active(networks.wireless_enabled);
+            networking_toggle.set_active(networks.networking_enabled);
+
+            for list in [&access_points, &connections, &vpns] {
+                for child in list.children() {
+                    list.remove(&child);
+                }
+            }
+
+            for (access_point, active) in visible_access_points(&networks) {
+                let button = Button::new();
+                button.add_class("access-point");
+                if active {
+                    button.add_class("active");
+                }
+
+                let row = GtkBox::new(Orientation::Horizontal, 5);
+                button.add(&row);
+
+                let icon = Image::new();
+                icon.add_class("icon");
+                ImageProvider::parse(wifi_icon(Some(access_point.strength)), &icon_theme, false, icon_size)
+                    .map(|provider| provider.load_into_image(&icon));
+                row.add(&icon);
+
+                let label = Label::new(Some(&access_point.ssid));
+                label.add_class("ssid");
+                row.add(&label);
+
+                if access_point.secured {
+                    let secured = Image::new();
+                    secured.add_class("secured");
+                    ImageProvider::parse("network-wireless-encrypted-symbolic", &icon_theme, false, icon_size)
+                        .map(|provider| provider.load_into_image(&secured));
+                    row.pack_end(&secured, false, false, 0);
+                }
+
+                {
+                    let tx = tx.clone();
+                    let ssid = access_point.ssid.clone();
+                    button.connect_clicked(move |_| {
+                        try_send!(tx, Update::ToggleWifi(ssid.clone()));
+                    });
+                }
+
+                access_points.add(&button);
+            }
+
+            for connection in &networks.connections {
+                let list = match connection.kind {
+                    ConnectionKind::Wifi { .. } => continue,
+                    ConnectionKind::Vpn => &vpns,
+                    ConnectionKind::Other => &connections,
+                };
+
+                let button = Button::with_label(&connection.id);
+                button.add_class("connection");
+                if connection.active_path.is_some() {
+                    button.add_class("active");
+                }
+
+                {
+                    let tx = tx.clone();
+                    let path = connection.path.clone();
+                    button.connect_clicked(move |_| {
+                        try_send!(tx, Update::ToggleConnection(path.clone()));
+                    });
+                }
+
+                list.add(&button);
+            }
+
+            for (section, list) in [
+                (&access_points_section, &access_points),
+                (&connections_section, &connections),
+                (&vpns_section, &vpns),
+            ] {
+                list.show_all();
+                section.set_visible(!list.children().is_empty());
+            }
         });
 
-        Ok(ModuleParts::new(container, None))
+        Some(container)
     }
 }
+
+/// Adds a labelled switch to `container`.
+fn add_toggle(container: &GtkBox, label: &str, class: &str) -> Switch {
+    let label = Label::new(Some(label));
+    label.add_class("label");
+
+    let switch = Switch::new();
+    switch.add_class(class);
+
+    container.add(&label);
+    container.add(&switch);
+
+    switch
+}
+
+/// Adds a titled section to `container`,
+/// returning the section and the box to add its items to.
+///
+/// Sections start hidden, and are shown once they have items.
+fn add_section(container: &GtkBox, title: &str, class: &str) -> (GtkBox, GtkBox) {
+    let section = GtkBox::new(Orientation::Vertical, 5);
+    section.add_class(class);
+
+    let header = Label::new(Some(title));
+    header.add_class("header");
+    header.set_halign(gtk::Align::Start);
+    section.add(&header);
+
+    let list = GtkBox::new(Orientation::Vertical, 2);
+    list.add_class("items");
+    section.add(&list);
+
+    header.show();
+    list.show();
+
+    section.set_no_show_all(true);
+    container.add(&section);
+
+    (section, list)
+}
@@ -2,14 +2,14 @@ use crate::clients::swaync;
 use crate::config::CommonConfig;
 use crate::gtk_helpers::IronbarGtkExt;
 use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
-use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use crate::{glib_recv, module_impl, send_async, try_send};
 use gtk::prelude::*;
 use gtk::{Align, Button, Label, Overlay};
 use serde::Deserialize;
 use tokio::sync::mpsc::Receiver;
 use tracing::error;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct NotificationsModule {
     /// Whether to show the current notification count.
@@ -29,7 +29,7 @@ pub struct NotificationsModule {
     pub common: Option<CommonConfig>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 struct Icons {
     /// Icon to show when the panel is closed, with no notifications.
@@ -149,7 +149,7 @@ impl Module<Overlay> for NotificationsModule {
             let mut rx = client.subscribe();
             let tx = context.tx.clone();
 
-            spawn(async move {
+            context.spawn(async move {
                 let initial_state = client.state().await;
 
                 match initial_state {
@@ -163,7 +163,7 @@ impl Module<Overlay> for NotificationsModule {
             });
         }
 
-        spawn(async move {
+        context.spawn(async move {
             while let Some(event) = rx.recv().await {
                 match event {
                     UiEvent::ToggleVisibility => client.toggle_visibility().await,
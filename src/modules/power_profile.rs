@@ -0,0 +1,326 @@
+use crate::clients::power_profiles::{self, State};
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{glib_recv, module_impl, send_async, try_send};
+use glib::Propagation;
+use gtk::gdk::{BUTTON_PRIMARY, BUTTON_SECONDARY};
+use gtk::prelude::*;
+use gtk::{Button, Label, Orientation};
+use serde::Deserialize;
+use tokio::sync::{broadcast, mpsc};
+use tracing::error;
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PowerProfileModule {
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon}`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// Icons to show for each profile.
+    ///
+    /// See [icons](#icons).
+    #[serde(default)]
+    icons: Icons,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(default, flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("{icon}")
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Icons {
+    /// Icon to show for the `power-saver` profile.
+    ///
+    /// **Default**: `󰾆`
+    #[serde(default = "default_icon_power_saver")]
+    power_saver: String,
+
+    /// Icon to show for the `balanced` profile.
+    ///
+    /// **Default**: `󰾅`
+    #[serde(default = "default_icon_balanced")]
+    balanced: String,
+
+    /// Icon to show for the `performance` profile.
+    ///
+    /// **Default**: `󰓅`
+    #[serde(default = "default_icon_performance")]
+    performance: String,
+
+    /// Icon to show for any other profile.
+    ///
+    /// **Default**: `󰾅`
+    #[serde(default = "default_icon_balanced")]
+    unknown: String,
+}
+
+impl Icons {
+    fn profile_icon(&self, profile: &str) -> &str {
+        match profile {
+            "power-saver" => &self.power_saver,
+            "balanced" => &self.balanced,
+            "performance" => &self.performance,
+            _ => &self.unknown,
+        }
+    }
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            power_saver: default_icon_power_saver(),
+            balanced: default_icon_balanced(),
+            performance: default_icon_performance(),
+            unknown: default_icon_balanced(),
+        }
+    }
+}
+
+fn default_icon_power_saver() -> String {
+    String::from("󰾆")
+}
+
+fn default_icon_balanced() -> String {
+    String::from("󰾅")
+}
+
+fn default_icon_performance() -> String {
+    String::from("󰓅")
+}
+
+#[derive(Debug, Clone)]
+pub enum Update {
+    /// Sets the active profile by name.
+    Set(String),
+    /// Switches to the profile after the active one,
+    /// wrapping back to the first.
+    Cycle,
+}
+
+/// Gets a human-readable name for the profile,
+/// for example `power-saver` becomes `Power saver`.
+fn profile_name(profile: &str) -> String {
+    let name = profile.replace('-', " ");
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// Gets the profile after `active` in `profiles`,
+/// wrapping back to the first.
+fn next_profile<'a>(profiles: &'a [String], active: &str) -> Option<&'a String> {
+    let index = profiles
+        .iter()
+        .position(|profile| profile == active)
+        .map_or(0, |index| (index + 1) % profiles.len());
+
+    profiles.get(index)
+}
+
+impl Module<Button> for PowerProfileModule {
+    type SendMessage = State;
+    type ReceiveMessage = Update;
+
+    module_impl!("power_profile");
+
+    #[cfg(feature = "ipc")]
+    fn ipc_action(action: &str, args: &[String]) -> color_eyre::Result<Self::ReceiveMessage> {
+        use crate::modules::actions::{parse_arg, unknown_action};
+
+        match action {
+            "set" => parse_arg(args, 0, "profile").map(Update::Set),
+            "cycle" => Ok(Update::Cycle),
+            _ => Err(unknown_action(action, &["set", "cycle"])),
+        }
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> color_eyre::Result<()> {
+        let client = context.try_client::<power_profiles::Client>()?;
+
+        {
+            let client = client.clone();
+            let tx = context.tx.clone();
+
+            context.spawn(async move {
+                let mut rx = client.subscribe();
+
+                send_async!(tx, ModuleUpdateEvent::Update(client.state()));
+
+                while let Ok(state) = rx.recv().await {
+                    send_async!(tx, ModuleUpdateEvent::Update(state));
+                }
+            });
+        }
+
+        context.spawn(async move {
+            while let Some(update) = rx.recv().await {
+                let profile = match update {
+                    Update::Set(profile) => profile,
+                    Update::Cycle => {
+                        let state = client.state();
+                        match next_profile(&state.profiles, &state.active) {
+                            Some(profile) => profile.clone(),
+                            None => continue,
+                        }
+                    }
+                };
+
+                if let Err(err) = client.set_profile(&profile).await {
+                    error!("Failed to set power profile to '{profile}': {err:?}");
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> color_eyre::Result<ModuleParts<Button>> {
+        let button_label = Label::builder()
+            .use_markup(true)
+            .angle(self.layout.angle(info))
+            .justify(self.layout.justify.into())
+            .build();
+
+        let button = Button::new();
+        button.add(&button_label);
+
+        {
+            let tx = context.tx.clone();
+            let controller_tx = context.controller_tx.clone();
+
+            button.connect_button_release_event(move |button, event| {
+                match event.button() {
+                    BUTTON_PRIMARY => try_send!(controller_tx, Update::Cycle),
+                    BUTTON_SECONDARY => {
+                        try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+                    }
+                    _ => {}
+                }
+
+                Propagation::Proceed
+            });
+        }
+
+        {
+            let rx = context.subscribe();
+            let icons = self.icons.clone();
+            let format = self.format.clone();
+            let button = button.clone();
+
+            let mut active = String::new();
+
+            glib_recv!(rx, state => {
+                button.remove_class(&active);
+
+                let label = format
+                    .replace("{icon}", icons.profile_icon(&state.active))
+                    .replace("{profile}", &profile_name(&state.active));
+
+                button_label.set_label_escaped(&label);
+
+                button.add_class(&state.active);
+                active = state.active;
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+        container.add_class("profiles");
+
+        {
+            let container = container.clone();
+            let mut profiles = vec![];
+
+            glib_recv!(rx, state => {
+                if state.profiles != profiles {
+                    for child in container.children() {
+                        container.remove(&child);
+                    }
+
+                    for profile in &state.profiles {
+                        let label = format!(
+                            "{} {}",
+                            self.icons.profile_icon(profile),
+                            profile_name(profile)
+                        );
+
+                        let button = Button::with_label(&label);
+                        button.add_class("profile");
+                        button.add_class(profile);
+
+                        {
+                            let tx = tx.clone();
+                            let profile = profile.clone();
+
+                            button.connect_clicked(move |_| {
+                                try_send!(tx, Update::Set(profile.clone()));
+                            });
+                        }
+
+                        container.add(&button);
+                    }
+
+                    container.show_all();
+                    profiles = state.profiles;
+                }
+
+                for (button, profile) in container.children().iter().zip(&profiles) {
+                    if *profile == state.active {
+                        button.add_class("active");
+                    } else {
+                        button.remove_class("active");
+                    }
+                }
+            });
+        }
+
+        Some(container)
+    }
+}
@@ -2,14 +2,14 @@ use crate::config::{CommonConfig, LayoutConfig};
 use crate::gtk_helpers::IronbarLabelExt;
 use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
 use crate::script::{OutputStream, Script, ScriptMode};
-use crate::{glib_recv, module_impl, spawn, try_send};
+use crate::{glib_recv, module_impl, try_send};
 use color_eyre::{Help, Report, Result};
 use gtk::Label;
 use serde::Deserialize;
 use tokio::sync::mpsc;
 use tracing::error;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ScriptModule {
     /// Path to script to execute.
@@ -80,7 +80,7 @@ impl Module<Label> for ScriptModule {
         let script: Script = self.into();
 
         let tx = context.tx.clone();
-        spawn(async move {
+        context.spawn(async move {
             script.run(None, move |out, _| match out {
                OutputStream::Stdout(stdout) => {
                    try_send!(tx, ModuleUpdateEvent::Update(stdout));
@@ -10,7 +10,7 @@ use swayipc_async::ModeEvent;
 use tokio::sync::mpsc;
 use tracing::{info, trace};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SwayModeModule {
     // -- Common --
@@ -7,7 +7,7 @@ use crate::config::{CommonConfig, LayoutConfig, ModuleOrientation};
 use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
 use crate::modules::sysinfo::token::Part;
 use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
-use crate::{clients, glib_recv, module_impl, send_async, spawn, try_send};
+use crate::{clients, glib_recv, module_impl, send_async, try_send};
 use color_eyre::Result;
 use gtk::Label;
 use gtk::prelude::*;
@@ -16,7 +16,7 @@ use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::time::sleep;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SysInfoModule {
     /// List of strings including formatting tokens.
@@ -51,7 +51,7 @@ pub struct SysInfoModule {
     pub common: Option<CommonConfig>,
 }
 
-#[derive(Debug, Deserialize, Copy, Clone)]
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Intervals {
     /// The number of seconds between refreshing memory data.
@@ -91,7 +91,7 @@ pub struct Intervals {
     system: u64,
 }
 
-#[derive(Debug, Deserialize, Copy, Clone)]
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq)]
 #[serde(untagged)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum Interval {
@@ -224,7 +224,7 @@ impl Module<gtk::Box> for SysInfoModule {
         macro_rules! spawn_refresh {
             ($refresh_type:expr, $func:ident) => {{
                 let tx = refresh_tx.clone();
-                spawn(async move {
+                context.spawn(async move {
                     loop {
                         send_async!(tx, $refresh_type);
                         sleep(Duration::from_secs(interval.$func())).await;
@@ -241,7 +241,7 @@ impl Module<gtk::Box> for SysInfoModule {
         spawn_refresh!(RefreshType::System, system);
 
         let tx = context.tx.clone();
-        spawn(async move {
+        context.spawn(async move {
             while let Some(refresh) = refresh_rx.recv().await {
                 match refresh {
                     RefreshType::Memory => client.refresh_memory(),
@@ -4,7 +4,7 @@ mod interface;
 use crate::clients::tray;
 use crate::config::{CommonConfig, ModuleOrientation};
 use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
-use crate::{glib_recv, lock, module_impl, send_async, spawn};
+use crate::{glib_recv, lock, module_impl, send_async};
 use color_eyre::{Report, Result};
 use gtk::prelude::*;
 use gtk::{IconTheme, Orientation};
@@ -16,7 +16,7 @@ use system_tray::client::{ActivateRequest, UpdateEvent};
 use tokio::sync::mpsc;
 use tracing::{debug, error, trace, warn};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TrayModule {
     /// Requests that icons from the theme be used over the item-provided item.
@@ -72,7 +72,7 @@ impl Module<gtk::Box> for TrayModule {
         };
 
         // listen to tray updates
-        spawn(async move {
+        context.spawn(async move {
             for (key, (item, menu)) in initial_items {
                 send_async!(
                     tx,
@@ -93,7 +93,7 @@ impl Module<gtk::Box> for TrayModule {
         });
 
         // send tray commands
-        spawn(async move {
+        context.spawn(async move {
             while let Some(cmd) = rx.recv().await {
                 if let Err(err) = client.activate(cmd).await {
                     error!("{err:?}");
@@ -0,0 +1,496 @@
+mod threshold;
+
+use color_eyre::Result;
+use gtk::{Button, prelude::*};
+use gtk::{Label, Orientation};
+use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::clients::upower::{self, BatteryState, BatteryType, Device, Event};
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::image::ImageProvider;
+use crate::modules::PopupButton;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, WidgetContext,
+};
+use crate::script::Script;
+use crate::{glib_recv, module_impl, send_async, try_send};
+use threshold::Threshold;
+
+const DAY: i64 = 24 * 60 * 60;
+const HOUR: i64 = 60 * 60;
+const MINUTE: i64 = 60;
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct UpowerModule {
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{percentage}%`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The size to render the icon at, in pixels.
+    ///
+    /// **Default**: `24`
+    #[serde(default = "default_icon_size")]
+    icon_size: i32,
+
+    /// Shows the first device of this type on the widget,
+    /// instead of the combined display device.
+    ///
+    /// **Valid options**: `battery`, `ups`, `mouse`, `keyboard`, `headset`, `headphones`, `gaming_input`, `phone`, ...
+    /// See the [UPower docs](https://upower.freedesktop.org/docs/Device.html#Device:Type) for the full list,
+    /// written in `snake_case`.
+    /// <br>
+    /// **Default**: `null`
+    device_type: Option<BatteryType>,
+
+    /// Shows the first device whose model contains this text on the widget,
+    /// instead of the combined display device.
+    /// Matching is case-insensitive.
+    ///
+    /// This can be combined with `device_type`, in which case both must match.
+    ///
+    /// **Default**: `null`
+    model: Option<String>,
+
+    /// Actions to take when the battery percentage of the widget device crosses set levels.
+    /// See [thresholds](#thresholds).
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    thresholds: Vec<Threshold>,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(default, flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("{percentage}%")
+}
+
+const fn default_icon_size() -> i32 {
+    24
+}
+
+impl UpowerModule {
+    /// Whether a device has been selected using `device_type` or `model`,
+    /// rather than using the display device.
+    const fn has_selector(&self) -> bool {
+        self.device_type.is_some() || self.model.is_some()
+    }
+
+    fn matches(&self, device: &Device) -> bool {
+        self.device_type
+            .is_none_or(|device_type| device.device_type == device_type)
+            && self
+                .model
+                .as_ref()
+                .is_none_or(|model| device.model.to_lowercase().contains(&model.to_lowercase()))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct UpowerUpdate {
+    /// The device shown on the widget,
+    /// if one is available.
+    device: Option<Device>,
+    /// All devices with a battery, ordered by path.
+    devices: Vec<Device>,
+    /// Whether each configured threshold is entered.
+    thresholds: Vec<bool>,
+}
+
+impl UpowerUpdate {
+    fn new(module: &UpowerModule, display: Option<Device>, devices: Vec<Device>) -> Self {
+        let device = if module.has_selector() {
+            devices
+                .iter()
+                .find(|device| module.matches(device))
+                .cloned()
+        } else {
+            display
+        };
+
+        let devices = devices
+            .into_iter()
+            .filter(|device| device.device_type != BatteryType::LinePower)
+            .collect();
+
+        Self {
+            device,
+            devices,
+            thresholds: vec![],
+        }
+    }
+}
+
+impl Module<Button> for UpowerModule {
+    type SendMessage = UpowerUpdate;
+    type ReceiveMessage = ();
+
+    module_impl!("upower");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let tx = context.tx.clone();
+
+        let client = context.try_client::<upower::Client>()?;
+        let module = self.clone();
+
+        let thresholds = self
+            .thresholds
+            .iter()
+            .map(|threshold| {
+                let script = threshold.on_enter.clone().map(Script::new_polling);
+                (threshold.clone(), script)
+            })
+            .collect::<Vec<_>>();
+
+        context.spawn(async move {
+            let mut rx = client.subscribe();
+
+            let display_path = client.display_path().to_string();
+            let mut devices = client
+                .devices()
+                .into_iter()
+                .chain(client.display_device())
+                .map(|device| (device.path.clone(), device))
+                .collect::<HashMap<_, _>>();
+
+            // whether each threshold is entered, kept per module instance
+            let mut entered = vec![false; thresholds.len()];
+
+            loop {
+                let display = devices.get(&display_path).cloned();
+
+                let mut others = devices
+                    .values()
+                    .filter(|device| device.path != display_path)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                others.sort_by(|a, b| a.path.cmp(&b.path));
+
+                let mut update = UpowerUpdate::new(&module, display, others);
+
+                update_thresholds(&thresholds, &mut entered, update.device.as_ref());
+                update.thresholds.clone_from(&entered);
+
+                send_async!(tx, ModuleUpdateEvent::Update(update));
+
+                match rx.recv().await {
+                    Ok(Event::Update(device)) => {
+                        devices.insert(device.path.clone(), device);
+                    }
+                    Ok(Event::Remove(path)) => {
+                        devices.remove(&path);
+                    }
+                    Err(RecvError::Lagged(_)) => {
+                        devices = client
+                            .devices()
+                            .into_iter()
+                            .chain(client.display_device())
+                            .map(|device| (device.path.clone(), device))
+                            .collect();
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let icon_theme = info.icon_theme.clone();
+        let icon = gtk::Image::new();
+        icon.add_class("icon");
+
+        let label = Label::builder()
+            .label(&self.format)
+            .use_markup(true)
+            .angle(self.layout.angle(info))
+            .justify(self.layout.justify.into())
+            .build();
+
+        label.add_class("label");
+
+        let container = gtk::Box::new(self.layout.orientation(info), 5);
+        container.add_class("contents");
+
+        let button = Button::new();
+        button.add_class("button");
+
+        container.add(&icon);
+        container.add(&label);
+        button.add(&container);
+
+        let tx = context.tx.clone();
+        button.connect_clicked(move |button| {
+            try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+        });
+
+        let format = self.format.clone();
+        let threshold_classes = self
+            .thresholds
+            .iter()
+            .map(|threshold| threshold.class.clone())
+            .collect::<Vec<_>>();
+
+        let rx = context.subscribe();
+        {
+            let button = button.clone();
+            let icon_theme = icon_theme.clone();
+
+            glib_recv!(rx, update => {
+                for (class, &active) in threshold_classes.iter().zip(&update.thresholds) {
+                    if let Some(class) = class {
+                        if active {
+                            button.add_class(class);
+                        } else {
+                            button.remove_class(class);
+                        }
+                    }
+                }
+
+                // hide the widget while the selected device is not connected
+                button.set_visible(update.device.is_some());
+                let Some(properties) = update.device else {
+                    continue;
+                };
+
+                let state = properties.state;
+                let is_charging = state == BatteryState::Charging || state == BatteryState::PendingCharge;
+                let time_remaining = if is_charging {
+                    seconds_to_string(properties.time_to_full)
+                }
+                else {
+                    seconds_to_string(properties.time_to_empty)
+                };
+                let format = format.replace("{percentage}", &properties.percentage.to_string())
+                    .replace("{time_remaining}", &time_remaining)
+                    .replace("{state}", battery_state_to_string(state))
+                    .replace("{model}", device_name(&properties));
+
+                let mut icon_name = String::from("icon:");
+                icon_name.push_str(&properties.icon_name);
+
+                ImageProvider::parse(&icon_name, &icon_theme, false, self.icon_size)
+                        .map(|provider| provider.load_into_image(&icon));
+
+                label.set_label_escaped(&format);
+            });
+        }
+
+        let rx = context.subscribe();
+        let popup = self
+            .into_popup(context.controller_tx.clone(), rx, context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        _tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::builder()
+            .orientation(Orientation::Vertical)
+            .build();
+
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("upower-details");
+        container.add(&label);
+
+        let devices_container = gtk::Box::new(Orientation::Vertical, 5);
+        devices_container.add_class("devices");
+        container.add(&devices_container);
+
+        let icon_theme = info.icon_theme.clone();
+
+        glib_recv!(rx, update => {
+            let format = update.device.as_ref().map(|properties| match properties.state {
+                BatteryState::Charging | BatteryState::PendingCharge => {
+                    let ttf = properties.time_to_full;
+                    if ttf > 0 {
+                        format!("Full in {}", seconds_to_string(ttf))
+                    } else {
+                        String::new()
+                    }
+                }
+                BatteryState::Discharging | BatteryState::PendingDischarge => {
+                    let tte = properties.time_to_empty;
+                    if tte > 0 {
+                        format!("Empty in {}", seconds_to_string(tte))
+                    } else {
+                        String::new()
+                    }
+                }
+                _ => String::new(),
+            }).unwrap_or_default();
+
+            label.set_label_escaped(&format);
+
+            for child in devices_container.children() {
+                devices_container.remove(&child);
+            }
+
+            for device in &update.devices {
+                let row = gtk::Box::new(Orientation::Horizontal, 5);
+                row.add_class("device");
+
+                let icon = gtk::Image::new();
+                icon.add_class("icon");
+                ImageProvider::parse(&format!("icon:{}", device.icon_name), &icon_theme, false, self.icon_size)
+                    .map(|provider| provider.load_into_image(&icon));
+
+                let name = Label::new(Some(device_name(device)));
+                name.add_class("name");
+
+                let percentage = Label::new(Some(&format!("{}%", device.percentage.round())));
+                percentage.add_class("percentage");
+
+                let state = Label::new(Some(battery_state_to_string(device.state)));
+                state.add_class("state");
+
+                row.add(&icon);
+                row.add(&name);
+                row.add(&percentage);
+                row.add(&state);
+
+                devices_container.add(&row);
+            }
+
+            devices_container.show_all();
+        });
+
+        container.show_all();
+
+        Some(container)
+    }
+}
+
+/// Enters or leaves each threshold for the latest properties of the device,
+/// running scripts for any newly entered.
+///
+/// All thresholds are left if the device is not available.
+fn update_thresholds(
+    thresholds: &[(Threshold, Option<Script>)],
+    entered: &mut [bool],
+    properties: Option<&Device>,
+) {
+    let state = properties.map(|properties| {
+        let charging = matches!(
+            properties.state,
+            BatteryState::Charging | BatteryState::PendingCharge | BatteryState::FullyCharged
+        );
+
+        (properties.percentage, charging)
+    });
+
+    for ((threshold, script), entered) in thresholds.iter().zip(entered) {
+        if threshold.update(entered, state) {
+            if let (Some(script), Some(properties)) = (script, properties) {
+                script.run_as_oneshot(Some(&[properties.percentage.to_string()]));
+            }
+        }
+    }
+}
+
+fn seconds_to_string(seconds: i64) -> String {
+    let mut time_string = String::new();
+    let days = seconds / (DAY);
+    if days > 0 {
+        time_string += &format!("{days}d");
+    }
+    let hours = (seconds % DAY) / HOUR;
+    if hours > 0 {
+        time_string += &format!(" {hours}h");
+    }
+    let minutes = (seconds % HOUR) / MINUTE;
+    if minutes > 0 {
+        time_string += &format!(" {minutes}m");
+    }
+    time_string.trim_start().to_string()
+}
+
+fn battery_state_to_string(state: BatteryState) -> &'static str {
+    match state {
+        BatteryState::Unknown => "Unknown",
+        BatteryState::Charging => "Charging",
+        BatteryState::Discharging => "Discharging",
+        BatteryState::Empty => "Empty",
+        BatteryState::FullyCharged => "Fully charged",
+        BatteryState::PendingCharge => "Pending charge",
+        BatteryState::PendingDischarge => "Pending discharge",
+    }
+}
+
+/// The name to show for the device,
+/// falling back to its type if the model is unknown.
+fn device_name(device: &Device) -> &str {
+    if device.model.is_empty() {
+        battery_type_to_string(device.device_type)
+    } else {
+        &device.model
+    }
+}
+
+fn battery_type_to_string(device_type: BatteryType) -> &'static str {
+    match device_type {
+        BatteryType::Unknown => "Unknown",
+        BatteryType::LinePower => "Power supply",
+        BatteryType::Battery => "Battery",
+        BatteryType::Ups => "UPS",
+        BatteryType::Monitor => "Monitor",
+        BatteryType::Mouse => "Mouse",
+        BatteryType::Keyboard => "Keyboard",
+        BatteryType::Pda => "PDA",
+        BatteryType::Phone => "Phone",
+        BatteryType::MediaPlayer => "Media player",
+        BatteryType::Tablet => "Tablet",
+        BatteryType::Computer => "Computer",
+        BatteryType::GamingInput => "Controller",
+        BatteryType::Pen => "Pen",
+        BatteryType::Touchpad => "Touchpad",
+        BatteryType::Modem => "Modem",
+        BatteryType::Network => "Network device",
+        BatteryType::Headset => "Headset",
+        BatteryType::Speakers => "Speakers",
+        BatteryType::Headphones => "Headphones",
+        BatteryType::Video => "Video device",
+        BatteryType::OtherAudio => "Audio device",
+        BatteryType::RemoteControl => "Remote control",
+        BatteryType::Printer => "Printer",
+        BatteryType::Scanner => "Scanner",
+        BatteryType::Camera => "Camera",
+        BatteryType::Wearable => "Wearable",
+        BatteryType::Toy => "Toy",
+        BatteryType::BluetoothGeneric => "Bluetooth device",
+    }
+}
@@ -0,0 +1,171 @@
+use crate::script::ScriptInput;
+use serde::Deserialize;
+
+/// An action to take when the battery percentage crosses a set level.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Threshold {
+    /// The battery percentage at which the threshold is entered.
+    pub percentage: f64,
+
+    /// Whether the threshold is entered when the percentage
+    /// falls to or below `percentage`, or rises to or above it.
+    ///
+    /// **Valid options**: `below`, `above`
+    /// <br>
+    /// **Default**: `below`
+    #[serde(default)]
+    pub direction: ThresholdDirection,
+
+    /// Only enters the threshold while the battery is in this state.
+    /// The threshold is left as soon as the state no longer matches.
+    ///
+    /// **Valid options**: `charging`, `discharging`
+    /// <br>
+    /// **Default**: `null`
+    pub state: Option<ChargeState>,
+
+    /// How far the percentage must move back past `percentage`
+    /// before the threshold is left.
+    /// This prevents the threshold flapping when the percentage hovers around it.
+    ///
+    /// **Default**: `2.0`
+    #[serde(default = "default_hysteresis")]
+    pub hysteresis: f64,
+
+    /// A [script](scripts) to run once each time the threshold is entered.
+    /// The current percentage is passed as the first argument.
+    ///
+    /// **Supported script types**: `oneshot`.
+    /// <br>
+    /// **Default**: `null`
+    pub on_enter: Option<ScriptInput>,
+
+    /// A CSS class to add to the widget while the threshold is entered.
+    ///
+    /// **Default**: `null`
+    pub class: Option<String>,
+}
+
+const fn default_hysteresis() -> f64 {
+    2.0
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ThresholdDirection {
+    #[default]
+    Below,
+    Above,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ChargeState {
+    /// Plugged in, including when fully charged.
+    Charging,
+    /// Running on battery.
+    Discharging,
+}
+
+impl Threshold {
+    /// Checks whether the threshold should be entered
+    /// for the current `percentage` and `charging` state.
+    ///
+    /// When the threshold is already `active`,
+    /// the hysteresis is applied before it is left.
+    pub fn is_met(&self, percentage: f64, charging: bool, active: bool) -> bool {
+        let state_matches = match self.state {
+            Some(ChargeState::Charging) => charging,
+            Some(ChargeState::Discharging) => !charging,
+            None => true,
+        };
+
+        if !state_matches {
+            return false;
+        }
+
+        match (self.direction, active) {
+            (ThresholdDirection::Below, false) => percentage <= self.percentage,
+            (ThresholdDirection::Below, true) => percentage < self.percentage + self.hysteresis,
+            (ThresholdDirection::Above, false) => percentage >= self.percentage,
+            (ThresholdDirection::Above, true) => percentage > self.percentage - self.hysteresis,
+        }
+    }
+
+    /// Enters or leaves the threshold given the device's
+    /// current percentage and charging state, if it is available.
+    /// `entered` tracks whether the threshold is currently entered.
+    ///
+    /// Returns whether the threshold was newly entered by this call.
+    pub fn update(&self, entered: &mut bool, state: Option<(f64, bool)>) -> bool {
+        let met =
+            state.is_some_and(|(percentage, charging)| self.is_met(percentage, charging, *entered));
+
+        let newly_entered = met && !*entered;
+        *entered = met;
+
+        newly_entered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn threshold(direction: ThresholdDirection, state: Option<ChargeState>) -> Threshold {
+        Threshold {
+            percentage: 20.0,
+            direction,
+            state,
+            hysteresis: default_hysteresis(),
+            on_enter: None,
+            class: None,
+        }
+    }
+
+    #[test]
+    fn test_below() {
+        let threshold = threshold(ThresholdDirection::Below, None);
+
+        assert!(!threshold.is_met(21.0, false, false));
+        assert!(threshold.is_met(20.0, false, false));
+        assert!(threshold.is_met(15.0, false, false));
+    }
+
+    #[test]
+    fn test_above() {
+        let threshold = threshold(ThresholdDirection::Above, None);
+
+        assert!(!threshold.is_met(19.0, true, false));
+        assert!(threshold.is_met(20.0, true, false));
+        assert!(threshold.is_met(80.0, true, false));
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let threshold = threshold(ThresholdDirection::Below, None);
+
+        assert!(threshold.is_met(21.0, false, true));
+        assert!(!threshold.is_met(22.0, false, true));
+
+        let threshold = Threshold {
+            direction: ThresholdDirection::Above,
+            ..threshold
+        };
+
+        assert!(threshold.is_met(19.0, true, true));
+        assert!(!threshold.is_met(18.0, true, true));
+    }
+
+    #[test]
+    fn test_state() {
+        let threshold = threshold(ThresholdDirection::Below, Some(ChargeState::Discharging));
+
+        assert!(threshold.is_met(10.0, false, false));
+        assert!(!threshold.is_met(10.0, true, false));
+        assert!(!threshold.is_met(10.0, true, true));
+    }
+}
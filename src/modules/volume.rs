@@ -1,20 +1,25 @@
-use crate::clients::volume::{self, Event};
+use crate::clients::volume::{self, Card, Port, Sink, SinkInput, Source, SourceOutput};
 use crate::config::{CommonConfig, LayoutConfig};
 use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
 use crate::modules::{
     Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
 };
-use crate::{glib_recv, lock, module_impl, send_async, spawn, try_send};
+use crate::{glib_recv, lock, send_async, try_send};
 use glib::Propagation;
+use gtk::gdk::{BUTTON_MIDDLE, EventMask, EventScroll, ScrollDirection};
 use gtk::pango::EllipsizeMode;
 use gtk::prelude::*;
-use gtk::{Button, CellRendererText, ComboBoxText, Label, Orientation, Scale, ToggleButton};
+use gtk::{
+    Button, CellRendererText, ComboBoxText, EventBox, Label, Orientation, Scale, ToggleButton,
+};
 use serde::Deserialize;
+use std::cell::Cell;
 use std::collections::HashMap;
+use std::rc::Rc;
 use tokio::sync::mpsc;
 use tracing::trace;
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct VolumeModule {
     /// The format string to use for the widget button label.
@@ -24,6 +29,13 @@ pub struct VolumeModule {
     #[serde(default = "default_format")]
     format: String,
 
+    /// Whether to control playback devices (`sink`)
+    /// or recording devices such as microphones (`source`).
+    ///
+    /// **Default**: `sink`
+    #[serde(default)]
+    mode: Mode,
+
     /// Maximum value to allow volume sliders to reach.
     /// Pulse supports values > 100 but this may result in distortion.
     ///
@@ -31,12 +43,36 @@ pub struct VolumeModule {
     #[serde(default = "default_max_volume")]
     max_volume: f64,
 
+    /// The amount to change the volume by when scrolling
+    /// over the widget, or over an application in the popup.
+    /// Set to `0` to disable scrolling.
+    ///
+    /// Scrolling over the widget is ignored if `on_scroll_up` or `on_scroll_down` is set.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_scroll_step")]
+    scroll_step: f64,
+
+    /// Whether to toggle the mute state of the default device
+    /// when middle-clicking the widget.
+    /// This is ignored if `on_click_middle` is set.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    mute_on_middle_click: bool,
+
     /// Volume state icons.
     ///
     /// See [icons](#icons).
     #[serde(default)]
     icons: Icons,
 
+    /// Whether scrolling the widget should change the volume,
+    /// which is disabled if the common scroll options are set.
+    #[serde(skip, default = "crate::config::default_true")]
+    #[cfg_attr(feature = "schema", schemars(skip))]
+    scroll_widget: bool,
+
     // -- Common --
     /// See [layout options](module-level-options#layout)
     #[serde(default, flatten)]
@@ -51,7 +87,19 @@ fn default_format() -> String {
     String::from("{icon} {percentage}%")
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Deserialize, Default, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Mode {
+    /// Controls output devices and application playback streams.
+    #[default]
+    Sink,
+
+    /// Controls input devices and application recording streams.
+    Source,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Icons {
     /// Icon to show for high volume levels.
@@ -77,14 +125,32 @@ pub struct Icons {
     /// **Default**: `󰝟`
     #[serde(default = "default_icon_muted")]
     muted: String,
+
+    /// Icon to show for inputs in `source` mode,
+    /// and for the `{mic}` token while an application is recording.
+    ///
+    /// **Default**: `󰍬`
+    #[serde(default = "default_icon_mic")]
+    mic: String,
+
+    /// Icon to show for muted inputs in `source` mode.
+    ///
+    /// **Default**: `󰍭`
+    #[serde(default = "default_icon_mic_muted")]
+    mic_muted: String,
 }
 
 impl Icons {
-    fn volume_icon(&self, volume_percent: f64) -> &str {
-        match volume_percent as u32 {
-            0..=33 => &self.volume_low,
-            34..=66 => &self.volume_medium,
-            67.. => &self.volume_high,
+    fn volume_icon(&self, mode: Mode, volume_percent: f64, muted: bool) -> &str {
+        match (mode, muted) {
+            (Mode::Sink, true) => &self.muted,
+            (Mode::Source, true) => &self.mic_muted,
+            (Mode::Source, false) => &self.mic,
+            (Mode::Sink, false) => match volume_percent as u32 {
+                0..=33 => &self.volume_low,
+                34..=66 => &self.volume_medium,
+                67.. => &self.volume_high,
+            },
         }
     }
 }
@@ -96,6 +162,8 @@ impl Default for Icons {
             volume_medium: default_icon_volume_medium(),
             volume_low: default_icon_volume_low(),
             muted: default_icon_muted(),
+            mic: default_icon_mic(),
+            mic_muted: default_icon_mic_muted(),
         }
     }
 }
@@ -104,6 +172,10 @@ const fn default_max_volume() -> f64 {
     100.0
 }
 
+const fn default_scroll_step() -> f64 {
+    5.0
+}
+
 fn default_icon_volume_high() -> String {
     String::from("󰕾")
 }
@@ -120,21 +192,290 @@ fn default_icon_muted() -> String {
     String::from("󰝟")
 }
 
+fn default_icon_mic() -> String {
+    String::from("󰍬")
+}
+
+fn default_icon_mic_muted() -> String {
+    String::from("󰍭")
+}
+
+/// A sink or source, depending on the module [`Mode`].
+#[derive(Debug, Clone)]
+pub struct Device {
+    name: String,
+    description: String,
+    volume: f64,
+    muted: bool,
+    active: bool,
+    ports: Vec<Port>,
+    active_port: Option<String>,
+    card: Option<u32>,
+}
+
+impl From<Sink> for Device {
+    fn from(sink: Sink) -> Self {
+        Self {
+            name: sink.name,
+            description: sink.description,
+            volume: sink.volume,
+            muted: sink.muted,
+            active: sink.active,
+            ports: sink.ports,
+            active_port: sink.active_port,
+            card: sink.card,
+        }
+    }
+}
+
+impl From<Source> for Device {
+    fn from(source: Source) -> Self {
+        Self {
+            name: source.name,
+            description: source.description,
+            volume: source.volume,
+            muted: source.muted,
+            active: source.active,
+            ports: source.ports,
+            active_port: source.active_port,
+            card: source.card,
+        }
+    }
+}
+
+/// A sink input or source output, depending on the module [`Mode`].
+#[derive(Debug, Clone)]
+pub struct Stream {
+    index: u32,
+    name: String,
+    volume: f64,
+    muted: bool,
+    can_set_volume: bool,
+}
+
+impl From<SinkInput> for Stream {
+    fn from(input: SinkInput) -> Self {
+        Self {
+            index: input.index,
+            name: input.name,
+            volume: input.volume,
+            muted: input.muted,
+            can_set_volume: input.can_set_volume,
+        }
+    }
+}
+
+impl From<SourceOutput> for Stream {
+    fn from(output: SourceOutput) -> Self {
+        Self {
+            index: output.index,
+            name: output.name,
+            volume: output.volume,
+            muted: output.muted,
+            can_set_volume: output.can_set_volume,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    AddDevice(Device),
+    UpdateDevice(Device),
+    RemoveDevice(String),
+
+    AddStream(Stream),
+    UpdateStream(Stream),
+    RemoveStream(u32),
+
+    /// Whether any application is recording from a source.
+    MicInUse(bool),
+
+    AddCard(Card),
+    UpdateCard(Card),
+    RemoveCard(u32),
+}
+
+impl Mode {
+    /// Converts a client event into a module event,
+    /// if it concerns the devices controlled in this mode.
+    fn map_event(self, event: volume::Event) -> Option<Event> {
+        use volume::Event as ClientEvent;
+
+        let event = match (self, event) {
+            (Self::Sink, ClientEvent::AddSink(sink)) => Event::AddDevice(sink.into()),
+            (Self::Sink, ClientEvent::UpdateSink(sink)) => Event::UpdateDevice(sink.into()),
+            (Self::Sink, ClientEvent::RemoveSink(name)) => Event::RemoveDevice(name),
+            (Self::Sink, ClientEvent::AddInput(input)) => Event::AddStream(input.into()),
+            (Self::Sink, ClientEvent::UpdateInput(input)) => Event::UpdateStream(input.into()),
+            (Self::Sink, ClientEvent::RemoveInput(index)) => Event::RemoveStream(index),
+
+            (Self::Source, ClientEvent::AddSource(source)) => Event::AddDevice(source.into()),
+            (Self::Source, ClientEvent::UpdateSource(source)) => Event::UpdateDevice(source.into()),
+            (Self::Source, ClientEvent::RemoveSource(name)) => Event::RemoveDevice(name),
+            (Self::Source, ClientEvent::AddOutput(output)) => Event::AddStream(output.into()),
+            (Self::Source, ClientEvent::UpdateOutput(output)) => Event::UpdateStream(output.into()),
+            (Self::Source, ClientEvent::RemoveOutput(index)) => Event::RemoveStream(index),
+
+            (_, ClientEvent::AddCard(card)) => Event::AddCard(card),
+            (_, ClientEvent::UpdateCard(card)) => Event::UpdateCard(card),
+            (_, ClientEvent::RemoveCard(index)) => Event::RemoveCard(index),
+
+            _ => return None,
+        };
+
+        Some(event)
+    }
+
+    fn devices(self, client: &volume::Client) -> Vec<Device> {
+        match self {
+            Self::Sink => lock!(client.sinks())
+                .iter()
+                .cloned()
+                .map(Device::from)
+                .collect(),
+            Self::Source => lock!(client.sources())
+                .iter()
+                .cloned()
+                .map(Device::from)
+                .collect(),
+        }
+    }
+
+    fn streams(self, client: &volume::Client) -> Vec<Stream> {
+        match self {
+            Self::Sink => lock!(client.sink_inputs())
+                .iter()
+                .cloned()
+                .map(Stream::from)
+                .collect(),
+            Self::Source => lock!(client.source_outputs())
+                .iter()
+                .cloned()
+                .map(Stream::from)
+                .collect(),
+        }
+    }
+
+    fn set_device_volume(self, client: &volume::Client, name: &str, volume: f64) {
+        match self {
+            Self::Sink => client.set_sink_volume(name, volume),
+            Self::Source => client.set_source_volume(name, volume),
+        }
+    }
+
+    fn set_device_muted(self, client: &volume::Client, name: &str, muted: bool) {
+        match self {
+            Self::Sink => client.set_sink_muted(name, muted),
+            Self::Source => client.set_source_muted(name, muted),
+        }
+    }
+
+    fn set_device_port(self, client: &volume::Client, name: &str, port: &str) {
+        match self {
+            Self::Sink => client.set_sink_port(name, port),
+            Self::Source => client.set_source_port(name, port),
+        }
+    }
+
+    fn set_stream_volume(self, client: &volume::Client, index: u32, volume: f64) {
+        match self {
+            Self::Sink => client.set_input_volume(index, volume),
+            Self::Source => client.set_output_volume(index, volume),
+        }
+    }
+
+    fn set_stream_muted(self, client: &volume::Client, index: u32, muted: bool) {
+        match self {
+            Self::Sink => client.set_input_muted(index, muted),
+            Self::Source => client.set_output_muted(index, muted),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Update {
     SinkChange(String),
-    SinkVolume(String, f64),
-    SinkMute(String, bool),
-
-    InputVolume(u32, f64),
-    InputMute(u32, bool),
+    SourceChange(String),
+
+    DeviceVolume(String, f64),
+    DeviceMute(String, bool),
+    /// Switches the named device to the port with the given name.
+    DevicePort(String, String),
+    /// Switches the card of the named device to the profile with the given name.
+    DeviceProfile(String, String),
+
+    StreamVolume(u32, f64),
+    /// Changes the volume of a stream by a relative amount.
+    StreamVolumeBy(u32, f64),
+    StreamMute(u32, bool),
+
+    /// Sets the volume of the default device.
+    ActiveVolume(f64),
+    /// Changes the volume of the default device by a relative amount.
+    ActiveVolumeBy(f64),
+    /// Changes the volume of the default device
+    /// by this many multiples of the scroll step.
+    ActiveVolumeSteps(f64),
+    /// Sets the mute state of the default device,
+    /// or toggles it if `None`.
+    ActiveMute(Option<bool>),
 }
 
 impl Module<Button> for VolumeModule {
     type SendMessage = Event;
     type ReceiveMessage = Update;
 
-    module_impl!("volume");
+    fn name() -> &'static str {
+        "volume"
+    }
+
+    fn take_common(&mut self) -> CommonConfig {
+        let common = self.common.take().unwrap_or_default();
+
+        // the common options would otherwise run alongside the built-in handlers
+        self.mute_on_middle_click &= common.on_click_middle.is_none();
+        self.scroll_widget = common.on_scroll_up.is_none() && common.on_scroll_down.is_none();
+
+        common
+    }
+
+    #[cfg(feature = "ipc")]
+    fn ipc_action(action: &str, args: &[String]) -> color_eyre::Result<Self::ReceiveMessage> {
+        use crate::modules::actions::{parse_arg, unknown_action};
+
+        // without a step, the module's scroll step is used
+        let step = |direction: f64| {
+            if args.is_empty() {
+                Ok(Update::ActiveVolumeSteps(direction))
+            } else {
+                parse_arg(args, 0, "step").map(|step: f64| Update::ActiveVolumeBy(step * direction))
+            }
+        };
+
+        match action {
+            "set" => parse_arg(args, 0, "percent").map(Update::ActiveVolume),
+            "up" => step(1.0),
+            "down" => step(-1.0),
+            "mute" => Ok(Update::ActiveMute(Some(true))),
+            "unmute" => Ok(Update::ActiveMute(Some(false))),
+            "toggle-mute" => Ok(Update::ActiveMute(None)),
+            "sink" => parse_arg(args, 0, "name").map(Update::SinkChange),
+            "source" => parse_arg(args, 0, "name").map(Update::SourceChange),
+            _ => Err(unknown_action(
+                action,
+                &[
+                    "set",
+                    "up",
+                    "down",
+                    "mute",
+                    "unmute",
+                    "toggle-mute",
+                    "sink",
+                    "source",
+                ],
+            )),
+        }
+    }
 
     fn spawn_controller(
         &self,
@@ -146,58 +487,155 @@ impl Module<Button> for VolumeModule {
         <Self as Module<Button>>::SendMessage: Clone,
     {
         let client = context.client::<volume::Client>();
+        let mode = self.mode;
 
         {
             let client = client.clone();
             let mut rx = client.subscribe();
             let tx = context.tx.clone();
 
-            spawn(async move {
+            context.spawn(async move {
                 // init
-                let sinks = {
-                    let sinks = client.sinks();
-                    let sinks = lock!(sinks);
-                    sinks.iter().cloned().collect::<Vec<_>>()
-                };
-
-                trace!("initial syncs: {sinks:?}");
+                let devices = mode.devices(&client);
+                trace!("initial devices: {devices:?}");
 
-                let inputs = {
-                    let inputs = client.sink_inputs();
-                    let inputs = lock!(inputs);
-                    inputs.iter().cloned().collect::<Vec<_>>()
-                };
+                let streams = mode.streams(&client);
+                trace!("initial streams: {streams:?}");
 
-                trace!("initial inputs: {inputs:?}");
+                for device in devices {
+                    send_async!(tx, ModuleUpdateEvent::Update(Event::AddDevice(device)));
+                }
 
-                for sink in sinks {
-                    send_async!(tx, ModuleUpdateEvent::Update(Event::AddSink(sink)));
+                for stream in streams {
+                    send_async!(tx, ModuleUpdateEvent::Update(Event::AddStream(stream)));
                 }
 
-                for input in inputs {
-                    send_async!(
-                        tx,
-                        ModuleUpdateEvent::Update(Event::AddInput(input.clone()))
-                    );
+                let cards = lock!(client.cards()).clone();
+                trace!("initial cards: {cards:?}");
+
+                for card in cards {
+                    send_async!(tx, ModuleUpdateEvent::Update(Event::AddCard(card)));
                 }
 
+                let mic_in_use = client.mic_in_use();
+                send_async!(tx, ModuleUpdateEvent::Update(Event::MicInUse(mic_in_use)));
+
                 // recv loop
                 while let Ok(event) = rx.recv().await {
                     trace!("received event: {event:?}");
-                    send_async!(tx, ModuleUpdateEvent::Update(event));
+
+                    if matches!(
+                        event,
+                        volume::Event::AddOutput(_)
+                            | volume::Event::UpdateOutput(_)
+                            | volume::Event::RemoveOutput(_)
+                            | volume::Event::AddSource(_)
+                            | volume::Event::RemoveSource(_)
+                    ) {
+                        let mic_in_use = client.mic_in_use();
+                        send_async!(tx, ModuleUpdateEvent::Update(Event::MicInUse(mic_in_use)));
+                    }
+
+                    if let Some(event) = mode.map_event(event) {
+                        send_async!(tx, ModuleUpdateEvent::Update(event));
+                    }
                 }
             });
         }
 
+        let max_volume = self.max_volume;
+
+        // scrolling may be disabled, in which case IPC still uses the default step
+        let step = if self.scroll_step > 0.0 {
+            self.scroll_step
+        } else {
+            default_scroll_step()
+        };
+
         // ui events
-        spawn(async move {
+        context.spawn(async move {
             while let Some(update) = rx.recv().await {
+                let active_device = || {
+                    mode.devices(&client)
+                        .into_iter()
+                        .find(|device| device.active)
+                };
+
+                let change_active_volume = |change: f64| {
+                    if let Some(device) = active_device() {
+                        let volume = (device.volume + change).clamp(0.0, max_volume);
+                        mode.set_device_volume(&client, &device.name, volume);
+                    }
+                };
+
                 match update {
                     Update::SinkChange(name) => client.set_default_sink(&name),
-                    Update::SinkVolume(name, volume) => client.set_sink_volume(&name, volume),
-                    Update::SinkMute(name, muted) => client.set_sink_muted(&name, muted),
-                    Update::InputVolume(index, volume) => client.set_input_volume(index, volume),
-                    Update::InputMute(index, muted) => client.set_input_muted(index, muted),
+                    Update::SourceChange(name) => client.set_default_source(&name),
+                    Update::DeviceVolume(name, volume) => {
+                        mode.set_device_volume(&client, &name, volume);
+                    }
+                    Update::DeviceMute(name, muted) => mode.set_device_muted(&client, &name, muted),
+                    // the popup selectors also fire when updated to match the current state
+                    Update::DevicePort(name, port) => {
+                        let device = mode
+                            .devices(&client)
+                            .into_iter()
+                            .find(|device| device.name == name);
+
+                        if device.is_some_and(|device| device.active_port.as_ref() != Some(&port)) {
+                            mode.set_device_port(&client, &name, &port);
+                        }
+                    }
+                    Update::DeviceProfile(name, profile) => {
+                        let card = mode
+                            .devices(&client)
+                            .into_iter()
+                            .find(|device| device.name == name)
+                            .and_then(|device| device.card)
+                            .and_then(|index| {
+                                lock!(client.cards())
+                                    .iter()
+                                    .find(|card| card.index == index)
+                                    .cloned()
+                            });
+
+                        if let Some(card) = card {
+                            if card.active_profile.as_ref() != Some(&profile) {
+                                client.set_card_profile(card.index, &profile);
+                            }
+                        }
+                    }
+                    Update::StreamVolume(index, volume) => {
+                        mode.set_stream_volume(&client, index, volume);
+                    }
+                    Update::StreamVolumeBy(index, change) => {
+                        let stream = mode
+                            .streams(&client)
+                            .into_iter()
+                            .find(|stream| stream.index == index && stream.can_set_volume);
+
+                        if let Some(stream) = stream {
+                            let volume = (stream.volume + change).clamp(0.0, max_volume);
+                            mode.set_stream_volume(&client, index, volume);
+                        }
+                    }
+                    Update::StreamMute(index, muted) => {
+                        mode.set_stream_muted(&client, index, muted);
+                    }
+                    Update::ActiveVolume(volume) => {
+                        if let Some(device) = active_device() {
+                            let volume = volume.clamp(0.0, max_volume);
+                            mode.set_device_volume(&client, &device.name, volume);
+                        }
+                    }
+                    Update::ActiveVolumeBy(change) => change_active_volume(change),
+                    Update::ActiveVolumeSteps(steps) => change_active_volume(steps * step),
+                    Update::ActiveMute(muted) => {
+                        if let Some(device) = active_device() {
+                            let muted = muted.unwrap_or(!device.muted);
+                            mode.set_device_muted(&client, &device.name, muted);
+                        }
+                    }
                 }
             }
         });
@@ -230,23 +668,68 @@ impl Module<Button> for VolumeModule {
             });
         }
 
+        if self.mute_on_middle_click {
+            let tx = context.controller_tx.clone();
+
+            button.connect_button_release_event(move |_, event| {
+                if event.button() == BUTTON_MIDDLE {
+                    try_send!(tx, Update::ActiveMute(None));
+                }
+
+                Propagation::Proceed
+            });
+        }
+
+        if self.scroll_widget && self.scroll_step > 0.0 {
+            let tx = context.controller_tx.clone();
+            let steps = ScrollSteps::new(self.scroll_step);
+
+            button.add_events(EventMask::SCROLL_MASK | EventMask::SMOOTH_SCROLL_MASK);
+            button.connect_scroll_event(move |_, event| {
+                if let Some(change) = steps.change(event) {
+                    try_send!(tx, Update::ActiveVolumeBy(change));
+                }
+
+                Propagation::Proceed
+            });
+        }
+
         {
             let rx = context.subscribe();
             let icons = self.icons.clone();
 
             let format = self.format.clone();
+            let mode = self.mode;
+            let button = button.clone();
+
+            let mut active = None;
+            let mut mic_in_use = false;
 
             glib_recv!(rx, event => {
                 match event {
-                    Event::AddSink(sink) | Event::UpdateSink(sink) if sink.active => {
-                        let label = format
-                            .replace("{icon}", if sink.muted { &icons.muted } else { icons.volume_icon(sink.volume) })
-                            .replace("{percentage}", &sink.volume.to_string())
-                            .replace("{name}", &sink.description);
-
-                        button_label.set_label_escaped(&label);
-                    },
-                    _ => {}
+                    Event::AddDevice(device) | Event::UpdateDevice(device) if device.active => {
+                        active = Some(device);
+                    }
+                    Event::MicInUse(in_use) => {
+                        mic_in_use = in_use;
+
+                        if in_use {
+                            button.add_class("mic-in-use");
+                        } else {
+                            button.remove_class("mic-in-use");
+                        }
+                    }
+                    _ => continue,
+                }
+
+                if let Some(device) = &active {
+                    let label = format
+                        .replace("{icon}", icons.volume_icon(mode, device.volume, device.muted))
+                        .replace("{percentage}", &device.volume.to_string())
+                        .replace("{name}", &device.description)
+                        .replace("{mic}", if mic_in_use { &icons.mic } else { "" });
+
+                    button_label.set_label_escaped(&label);
                 }
             });
         }
@@ -284,31 +767,66 @@ impl Module<Button> for VolumeModule {
         container.add(&sink_container);
         container.add(&input_container);
 
-        let sink_selector = ComboBoxText::new();
-        sink_selector.add_class("device-selector");
-
-        let renderer = sink_selector
-            .cells()
-            .first()
-            .expect("to exist")
-            .clone()
-            .downcast::<CellRendererText>()
-            .expect("to be valid cast");
+        let sink_selector = selector("device-selector");
 
-        renderer.set_width_chars(20);
-        renderer.set_ellipsize(EllipsizeMode::End);
+        let mode = self.mode;
 
         {
             let tx = tx.clone();
+            let device_change = match mode {
+                Mode::Sink => Update::SinkChange,
+                Mode::Source => Update::SourceChange,
+            };
+
             sink_selector.connect_changed(move |selector| {
                 if let Some(name) = selector.active_id() {
-                    try_send!(tx, Update::SinkChange(name.into()));
+                    try_send!(tx, device_change(name.into()));
                 }
             });
         }
 
         sink_container.add(&sink_selector);
 
+        let mut device_options = DeviceOptions {
+            port_selector: selector("port-selector"),
+            profile_selector: selector("profile-selector"),
+            ports: vec![],
+            profiles: vec![],
+        };
+
+        {
+            let tx = tx.clone();
+            let device_selector = sink_selector.clone();
+
+            device_options
+                .port_selector
+                .connect_changed(move |selector| {
+                    if let (Some(device), Some(port)) =
+                        (device_selector.active_id(), selector.active_id())
+                    {
+                        try_send!(tx, Update::DevicePort(device.into(), port.into()));
+                    }
+                });
+        }
+
+        {
+            let tx = tx.clone();
+            let device_selector = sink_selector.clone();
+
+            device_options
+                .profile_selector
+                .connect_changed(move |selector| {
+                    if let (Some(device), Some(profile)) =
+                        (device_selector.active_id(), selector.active_id())
+                    {
+                        try_send!(tx, Update::DeviceProfile(device.into(), profile.into()));
+                    }
+                });
+        }
+
+        sink_container.add(&device_options.port_selector);
+        sink_container.add(&device_options.profile_selector);
+
         let slider = Scale::builder()
             .orientation(Orientation::Vertical)
             .height_request(100)
@@ -329,13 +847,27 @@ impl Module<Button> for VolumeModule {
                 if let Some(sink) = selector.active_id() {
                     // GTK will send values outside min/max range
                     let val = scale.value().clamp(0.0, self.max_volume);
-                    try_send!(tx, Update::SinkVolume(sink.into(), val));
+                    try_send!(tx, Update::DeviceVolume(sink.into(), val));
                 }
 
                 Propagation::Proceed
             });
         }
 
+        if self.scroll_step > 0.0 {
+            let tx = tx.clone();
+            let steps = ScrollSteps::new(self.scroll_step);
+
+            slider.connect_scroll_event(move |_, event| {
+                if let Some(change) = steps.change(event) {
+                    try_send!(tx, Update::ActiveVolumeBy(change));
+                }
+
+                // stop the slider from applying its own scroll step
+                Propagation::Stop
+            });
+        }
+
         let btn_mute = ToggleButton::new();
         btn_mute.add_class("btn-mute");
         sink_container.add(&btn_mute);
@@ -347,7 +879,7 @@ impl Module<Button> for VolumeModule {
             btn_mute.connect_toggled(move |btn| {
                 if let Some(sink) = selector.active_id() {
                     let muted = btn.is_active();
-                    try_send!(tx, Update::SinkMute(sink.into(), muted));
+                    try_send!(tx, Update::DeviceMute(sink.into(), muted));
                 }
             });
         }
@@ -359,11 +891,22 @@ impl Module<Button> for VolumeModule {
         {
             let input_container = input_container.clone();
 
-            let mut sinks = vec![];
+            let mut sinks: Vec<Device> = vec![];
+            let mut cards: Vec<Card> = vec![];
 
             glib_recv!(rx, event => {
+                let refresh_options = matches!(
+                    event,
+                    Event::AddDevice(_)
+                        | Event::UpdateDevice(_)
+                        | Event::RemoveDevice(_)
+                        | Event::AddCard(_)
+                        | Event::UpdateCard(_)
+                        | Event::RemoveCard(_)
+                );
+
                 match event {
-                    Event::AddSink(info) => {
+                    Event::AddDevice(info) => {
                         sink_selector.append(Some(&info.name), &info.description);
 
                         if info.active {
@@ -371,34 +914,39 @@ impl Module<Button> for VolumeModule {
                             slider.set_value(info.volume);
 
                             btn_mute.set_active(info.muted);
-                            btn_mute.set_label(if info.muted { &self.icons.muted } else { self.icons.volume_icon(info.volume) });
+                            btn_mute.set_label(self.icons.volume_icon(mode, info.volume, info.muted));
                         }
 
                         sinks.push(info);
                     }
-                    Event::UpdateSink(info) => {
-                        if info.active {
-                            if let Some(pos) = sinks.iter().position(|s| s.name == info.name) {
+                    Event::UpdateDevice(info) => {
+                        if let Some(pos) = sinks.iter().position(|s| s.name == info.name) {
+                            if info.active {
                                 sink_selector.set_active(Some(pos as u32));
                                 slider.set_value(info.volume);
 
                                 btn_mute.set_active(info.muted);
-                                btn_mute.set_label(if info.muted { &self.icons.muted } else { self.icons.volume_icon(info.volume) });
+                                btn_mute.set_label(self.icons.volume_icon(mode, info.volume, info.muted));
                             }
+
+                            sinks[pos] = info;
                         }
                     }
-                    Event::RemoveSink(name) => {
+                    Event::RemoveDevice(name) => {
                         if let Some(pos) = sinks.iter().position(|s| s.name == name) {
                             ComboBoxTextExt::remove(&sink_selector, pos as i32);
                             sinks.remove(pos);
                         }
                     }
 
-                    Event::AddInput(info) => {
+                    Event::AddStream(info) => {
                         let index = info.index;
 
+                        let row = EventBox::new();
+
                         let item_container = gtk::Box::new(Orientation::Vertical, 0);
                         item_container.add_class("app-box");
+                        row.add(&item_container);
 
                         let label = Label::new(Some(&info.name));
                         label.add_class("title");
@@ -413,7 +961,7 @@ impl Module<Button> for VolumeModule {
                             slider.connect_button_release_event(move |scale, _| {
                                 // GTK will send values outside min/max range
                                 let val = scale.value().clamp(0.0, self.max_volume);
-                                try_send!(tx, Update::InputVolume(index, val));
+                                try_send!(tx, Update::StreamVolume(index, val));
 
                                 Propagation::Proceed
                             });
@@ -423,43 +971,79 @@ impl Module<Button> for VolumeModule {
                         btn_mute.add_class("btn-mute");
 
                         btn_mute.set_active(info.muted);
-                        btn_mute.set_label(if info.muted { &self.icons.muted } else { self.icons.volume_icon(info.volume) });
+                        btn_mute.set_label(self.icons.volume_icon(mode, info.volume, info.muted));
 
                         {
                             let tx = tx.clone();
                             btn_mute.connect_toggled(move |btn| {
                                 let muted = btn.is_active();
-                                try_send!(tx, Update::InputMute(index, muted));
+                                try_send!(tx, Update::StreamMute(index, muted));
                             });
                         }
 
+                        if self.scroll_step > 0.0 {
+                            let steps = ScrollSteps::new(self.scroll_step);
+                            let tx = tx.clone();
+
+                            let on_scroll = move |event: &EventScroll| {
+                                if let Some(change) = steps.change(event) {
+                                    try_send!(tx, Update::StreamVolumeBy(index, change));
+                                }
+
+                                // stop the slider from applying its own scroll step
+                                Propagation::Stop
+                            };
+
+                            row.add_events(EventMask::SCROLL_MASK | EventMask::SMOOTH_SCROLL_MASK);
+                            row.connect_scroll_event({
+                                let on_scroll = on_scroll.clone();
+                                move |_, event| on_scroll(event)
+                            });
+                            slider.connect_scroll_event(move |_, event| on_scroll(event));
+                        }
+
                         item_container.add(&label);
                         item_container.add(&slider);
                         item_container.add(&btn_mute);
-                        item_container.show_all();
+                        row.show_all();
 
-                        input_container.add(&item_container);
+                        input_container.add(&row);
 
                         inputs.insert(info.index, InputUi {
-                            container: item_container,
+                            container: row,
                             label,
                             slider,
                             btn_mute
                         });
                     }
-                    Event::UpdateInput(info) => {
+                    Event::UpdateStream(info) => {
                         if let Some(ui) = inputs.get(&info.index) {
                             ui.label.set_label(&info.name);
                             ui.slider.set_value(info.volume);
                             ui.slider.set_sensitive(info.can_set_volume);
-                            ui.btn_mute.set_label(if info.muted { &self.icons.muted } else { self.icons.volume_icon(info.volume) });
+                            ui.btn_mute.set_label(self.icons.volume_icon(mode, info.volume, info.muted));
                         }
                     }
-                    Event::RemoveInput(index) => {
+                    Event::RemoveStream(index) => {
                         if let Some(ui) = inputs.remove(&index) {
                             input_container.remove(&ui.container);
                         }
                     }
+                    Event::MicInUse(_) => {}
+
+                    Event::AddCard(card) => cards.push(card),
+                    Event::UpdateCard(card) => {
+                        if let Some(pos) = cards.iter().position(|c| c.index == card.index) {
+                            cards[pos] = card;
+                        }
+                    }
+                    Event::RemoveCard(index) => cards.retain(|card| card.index != index),
+                }
+
+                if refresh_options {
+                    let active = sink_selector.active_id();
+                    let device = sinks.iter().find(|s| Some(s.name.as_str()) == active.as_deref());
+                    device_options.update(device, &cards);
                 }
             });
         }
@@ -468,8 +1052,144 @@ impl Module<Button> for VolumeModule {
     }
 }
 
+/// Creates a dropdown with entries ellipsized to a fixed width.
+fn selector(class: &str) -> ComboBoxText {
+    let selector = ComboBoxText::new();
+    selector.add_class(class);
+
+    let renderer = selector
+        .cells()
+        .first()
+        .expect("to exist")
+        .clone()
+        .downcast::<CellRendererText>()
+        .expect("to be valid cast");
+
+    renderer.set_width_chars(20);
+    renderer.set_ellipsize(EllipsizeMode::End);
+
+    selector
+}
+
+/// Port and card profile selectors for the active device.
+struct DeviceOptions {
+    port_selector: ComboBoxText,
+    profile_selector: ComboBoxText,
+
+    /// IDs and labels of the current port entries.
+    ports: Vec<(String, String)>,
+    /// IDs and labels of the current profile entries.
+    profiles: Vec<(String, String)>,
+}
+
+impl DeviceOptions {
+    fn update(&mut self, device: Option<&Device>, cards: &[Card]) {
+        let active_port = device.and_then(|device| device.active_port.as_deref());
+
+        let ports = device
+            .map(|device| {
+                device
+                    .ports
+                    .iter()
+                    .filter(|port| port.available || Some(port.name.as_str()) == active_port)
+                    .map(|port| (port.name.clone(), port.description.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        set_options(&self.port_selector, &mut self.ports, ports, active_port);
+
+        let card = device
+            .and_then(|device| device.card)
+            .and_then(|index| cards.iter().find(|card| card.index == index));
+
+        let active_profile = card.and_then(|card| card.active_profile.as_deref());
+
+        let profiles = card
+            .map(|card| {
+                card.profiles
+                    .iter()
+                    .filter(|profile| {
+                        profile.available || Some(profile.name.as_str()) == active_profile
+                    })
+                    .map(|profile| (profile.name.clone(), profile.description.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        set_options(
+            &self.profile_selector,
+            &mut self.profiles,
+            profiles,
+            active_profile,
+        );
+    }
+}
+
+/// Replaces the selector entries if they have changed,
+/// and selects the entry with the `active` ID.
+/// The selector is hidden when there is nothing to choose between.
+fn set_options(
+    selector: &ComboBoxText,
+    current: &mut Vec<(String, String)>,
+    options: Vec<(String, String)>,
+    active: Option<&str>,
+) {
+    if *current != options {
+        selector.remove_all();
+
+        for (id, label) in &options {
+            selector.append(Some(id), label);
+        }
+
+        *current = options;
+    }
+
+    selector.set_active_id(active);
+    selector.set_visible(current.len() > 1);
+}
+
+/// Converts scroll events into volume changes,
+/// where scrolling up increases the volume by `step`.
+///
+/// Smooth scroll deltas are summed,
+/// so that a step is applied per whole unit scrolled rather than per event.
+#[derive(Debug, Clone)]
+struct ScrollSteps {
+    step: f64,
+    delta: Rc<Cell<f64>>,
+}
+
+impl ScrollSteps {
+    fn new(step: f64) -> Self {
+        Self {
+            step,
+            delta: Rc::new(Cell::new(0.0)),
+        }
+    }
+
+    fn change(&self, event: &EventScroll) -> Option<f64> {
+        match event.direction() {
+            ScrollDirection::Up => Some(self.step),
+            ScrollDirection::Down => Some(-self.step),
+            ScrollDirection::Smooth => {
+                let delta = self.delta.get() + event.scroll_deltas().unwrap_or_default().1;
+                let steps = delta.trunc();
+                self.delta.set(delta - steps);
+
+                if delta.abs() < 1.0 {
+                    None
+                } else {
+                    Some(-steps * self.step)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
 struct InputUi {
-    container: gtk::Box,
+    container: EventBox,
     label: Label,
     slider: Scale,
     btn_mute: ToggleButton,
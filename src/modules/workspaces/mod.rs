@@ -8,7 +8,7 @@ use crate::config::{CommonConfig, LayoutConfig};
 use crate::modules::workspaces::button_map::{ButtonMap, Identifier};
 use crate::modules::workspaces::open_state::OpenState;
 use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
-use crate::{glib_recv, module_impl, send_async, spawn};
+use crate::{arc_mut, glib_recv, lock, module_impl, send_async};
 use color_eyre::{Report, Result};
 use gtk::IconTheme;
 use gtk::prelude::*;
@@ -43,7 +43,16 @@ pub enum SortOrder {
     Name,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// A workspace to focus.
+#[derive(Debug, Clone)]
+pub enum FocusTarget {
+    /// The ID of the workspace, as supplied by the compositor.
+    Id(i64),
+    /// The real name of the workspace, as supplied by the compositor.
+    Name(String),
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[serde(untagged)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum Favorites {
@@ -57,7 +66,7 @@ impl Default for Favorites {
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct WorkspacesModule {
     /// Map of actual workspace names to custom names.
@@ -146,7 +155,7 @@ pub struct WorkspaceItemContext {
     name_map: HashMap<String, String>,
     icon_theme: IconTheme,
     icon_size: i32,
-    tx: mpsc::Sender<i64>,
+    tx: mpsc::Sender<FocusTarget>,
 }
 
 /// Re-orders the container children alphabetically,
@@ -186,12 +195,44 @@ fn reorder_workspaces(container: &gtk::Box, sort_order: SortOrder) {
     }
 }
 
+/// Keeps the map of workspace names to IDs in sync with `update`.
+fn update_workspace_ids(ids: &mut HashMap<String, i64>, update: &WorkspaceUpdate) {
+    match update {
+        WorkspaceUpdate::Init(workspaces) => {
+            ids.clear();
+            ids.extend(workspaces.iter().map(|w| (w.name.clone(), w.id)));
+        }
+        WorkspaceUpdate::Add(workspace)
+        | WorkspaceUpdate::Move(workspace)
+        | WorkspaceUpdate::Focus { new: workspace, .. } => {
+            ids.insert(workspace.name.clone(), workspace.id);
+        }
+        WorkspaceUpdate::Remove(id) => ids.retain(|_, existing| existing != id),
+        WorkspaceUpdate::Rename { id, name } => {
+            ids.retain(|_, existing| existing != id);
+            ids.insert(name.clone(), *id);
+        }
+        WorkspaceUpdate::Urgent { .. } | WorkspaceUpdate::Unknown => {}
+    }
+}
+
 impl Module<gtk::Box> for WorkspacesModule {
     type SendMessage = WorkspaceUpdate;
-    type ReceiveMessage = i64;
+    type ReceiveMessage = FocusTarget;
 
     module_impl!("workspaces");
 
+    #[cfg(feature = "ipc")]
+    fn ipc_action(action: &str, args: &[String]) -> Result<Self::ReceiveMessage> {
+        use crate::modules::actions::{parse_arg, unknown_action};
+
+        match action {
+            "focus" => parse_arg(args, 0, "name").map(FocusTarget::Name),
+            "focus-id" => parse_arg(args, 0, "id").map(FocusTarget::Id),
+            _ => Err(unknown_action(action, &["focus", "focus-id"])),
+        }
+    }
+
     fn spawn_controller(
         &self,
         _info: &ModuleInfo,
@@ -200,26 +241,48 @@ impl Module<gtk::Box> for WorkspacesModule {
     ) -> Result<()> {
         let tx = context.tx.clone();
         let client = context.ironbar.clients.borrow_mut().workspaces()?;
+
+        // workspace names mapped to their IDs, used to resolve focus requests by name
+        let workspace_ids = arc_mut!(HashMap::new());
+
         // Subscribe & send events
-        spawn(async move {
-            let mut srx = client.subscribe();
+        {
+            let workspace_ids = workspace_ids.clone();
 
-            trace!("Set up workspace subscription");
+            context.spawn(async move {
+                let mut srx = client.subscribe();
 
-            while let Ok(payload) = srx.recv().await {
-                debug!("Received update: {payload:?}");
-                send_async!(tx, ModuleUpdateEvent::Update(payload));
-            }
-        });
+                trace!("Set up workspace subscription");
+
+                while let Ok(payload) = srx.recv().await {
+                    debug!("Received update: {payload:?}");
+                    update_workspace_ids(&mut lock!(workspace_ids), &payload);
+                    send_async!(tx, ModuleUpdateEvent::Update(payload));
+                }
+            });
+        }
 
         let client = context.try_client::<dyn WorkspaceClient>()?;
 
         // Change workspace focus
-        spawn(async move {
+        context.spawn(async move {
             trace!("Setting up UI event handler");
 
-            while let Some(id) = rx.recv().await {
-                client.focus(id);
+            while let Some(target) = rx.recv().await {
+                let id = match target {
+                    FocusTarget::Id(id) => Some(id),
+                    FocusTarget::Name(name) => {
+                        let id = lock!(workspace_ids).get(&name).copied();
+                        if id.is_none() {
+                            warn!("No workspace named '{name}'");
+                        }
+                        id
+                    }
+                };
+
+                if let Some(id) = id {
+                    client.focus(id);
+                }
             }
 
             Ok::<(), Report>(())
@@ -27,6 +27,11 @@ pub struct Popup {
     pos: BarPosition,
     current_widget: Rc<RefCell<Option<(usize, usize)>>>,
     output_size: Rc<RefCell<(i32, i32)>>,
+    #[cfg(feature = "ipc")]
+    output_name: String,
+    /// The name of the module whose content was last shown.
+    #[cfg(feature = "ipc")]
+    shown_name: Rc<RefCell<Option<String>>>,
 }
 
 impl Popup {
@@ -111,6 +116,23 @@ impl Popup {
 
         let output_size = rc_mut!(output_size);
 
+        #[cfg(feature = "ipc")]
+        let shown_name = rc_mut!(None);
+
+        #[cfg(feature = "ipc")]
+        {
+            let output_name = module_info.output_name.to_string();
+            let shown_name = shown_name.clone();
+
+            win.connect_hide(move |_| {
+                crate::ipc::events::emit(crate::ipc::events::Event::Popup {
+                    monitor: output_name.clone(),
+                    module: shown_name.borrow_mut().take(),
+                    visible: false,
+                });
+            });
+        }
+
         // respond to resolution changes
         {
             let output_size = output_size.clone();
@@ -137,6 +159,10 @@ impl Popup {
             pos,
             current_widget: rc_mut!(None),
             output_size,
+            #[cfg(feature = "ipc")]
+            output_name: module_info.output_name.to_string(),
+            #[cfg(feature = "ipc")]
+            shown_name,
         }
     }
 
@@ -185,13 +211,38 @@ impl Popup {
             .insert(key, PopupCacheValue { name, content });
     }
 
+    /// Removes previously registered content,
+    /// hiding the popup if the content is currently open.
+    pub fn unregister_content(&self, key: usize) {
+        let Some(PopupCacheValue { content, .. }) = self.container_cache.borrow_mut().remove(&key)
+        else {
+            return;
+        };
+
+        debug!("Unregistered popup content for #{}", key);
+
+        if self.current_widget() == Some(key) {
+            self.hide();
+        }
+
+        self.button_cache
+            .borrow_mut()
+            .retain(|button| !content.buttons.contains(button));
+    }
+
     pub fn show(&self, widget_id: usize, button_id: usize) {
         self.clear_window();
 
-        if let Some(PopupCacheValue { content, .. }) = self.container_cache.borrow().get(&widget_id)
+        if let Some(PopupCacheValue { name, content }) =
+            self.container_cache.borrow().get(&widget_id)
         {
             *self.current_widget.borrow_mut() = Some((widget_id, button_id));
 
+            #[cfg(feature = "ipc")]
+            self.emit_shown(name);
+            #[cfg(not(feature = "ipc"))]
+            let _ = name;
+
             content.container.add_class("popup");
             self.window.add(&content.container);
 
@@ -210,8 +261,14 @@ impl Popup {
     pub fn show_at(&self, widget_id: usize, geometry: WidgetGeometry) {
         self.clear_window();
 
-        if let Some(PopupCacheValue { content, .. }) = self.container_cache.borrow().get(&widget_id)
+        if let Some(PopupCacheValue { name, content }) =
+            self.container_cache.borrow().get(&widget_id)
         {
+            #[cfg(feature = "ipc")]
+            self.emit_shown(name);
+            #[cfg(not(feature = "ipc"))]
+            let _ = name;
+
             content.container.add_class("popup");
             self.window.add(&content.container);
 
@@ -225,6 +282,20 @@ impl Popup {
         }
     }
 
+    /// Sends an IPC event for the popup opening with the named module's content.
+    #[cfg(feature = "ipc")]
+    fn emit_shown(&self, name: &str) {
+        use crate::ipc::events::{self, Event};
+
+        self.shown_name.replace(Some(name.to_string()));
+
+        events::emit(Event::Popup {
+            monitor: self.output_name.clone(),
+            module: Some(name.to_string()),
+            visible: true,
+        });
+    }
+
     fn set_position(
         buttons: &[Button],
         button_id: usize,
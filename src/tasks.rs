@@ -0,0 +1,81 @@
+use crate::{arc_mut, lock, spawn};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::task::{AbortHandle, JoinHandle};
+
+#[derive(Debug, Default)]
+struct Tasks {
+    handles: Vec<AbortHandle>,
+    children: Vec<TaskScope>,
+    aborted: bool,
+}
+
+/// The Tokio tasks belonging to a module,
+/// which are stopped together when the module is removed.
+///
+/// Modules spawn long-running tasks through the scope on their
+/// [`WidgetContext`](crate::modules::WidgetContext).
+/// Tasks spawned with the global [`spawn`] are never stopped,
+/// so should only be used for work which outlives a module,
+/// such as the tasks started by clients.
+#[derive(Debug, Clone)]
+pub struct TaskScope(Arc<Mutex<Tasks>>);
+
+impl Default for TaskScope {
+    fn default() -> Self {
+        Self(arc_mut!(Tasks::default()))
+    }
+}
+
+impl TaskScope {
+    /// Spawns a task on the Tokio runtime,
+    /// which is aborted along with the scope.
+    pub fn spawn<F>(&self, f: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let handle = spawn(f);
+
+        let mut tasks = lock!(self.0);
+        if tasks.aborted {
+            handle.abort();
+        } else {
+            tasks.handles.retain(|task| !task.is_finished());
+            tasks.handles.push(handle.abort_handle());
+        }
+
+        handle
+    }
+
+    /// Adds a nested scope, such as that of a module inside a custom module,
+    /// which is aborted along with this one.
+    pub fn add_child(&self, child: TaskScope) {
+        let mut tasks = lock!(self.0);
+        if tasks.aborted {
+            child.abort();
+        } else {
+            tasks.children.push(child);
+        }
+    }
+
+    /// Aborts every task in the scope and its children.
+    pub fn abort(&self) {
+        let (handles, children) = {
+            let mut tasks = lock!(self.0);
+            tasks.aborted = true;
+            (
+                std::mem::take(&mut tasks.handles),
+                std::mem::take(&mut tasks.children),
+            )
+        };
+
+        for task in handles {
+            task.abort();
+        }
+
+        for child in children {
+            child.abort();
+        }
+    }
+}